@@ -15,7 +15,7 @@ import {
   TransactionInstruction,
   sendAndConfirmTransaction,
 } from '@solana/web3.js';
-import {u64, publicKey} from '@solana/buffer-layout-utils';
+import {u64, u128, publicKey} from '@solana/buffer-layout-utils';
 import {loadAccount} from './util/account.js';
 
 export const SOLANA_PSM_PROGRAM_ID: PublicKey = new PublicKey(
@@ -26,7 +26,7 @@ export const OLD_TOKEN_SWAP_PROGRAM_ID: PublicKey = new PublicKey(
   'SwaPpA9LAaLfeLi3a68M4DjnLqgtticKg6CnyNwgAC8',
 );
 
-export interface RawTokenSwap {
+export interface RawTokenSwapV1 {
   version: number;
   isInitialized: boolean;
   bumpSeed: number;
@@ -49,6 +49,57 @@ export interface RawTokenSwap {
   curveParameters: Uint8Array;
 }
 
+export interface RawTokenSwap extends RawTokenSwapV1 {
+  isPaused: boolean;
+  maxStaleness: bigint;
+  pendingSuperAdmin: PublicKey;
+  superAdminProposer: PublicKey;
+  timelockDelay: bigint;
+  maxSsrDelta: bigint;
+  minRatesUpdateInterval: bigint;
+  lastRatesUpdate: bigint;
+  aToBDisabled: boolean;
+  bToADisabled: boolean;
+  liquidityAllowlisted: boolean;
+  swapsAllowlisted: boolean;
+  aToBMaxAmount: bigint;
+  aToBSlope: bigint;
+  aToBLastAmount: bigint;
+  aToBLastUpdated: bigint;
+  bToAMaxAmount: bigint;
+  bToASlope: bigint;
+  bToALastAmount: bigint;
+  bToALastUpdated: bigint;
+  maxTvl: bigint;
+  bridgeReceiver: PublicKey;
+  poolConfigReserved: Uint8Array;
+}
+
+// Swaps created before the pool controls, the version byte followed by the
+// 355 bytes of SwapV1, until they are migrated with MigrateSwap
+export const TokenSwapV1Layout = struct<RawTokenSwapV1>([
+  u8('version'),
+  u8('isInitialized'),
+  u8('bumpSeed'),
+  publicKey('poolTokenProgramId'),
+  publicKey('tokenAccountA'),
+  publicKey('tokenAccountB'),
+  publicKey('tokenPool'),
+  publicKey('mintA'),
+  publicKey('mintB'),
+  publicKey('feeAccount'),
+  u64('tradeFeeNumerator'),
+  u64('tradeFeeDenominator'),
+  u64('ownerTradeFeeNumerator'),
+  u64('ownerTradeFeeDenominator'),
+  u64('ownerWithdrawFeeNumerator'),
+  u64('ownerWithdrawFeeDenominator'),
+  u64('hostFeeNumerator'),
+  u64('hostFeeDenominator'),
+  u8('curveType'),
+  blob(64, 'curveParameters'),
+]);
+
 export const TokenSwapLayout = struct<RawTokenSwap>([
   u8('version'),
   u8('isInitialized'),
@@ -70,8 +121,44 @@ export const TokenSwapLayout = struct<RawTokenSwap>([
   u64('hostFeeDenominator'),
   u8('curveType'),
   blob(64, 'curveParameters'),
+  u8('isPaused'),
+  u64('maxStaleness'),
+  publicKey('pendingSuperAdmin'),
+  publicKey('superAdminProposer'),
+  u64('timelockDelay'),
+  u128('maxSsrDelta'),
+  u64('minRatesUpdateInterval'),
+  u64('lastRatesUpdate'),
+  u8('aToBDisabled'),
+  u8('bToADisabled'),
+  u8('liquidityAllowlisted'),
+  u8('swapsAllowlisted'),
+  u64('aToBMaxAmount'),
+  u64('aToBSlope'),
+  u64('aToBLastAmount'),
+  u64('aToBLastUpdated'),
+  u64('bToAMaxAmount'),
+  u64('bToASlope'),
+  u64('bToALastAmount'),
+  u64('bToALastUpdated'),
+  u128('maxTvl'),
+  publicKey('bridgeReceiver'),
+  blob(27, 'poolConfigReserved'),
 ]);
 
+/**
+ * Decodes a swap account of either version, chosen by its size. The pool
+ * controls of a V1 swap are left undefined.
+ */
+export function decodeTokenSwap(
+  data: Buffer,
+): RawTokenSwapV1 & Partial<RawTokenSwap> {
+  if (data.length < TokenSwapLayout.span) {
+    return TokenSwapV1Layout.decode(data);
+  }
+  return TokenSwapLayout.decode(data);
+}
+
 export interface CreateInstruction {
   instruction: number;
   tradeFeeNumerator: bigint;
@@ -326,7 +413,7 @@ export class TokenSwap {
     payer: Keypair,
   ): Promise<TokenSwap> {
     const data = await loadAccount(connection, address, programId);
-    const tokenSwapData = TokenSwapLayout.decode(data);
+    const tokenSwapData = decodeTokenSwap(data);
     if (!tokenSwapData.isInitialized) {
       throw new Error(`Invalid token swap state`);
     }
@@ -588,7 +675,7 @@ export class TokenSwap {
     );
 
     const keys = [
-      {pubkey: tokenSwap, isSigner: false, isWritable: false},
+      {pubkey: tokenSwap, isSigner: false, isWritable: true},
       {pubkey: authority, isSigner: false, isWritable: false},
       {pubkey: userTransferAuthority, isSigner: true, isWritable: false},
       {pubkey: userSource, isSigner: false, isWritable: true},
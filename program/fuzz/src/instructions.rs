@@ -472,6 +472,11 @@ fn get_total_token_b_amount(fuzz_instructions: &[FuzzInstruction]) -> u64 {
 }
 
 fn get_swap_curve(curve_type: CurveType) -> SwapCurve {
+    // pools with a rate provider price with its redemption rates
+    let curve_type = match curve_type {
+        CurveType::RateProviderCurve => CurveType::RedemptionRateCurve,
+        curve_type => curve_type,
+    };
     SwapCurve {
         curve_type,
         calculator: match curve_type {
@@ -482,7 +487,7 @@ fn get_swap_curve(curve_type: CurveType) -> SwapCurve {
             CurveType::Offset => Arc::new(OffsetCurve {
                 token_b_offset: 100_000_000_000,
             }),
-            CurveType::RedemptionRateCurve => Arc::new(RedemptionRateCurve {
+            CurveType::RedemptionRateCurve | CurveType::RateProviderCurve => Arc::new(RedemptionRateCurve {
                 max_ssr: 0,
                 ssr: RAY,
                 rho: 0,
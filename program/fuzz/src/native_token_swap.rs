@@ -250,6 +250,8 @@ impl NativeTokenSwap {
             &self.pool_fee_account.key,
             &self.token_a_mint_account.key,
             &self.token_b_mint_account.key,
+            None,
+            None,
             Some(&self.pool_token_account.key),
             instruction,
         )
@@ -319,6 +321,8 @@ impl NativeTokenSwap {
             &self.pool_fee_account.key,
             &self.token_b_mint_account.key,
             &self.token_a_mint_account.key,
+            None,
+            None,
             Some(&self.pool_token_account.key),
             instruction,
         )
@@ -413,6 +417,8 @@ impl NativeTokenSwap {
             &pool_account.key,
             &self.token_a_mint_account.key,
             &self.token_b_mint_account.key,
+            None,
+            None,
             instruction,
         )
         .unwrap();
@@ -488,6 +494,8 @@ impl NativeTokenSwap {
             &token_b_account.key,
             &self.token_a_mint_account.key,
             &self.token_b_mint_account.key,
+            None,
+            None,
             instruction,
         )
         .unwrap();
@@ -569,6 +577,8 @@ impl NativeTokenSwap {
             &self.pool_mint_account.key,
             &pool_account.key,
             &source_token_mint_account.key,
+            None,
+            None,
             instruction,
         )
         .unwrap();
@@ -646,6 +656,8 @@ impl NativeTokenSwap {
             &self.token_b_account.key,
             &destination_token_account.key,
             &destination_token_mint_account.key,
+            None,
+            None,
             instruction,
         )
         .unwrap();
@@ -0,0 +1,531 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Processor for permissioned pool administration instructions.
+
+use solana_program::{
+    pubkey::Pubkey,
+    program_error::ProgramError,
+    account_info::{AccountInfo, next_account_info},
+    clock::Clock,
+    program_pack::Pack,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    constraints::SwapConstraints, 
+    curve::fees::Fees, 
+    error::SwapError, 
+    permission::{roles, Permission}, 
+    processor::Processor, 
+    psm3::Psm3Pool, 
+    rate_provider::RateProvider, 
+    rate_limit::SwapRateLimit, 
+    state::{SwapState, SwapVersion},
+    utils::close_account
+};
+
+/// Returns the swap, three-asset pool or rate provider of `accounts` once its permission
+/// account and signer, the next two accounts, are checked to hold `role`
+fn next_permissioned_pool<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+    role: u64
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info, 
+        swap_info, 
+        signer_info, 
+        program_id
+    )?;
+
+    permission.validate_role(role)?;
+
+    Ok(swap_info)
+}
+
+/// Applies `update` to a swap on behalf of a signer holding `role`.
+/// Accounts are the swap, the permission account and its signer,
+/// followed by any account specific to the instruction.
+pub fn with_permissioned_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u64,
+    update: impl FnOnce(&mut SwapVersion) -> Result<(), ProgramError>
+) -> Result<(), ProgramError> {
+    let swap_info = next_permissioned_pool(program_id, accounts, role)?;
+
+    let mut swap_data = swap_info.data.borrow_mut();
+    let mut swap = SwapVersion::unpack_version(&swap_data)?;
+
+    update(&mut swap)?;
+
+    SwapVersion::pack(swap, &mut swap_data)?;
+
+    Ok(())
+}
+
+/// Same as `with_permissioned_swap` for a three-asset pool
+pub fn with_permissioned_psm3_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u64,
+    update: impl FnOnce(&mut Psm3Pool)
+) -> Result<(), ProgramError> {
+    let pool_info = next_permissioned_pool(program_id, accounts, role)?;
+
+    let mut pool = Psm3Pool::unpack_pool(pool_info, program_id)?;
+
+    update(&mut pool);
+
+    Psm3Pool::pack(pool, &mut pool_info.data.borrow_mut())
+}
+
+/// Same as `with_timelocked_swap` for a three-asset pool
+pub fn with_timelocked_psm3_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u64,
+    update: impl FnOnce(&mut Psm3Pool)
+) -> Result<(), ProgramError> {
+    let pool_info = next_permissioned_pool(program_id, accounts, role)?;
+
+    let mut pool = Psm3Pool::unpack_pool(pool_info, program_id)?;
+
+    if pool.timelock_delay != 0 {
+        return Err(SwapError::TimelockRequired.into())
+    }
+
+    update(&mut pool);
+
+    Psm3Pool::pack(pool, &mut pool_info.data.borrow_mut())
+}
+
+/// Same as `with_timelocked_swap` for a rate provider
+pub fn with_timelocked_rate_provider(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u64,
+    update: impl FnOnce(&mut RateProvider)
+) -> Result<(), ProgramError> {
+    let rate_provider_info = next_permissioned_pool(program_id, accounts, role)?;
+
+    let mut rate_provider = RateProvider::unpack_rate_provider(rate_provider_info, program_id)?;
+
+    if rate_provider.timelock_delay != 0 {
+        return Err(SwapError::TimelockRequired.into())
+    }
+
+    update(&mut rate_provider);
+
+    RateProvider::pack(rate_provider, &mut rate_provider_info.data.borrow_mut())
+}
+
+/// Returns true if the instruction targets a three-asset pool
+fn is_psm3_instruction(accounts: &[AccountInfo]) -> bool {
+    accounts.first().is_some_and(Psm3Pool::is_psm3_pool)
+}
+
+/// Returns true if the instruction targets a rate provider
+fn is_rate_provider_instruction(accounts: &[AccountInfo]) -> bool {
+    accounts.first().is_some_and(RateProvider::is_rate_provider)
+}
+
+/// Same as `with_permissioned_swap` for changes that have to go through
+/// the timelock while the pool has a delay
+pub fn with_timelocked_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u64,
+    update: impl FnOnce(&mut SwapVersion) -> Result<(), ProgramError>
+) -> Result<(), ProgramError> {
+    with_permissioned_swap(program_id, accounts, role, |swap| {
+        if swap.timelock_delay() != 0 {
+            return Err(SwapError::TimelockRequired.into())
+        }
+
+        update(swap)
+    })
+}
+
+/// Processes pausing or unpausing the pool
+pub fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    is_paused: bool
+) -> Result<(), ProgramError> {
+    if is_psm3_instruction(accounts) {
+        return with_permissioned_psm3_pool(program_id, accounts, roles::PAUSE, |pool| {
+            pool.is_paused = is_paused;
+        })
+    }
+
+    with_permissioned_swap(program_id, accounts, roles::PAUSE, |swap| {
+        swap.pool_config_mut()?.is_paused = is_paused;
+        Ok(())
+    })
+}
+
+/// Processes enabling or disabling each swap direction of the pool
+pub fn process_set_swap_directions(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    a_to_b_enabled: bool,
+    b_to_a_enabled: bool
+) -> Result<(), ProgramError> {
+    with_permissioned_swap(program_id, accounts, roles::SET_SWAP_DIRECTIONS, |swap| {
+        let pool_config = swap.pool_config_mut()?;
+        pool_config.a_to_b_disabled = !a_to_b_enabled;
+        pool_config.b_to_a_disabled = !b_to_a_enabled;
+        Ok(())
+    })
+}
+
+/// Processes switching the allowlist mode of the pool
+pub fn process_set_allowlist_mode(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    liquidity_allowlisted: bool,
+    swaps_allowlisted: bool
+) -> Result<(), ProgramError> {
+    with_permissioned_swap(program_id, accounts, roles::SUPER_ADMIN, |swap| {
+        let pool_config = swap.pool_config_mut()?;
+        pool_config.liquidity_allowlisted = liquidity_allowlisted;
+        pool_config.swaps_allowlisted = swaps_allowlisted;
+        Ok(())
+    })
+}
+
+/// Processes updating the maximum staleness of the pool's rate
+pub fn process_set_max_staleness(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_staleness: u64
+) -> Result<(), ProgramError> {
+    if is_psm3_instruction(accounts) {
+        return with_timelocked_psm3_pool(program_id, accounts, roles::SUPER_ADMIN, |pool| {
+            pool.max_staleness = max_staleness;
+        })
+    }
+
+    with_timelocked_swap(program_id, accounts, roles::SUPER_ADMIN, |swap| {
+        swap.pool_config_mut()?.max_staleness = max_staleness;
+        Ok(())
+    })
+}
+
+/// Processes updating the value cap of the pool
+pub fn process_set_max_tvl(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_tvl: u128
+) -> Result<(), ProgramError> {
+    with_permissioned_swap(program_id, accounts, roles::MANAGE_LIQUIDITY, |swap| {
+        swap.pool_config_mut()?.max_tvl = max_tvl;
+        Ok(())
+    })
+}
+
+/// Processes updating the pool fees, enforcing the same constraints
+/// as on initialization
+pub fn process_update_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fees: Fees,
+    swap_constraints: &Option<SwapConstraints>
+) -> Result<(), ProgramError> {
+    with_timelocked_swap(program_id, accounts, roles::UPDATE_FEES, |swap| {
+        validate_fees(&fees, swap_constraints)?;
+        swap.set_fees(fees);
+        Ok(())
+    })
+}
+
+/// Validates new pool fees the same way as on initialization
+pub fn validate_fees(
+    fees: &Fees,
+    swap_constraints: &Option<SwapConstraints>
+) -> Result<(), ProgramError> {
+    if let Some(swap_constraints) = swap_constraints {
+        swap_constraints.validate_fees(fees)?;
+    }
+    fees.validate()?;
+
+    Ok(())
+}
+
+/// Processes changing the pool token account receiving the fees
+pub fn process_set_fee_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    swap_constraints: &Option<SwapConstraints>
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let _permission_info = next_account_info(accounts_info_iter)?;
+    let _signer_info = next_account_info(accounts_info_iter)?;
+    let fee_account_info = next_account_info(accounts_info_iter)?;
+
+    with_timelocked_swap(program_id, accounts, roles::UPDATE_FEE_ACCOUNT, |swap| {
+        validate_fee_account(
+            program_id, 
+            swap_info, 
+            swap, 
+            fee_account_info, 
+            swap_constraints
+        )?;
+
+        swap.set_pool_fee_account(*fee_account_info.key);
+        Ok(())
+    })
+}
+
+/// Validates a new pool fee account the same way as on initialization:
+/// it has to hold pool tokens and can't be owned by the swap authority
+pub fn validate_fee_account(
+    program_id: &Pubkey,
+    swap_info: &AccountInfo,
+    swap: &SwapVersion,
+    fee_account_info: &AccountInfo,
+    swap_constraints: &Option<SwapConstraints>
+) -> Result<(), ProgramError> {
+    swap.check_pool_fee_info(fee_account_info)?;
+
+    let fee_account = Processor::unpack_token_account(
+        fee_account_info, 
+        Some(swap.pool_mint())
+    )?;
+    let authority = Processor::authority_id(
+        program_id, 
+        swap_info.key, 
+        swap.bump_seed()
+    )?;
+
+    if fee_account.owner == authority {
+        return Err(SwapError::InvalidOutputOwner.into())
+    }
+
+    if let Some(swap_constraints) = swap_constraints {
+        let owner_key = swap_constraints
+            .owner_key
+            .unwrap()
+            .parse::<Pubkey>()
+            .map_err(|_| SwapError::InvalidOwner)?;
+        if fee_account.owner != owner_key {
+            return Err(SwapError::InvalidOwner.into())
+        }
+    }
+
+    Ok(())
+}
+
+/// Processes setting the bounds on rate updates of the pool
+pub fn process_set_rate_update_limits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_ssr_delta: u128,
+    min_update_interval: u64
+) -> Result<(), ProgramError> {
+    if is_rate_provider_instruction(accounts) {
+        return with_timelocked_rate_provider(program_id, accounts, roles::SUPER_ADMIN, |rate_provider| {
+            rate_provider.max_ssr_delta = max_ssr_delta;
+            rate_provider.min_rates_update_interval = min_update_interval;
+        })
+    }
+
+    if is_psm3_instruction(accounts) {
+        return with_timelocked_psm3_pool(program_id, accounts, roles::SUPER_ADMIN, |pool| {
+            pool.max_ssr_delta = max_ssr_delta;
+            pool.min_rates_update_interval = min_update_interval;
+        })
+    }
+
+    with_timelocked_swap(program_id, accounts, roles::SUPER_ADMIN, |swap| {
+        let pool_config = swap.pool_config_mut()?;
+        pool_config.max_ssr_delta = max_ssr_delta;
+        pool_config.min_rates_update_interval = min_update_interval;
+        Ok(())
+    })
+}
+
+/// Processes setting the swap volume limits of the pool, both limits
+/// start full
+pub fn process_set_swap_rate_limits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    a_to_b_max_amount: u64,
+    a_to_b_slope: u64,
+    b_to_a_max_amount: u64,
+    b_to_a_slope: u64
+) -> Result<(), ProgramError> {
+    with_timelocked_swap(program_id, accounts, roles::SUPER_ADMIN, |swap| {
+        let timestamp = Clock::get()?.unix_timestamp as u64;
+        let pool_config = swap.pool_config_mut()?;
+        pool_config.a_to_b_rate_limit = SwapRateLimit::new(a_to_b_max_amount, a_to_b_slope, timestamp);
+        pool_config.b_to_a_rate_limit = SwapRateLimit::new(b_to_a_max_amount, b_to_a_slope, timestamp);
+        Ok(())
+    })
+}
+
+/// Processes setting the timelock delay of the pool. Once set, the delay
+/// itself can only be changed through the timelock.
+pub fn process_set_timelock_delay(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    timelock_delay: u64
+) -> Result<(), ProgramError> {
+    if is_rate_provider_instruction(accounts) {
+        return with_timelocked_rate_provider(program_id, accounts, roles::SUPER_ADMIN, |rate_provider| {
+            rate_provider.timelock_delay = timelock_delay;
+        })
+    }
+
+    if is_psm3_instruction(accounts) {
+        return with_timelocked_psm3_pool(program_id, accounts, roles::SUPER_ADMIN, |pool| {
+            pool.timelock_delay = timelock_delay;
+        })
+    }
+
+    with_timelocked_swap(program_id, accounts, roles::SUPER_ADMIN, |swap| {
+        swap.pool_config_mut()?.timelock_delay = timelock_delay;
+        Ok(())
+    })
+}
+
+/// Processes closing a pool that has no liquidity left besides the
+/// initial supply locked at initialization. The remaining reserves are
+/// sent out, the reserve token accounts are closed through the swap
+/// authority and the swap account's rent is reclaimed.
+pub fn process_close_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let authority_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let token_a_info = next_account_info(accounts_info_iter)?;
+    let token_b_info = next_account_info(accounts_info_iter)?;
+    let pool_mint_info = next_account_info(accounts_info_iter)?;
+    let locked_pool_token_info = next_account_info(accounts_info_iter)?;
+    let dest_token_a_info = next_account_info(accounts_info_iter)?;
+    let dest_token_b_info = next_account_info(accounts_info_iter)?;
+    let token_a_mint_info = next_account_info(accounts_info_iter)?;
+    let token_b_mint_info = next_account_info(accounts_info_iter)?;
+    let recipient_info = next_account_info(accounts_info_iter)?;
+    let token_a_program_info = next_account_info(accounts_info_iter)?;
+    let token_b_program_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info, 
+        swap_info, 
+        signer_info, 
+        program_id
+    )?;
+
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    let swap = SwapVersion::unpack_version(&swap_info.data.borrow())?;
+
+    if *authority_info.key != Processor::authority_id(program_id, swap_info.key, swap.bump_seed())? {
+        return Err(SwapError::InvalidProgramAddress.into())
+    }
+    if *token_a_info.key != *swap.token_a_account() {
+        return Err(SwapError::IncorrectSwapAccount.into())
+    }
+    if *token_b_info.key != *swap.token_b_account() {
+        return Err(SwapError::IncorrectSwapAccount.into())
+    }
+    if *pool_mint_info.key != *swap.pool_mint() {
+        return Err(SwapError::IncorrectPoolMint.into())
+    }
+
+    // the initial supply is minted to a token account owned by this PDA,
+    // nobody can withdraw it
+    let (locked_owner, _) = Pubkey::find_program_address(
+        &[
+            b"init_destination", 
+            &swap_info.key.to_bytes()
+        ], 
+        program_id
+    );
+
+    let locked_pool_token = Processor::unpack_token_account(
+        locked_pool_token_info, 
+        Some(pool_mint_info.key)
+    )?;
+
+    if locked_pool_token.owner != locked_owner {
+        return Err(SwapError::InvalidOwner.into())
+    }
+
+    let pool_mint = Processor::unpack_mint(pool_mint_info)?;
+
+    if pool_mint.supply != locked_pool_token.amount {
+        return Err(SwapError::PoolNotEmpty.into())
+    }
+
+    for (
+        token_info,
+        dest_token_info,
+        token_mint_info,
+        token_program_info
+    ) in [
+        (token_a_info, dest_token_a_info, token_a_mint_info, token_a_program_info),
+        (token_b_info, dest_token_b_info, token_b_mint_info, token_b_program_info),
+    ] {
+        let token = Processor::unpack_token_account(
+            token_info, 
+            Some(token_mint_info.key)
+        )?;
+
+        if token.amount > 0 {
+            Processor::token_transfer(
+                swap_info.key,
+                token_program_info.clone(),
+                token_info.clone(),
+                token_mint_info.clone(),
+                dest_token_info.clone(),
+                authority_info.clone(),
+                swap.bump_seed(),
+                token.amount,
+                Processor::unpack_mint(token_mint_info)?.decimals,
+            )?;
+        }
+
+        Processor::token_close_account(
+            swap_info.key,
+            token_program_info.clone(),
+            token_info.clone(),
+            recipient_info.clone(),
+            authority_info.clone(),
+            swap.bump_seed(),
+        )?;
+    }
+
+    close_account(swap_info, recipient_info)
+}
@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Allowlist of users allowed to provide liquidity to or swap on a pool,
+//! once the pool is in allowlist mode.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::slice::Iter;
+
+use crate::{
+    error::SwapError,
+    permission::{roles, Permission},
+    state::SwapVersion,
+    utils::{close_account, create_pda_account},
+    ID as PROGRAM_ID,
+};
+
+/// Entry allowing a user on an allowlisted pool
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AllowlistEntry {
+    /// Boolean set to true after an AllowlistEntry is created
+    pub is_initialized: bool,
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// The allowed user, signing as user transfer authority
+    pub user: Pubkey,
+}
+
+impl IsInitialized for AllowlistEntry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for AllowlistEntry {}
+
+impl Pack for AllowlistEntry {
+    const LEN: usize = 65;
+
+    fn unpack_from_slice(input: &[u8]) -> Result<AllowlistEntry, ProgramError> {
+        let input = array_ref![input, 0, AllowlistEntry::LEN];
+        let (is_initialized, swap, user) = array_refs![input, 1, 32, 32];
+
+        Ok(Self {
+            is_initialized: is_initialized[0] != 0,
+            swap: Pubkey::new_from_array(*swap),
+            user: Pubkey::new_from_array(*user),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, AllowlistEntry::LEN];
+        let (is_initialized, swap, user) = mut_array_refs![output, 1, 32, 32];
+
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+    }
+}
+
+impl AllowlistEntry {
+    /// Seed for PDA
+    pub const ALLOWLIST_SEED: &'static [u8] = b"allowlist";
+
+    /// Derives the allowlist entry of a user on a swap
+    pub fn derive_allowlist_entry_pubkey_and_bump(
+        swap: &Pubkey,
+        user: &Pubkey
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::ALLOWLIST_SEED,
+                &swap.to_bytes(),
+                &user.to_bytes()
+            ],
+            &PROGRAM_ID
+        )
+    }
+
+    /// Takes the allowlist entry out of the optional accounts of `account_info_iter`
+    /// if the next one is an entry, recognized by its owner and size rather than
+    /// by the allowlist mode of the pool. Optional fee accounts are read at the
+    /// same position whether the pool is allowlisted or not.
+    pub fn next_allowlist_entry<'a, 'b>(
+        account_info_iter: &mut Iter<'a, AccountInfo<'b>>,
+        program_id: &Pubkey
+    ) -> Option<&'a AccountInfo<'b>> {
+        let allowlist_info = account_info_iter.as_slice().first()?;
+
+        if allowlist_info.owner != program_id || allowlist_info.data_len() != Self::LEN {
+            return None
+        }
+
+        account_info_iter.next()
+    }
+
+    /// Checks that `allowlist_info` is the entry of the signing user on the swap
+    pub fn check_allowlisted(
+        allowlist_info: Option<&AccountInfo>,
+        swap_info: &AccountInfo,
+        user_info: &AccountInfo,
+        program_id: &Pubkey
+    ) -> Result<(), ProgramError> {
+        if !user_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature)
+        }
+
+        let allowlist_info = allowlist_info.ok_or(SwapError::NotAllowlisted)?;
+
+        if allowlist_info.owner != program_id {
+            return Err(SwapError::NotAllowlisted.into())
+        }
+
+        let (allowlist_address, _) = Self::derive_allowlist_entry_pubkey_and_bump(
+            swap_info.key,
+            user_info.key
+        );
+
+        if *allowlist_info.key != allowlist_address {
+            return Err(SwapError::NotAllowlisted.into())
+        }
+
+        let entry = Self::unpack(&allowlist_info.data.borrow())?;
+
+        if entry.swap != *swap_info.key || entry.user != *user_info.key {
+            return Err(SwapError::NotAllowlisted.into())
+        }
+
+        Ok(())
+    }
+}
+
+/// Processes adding a user to the allowlist of a swap
+pub fn process_add_to_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user: Pubkey
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let allowlist_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info,
+        swap_info,
+        signer_info,
+        program_id
+    )?;
+
+    permission.validate_role(roles::MANAGE_ALLOWLIST)?;
+
+    // only pools with pool controls can be allowlisted
+    SwapVersion::unpack_version(&swap_info.data.borrow())?.pool_config()?;
+
+    let (
+        allowlist_address,
+        allowlist_bump
+    ) = AllowlistEntry::derive_allowlist_entry_pubkey_and_bump(swap_info.key, &user);
+
+    if *allowlist_info.key != allowlist_address {
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    if allowlist_info.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+
+    create_pda_account(
+        payer_info.clone(),
+        allowlist_info.clone(),
+        system_program_info.clone(),
+        AllowlistEntry::LEN,
+        &[&[
+            AllowlistEntry::ALLOWLIST_SEED,
+            swap_info.key.as_ref(),
+            user.as_ref(),
+            &[allowlist_bump]
+        ]]
+    )?;
+
+    let entry = AllowlistEntry {
+        is_initialized: true,
+        swap: *swap_info.key,
+        user,
+    };
+
+    AllowlistEntry::pack(entry, &mut allowlist_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Processes removing a user from the allowlist of a swap,
+/// closing its entry
+pub fn process_remove_from_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let allowlist_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let recipient_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    if allowlist_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info,
+        swap_info,
+        signer_info,
+        program_id
+    )?;
+
+    permission.validate_role(roles::MANAGE_ALLOWLIST)?;
+
+    let entry = AllowlistEntry::unpack(&allowlist_info.data.borrow())?;
+
+    if entry.swap != *swap_info.key {
+        return Err(SwapError::NotAllowlisted.into())
+    }
+
+    close_account(allowlist_info, recipient_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist_entry_pack() {
+        let entry = AllowlistEntry {
+            is_initialized: true,
+            swap: Pubkey::new_from_array([1u8; 32]),
+            user: Pubkey::new_from_array([2u8; 32]),
+        };
+
+        let mut packed = [0u8; AllowlistEntry::LEN];
+        AllowlistEntry::pack(entry.clone(), &mut packed).unwrap();
+
+        let mut expect = vec![1u8];
+        expect.extend_from_slice(&[1u8; 32]);
+        expect.extend_from_slice(&[2u8; 32]);
+        assert_eq!(packed.to_vec(), expect);
+        assert_eq!(AllowlistEntry::unpack(&packed).unwrap(), entry);
+    }
+
+    #[test]
+    fn next_allowlist_entry_by_owner_and_size() {
+        let program_id = PROGRAM_ID;
+        let token_program = Pubkey::new_unique();
+        let (entry_key, fee_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut entry_lamports, mut fee_lamports) = (0, 0);
+        let mut entry_data = [0u8; AllowlistEntry::LEN];
+        let mut fee_data = [0u8; 165];
+
+        let entry_info = AccountInfo::new(
+            &entry_key, false, false, &mut entry_lamports, &mut entry_data, &program_id, false, 0
+        );
+        let fee_info = AccountInfo::new(
+            &fee_key, false, true, &mut fee_lamports, &mut fee_data, &token_program, false, 0
+        );
+
+        // the entry is taken out before the host fee account
+        let accounts = [entry_info.clone(), fee_info.clone()];
+        let account_info_iter = &mut accounts.iter();
+        let entry = AllowlistEntry::next_allowlist_entry(account_info_iter, &program_id);
+        assert_eq!(entry.unwrap().key, &entry_key);
+        assert_eq!(account_info_iter.next().unwrap().key, &fee_key);
+
+        // a host fee account without an entry is left in place
+        let accounts = [fee_info];
+        let account_info_iter = &mut accounts.iter();
+        assert!(AllowlistEntry::next_allowlist_entry(account_info_iter, &program_id).is_none());
+        assert_eq!(account_info_iter.next().unwrap().key, &fee_key);
+
+        let account_info_iter = &mut [].iter();
+        assert!(AllowlistEntry::next_allowlist_entry(account_info_iter, &program_id).is_none());
+    }
+}
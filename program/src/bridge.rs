@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Rate updates sent by a bridge receiver program, so that rates relayed from
+//! another chain by a cross-chain messaging integration can replace a keeper.
+//!
+//! A swap or a rate provider configures its bridge receiver with
+//! `SetBridgeReceiver`. The receiver then updates the rates through a
+//! `BridgeSetRates` CPI, signed by its bridge authority PDA.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::{
+    error::SwapError,
+    permission::{roles, Permission},
+    rate_provider::RateProvider,
+    redemption_rate_processor::update_rates,
+    state::{SwapState, SwapVersion},
+};
+
+/// Seed of the bridge authority, a PDA of the bridge receiver program
+pub const BRIDGE_AUTHORITY_SEED: &[u8] = b"bridge_authority";
+
+/// Derives the bridge authority signing the rate updates of a bridge receiver
+pub fn derive_bridge_authority_pubkey_and_bump(bridge_receiver: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BRIDGE_AUTHORITY_SEED], bridge_receiver)
+}
+
+/// Returns the bridge receiver of a swap or a rate provider
+fn unpack_bridge_receiver(swap_info: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    if RateProvider::is_rate_provider(swap_info) {
+        return Ok(RateProvider::unpack(&swap_info.data.borrow())?.bridge_receiver)
+    }
+
+    let swap = SwapVersion::unpack_version(&swap_info.data.borrow())?;
+    Ok(swap.pool_config()?.bridge_receiver)
+}
+
+/// Processes setting the bridge receiver of a swap or a rate provider.
+/// Signer must have a super_admin associated permission account
+pub fn process_set_bridge_receiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    bridge_receiver: Pubkey
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info,
+        swap_info,
+        signer_info,
+        program_id
+    )?;
+
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    if RateProvider::is_rate_provider(swap_info) {
+        let mut rate_provider = RateProvider::unpack(&swap_info.data.borrow())?;
+
+        if rate_provider.timelock_delay != 0 {
+            return Err(SwapError::TimelockRequired.into())
+        }
+
+        rate_provider.bridge_receiver = bridge_receiver;
+        return RateProvider::pack(rate_provider, &mut swap_info.data.borrow_mut())
+    }
+
+    let mut swap_data = swap_info.data.borrow_mut();
+    let mut swap = SwapVersion::unpack_version(&swap_data)?;
+
+    if swap.timelock_delay() != 0 {
+        return Err(SwapError::TimelockRequired.into())
+    }
+
+    swap.pool_config_mut()?.bridge_receiver = bridge_receiver;
+
+    SwapVersion::pack(swap, &mut swap_data)?;
+
+    Ok(())
+}
+
+/// Processes a [BridgeSetRates](crate::instruction::SwapInstruction::BridgeSetRates),
+/// which must be invoked by the bridge receiver of the swap or rate provider
+pub fn process_bridge_set_rates(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    ssr: u128,
+    rho: u128,
+    chi: u128
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let bridge_authority_info = next_account_info(accounts_info_iter)?;
+    let instructions_sysvar_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let bridge_receiver = unpack_bridge_receiver(swap_info)?;
+    if bridge_receiver == Pubkey::default() {
+        return Err(SwapError::InvalidBridgeReceiver.into())
+    }
+
+    // the transaction instruction being processed has to be one of the
+    // bridge receiver, which invokes this one
+    let current_index = load_current_index_checked(instructions_sysvar_info)?;
+    let current_instruction = load_instruction_at_checked(
+        current_index as usize,
+        instructions_sysvar_info
+    )?;
+    if current_instruction.program_id != bridge_receiver {
+        return Err(SwapError::InvalidBridgeReceiver.into())
+    }
+
+    let (bridge_authority, _) = derive_bridge_authority_pubkey_and_bump(&bridge_receiver);
+    if *bridge_authority_info.key != bridge_authority {
+        return Err(SwapError::InvalidBridgeReceiver.into())
+    }
+    if !bridge_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    update_rates(swap_info, bridge_authority, ssr, rho, chi)
+}
@@ -80,7 +80,8 @@ const FEES: &Fees = &Fees {
 #[cfg(feature = "production")]
 const VALID_CURVE_TYPES: &[CurveType] = &[
     CurveType::ConstantPrice, 
-    CurveType::RedemptionRateCurve
+    CurveType::RedemptionRateCurve,
+    CurveType::RateProviderCurve
 ];
 
 /// Fee structure defined by program creator in order to enforce certain
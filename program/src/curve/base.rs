@@ -13,6 +13,7 @@ use {
         constant_product::ConstantProductCurve,
         fees::Fees,
         offset::OffsetCurve,
+        rate_provider::RateProviderCurve,
     },
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     solana_program::{
@@ -40,7 +41,10 @@ pub enum CurveType {
     /// Offset curve, like Uniswap, but the token B side has a faked offset
     Offset,
     /// Spark PSM3 style curve
-    RedemptionRateCurve
+    RedemptionRateCurve,
+    /// Spark PSM3 style curve reading its rates from a shared rate provider
+    /// account
+    RateProviderCurve,
 }
 
 /// Encodes all results of swapping from a source token to a destination token
@@ -132,6 +136,47 @@ impl SwapCurve {
         })
     }
 
+    /// Calculate how much source token, fees included, is required to get an
+    /// exact amount of destination token.
+    pub fn swap_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        fees: &Fees,
+        timestamp: Option<u128>
+    ) -> Option<SwapResult> {
+        let SwapWithoutFeesResult {
+            source_amount_swapped: amount_used,
+            destination_amount_swapped,
+        } = self.calculator.swap_without_fees_exact_out(
+            destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+            timestamp
+        )?;
+
+        // gross up the amount used by the curve so that the fees charged on
+        // it leave at least that amount, as a swap with the same input would
+        let pre_fee_amount = fees.pre_trading_fee_amount(amount_used)?;
+        let trade_fee = fees.trading_fee(pre_fee_amount)?;
+        let owner_fee = fees.owner_trading_fee(pre_fee_amount)?;
+        let total_fees = trade_fee.checked_add(owner_fee)?;
+
+        let source_amount_swapped = amount_used.checked_add(total_fees)?;
+        Some(SwapResult {
+            new_swap_source_amount: swap_source_amount.checked_add(source_amount_swapped)?,
+            new_swap_destination_amount: swap_destination_amount
+                .checked_sub(destination_amount_swapped)?,
+            source_amount_swapped,
+            destination_amount_swapped,
+            trade_fee,
+            owner_fee,
+        })
+    }
+
     /// Get the amount of pool tokens for the deposited amount of token A or B
     pub fn deposit_single_token_type(
         &self,
@@ -281,7 +326,8 @@ impl Pack for SwapCurve {
                     Arc::new(ConstantPriceCurve::unpack_from_slice(calculator)?)
                 }
                 CurveType::Offset => Arc::new(OffsetCurve::unpack_from_slice(calculator)?),
-                CurveType::RedemptionRateCurve => Arc::new(RedemptionRateCurve::unpack_from_slice(calculator)?)
+                CurveType::RedemptionRateCurve => Arc::new(RedemptionRateCurve::unpack_from_slice(calculator)?),
+                CurveType::RateProviderCurve => Arc::new(RateProviderCurve::unpack_from_slice(calculator)?),
             },
         })
     }
@@ -312,6 +358,7 @@ impl TryFrom<u8> for CurveType {
             1 => Ok(CurveType::ConstantPrice),
             2 => Ok(CurveType::Offset),
             3 => Ok(CurveType::RedemptionRateCurve),
+            4 => Ok(CurveType::RateProviderCurve),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
@@ -391,6 +438,71 @@ mod test {
         assert_eq!(result.owner_fee, expected_owner_fee);
     }
 
+    #[test]
+    fn swap_exact_out_charges_fees_on_pre_fee_amount() {
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantPrice,
+            calculator: Arc::new(ConstantPriceCurve { token_b_price: RAY }),
+        };
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 1_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let destination_amount: u128 = 10_000;
+        let swap_source_amount: u128 = 1_000_000;
+        let swap_destination_amount: u128 = 1_000_000;
+
+        let result = swap_curve
+            .swap_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+                &fees,
+                None,
+            )
+            .unwrap();
+
+        // ceil(10_000 / 0.97) = 10_310 before fees of 257 + 51
+        assert_eq!(result.destination_amount_swapped, destination_amount);
+        assert_eq!(result.trade_fee, 257);
+        assert_eq!(result.owner_fee, 51);
+        assert_eq!(result.source_amount_swapped, 10_308);
+        assert_eq!(result.new_swap_source_amount, swap_source_amount + 10_308);
+        assert_eq!(
+            result.new_swap_destination_amount,
+            swap_destination_amount - destination_amount
+        );
+
+        // swapping the same amount in gives the same result
+        let exact_in = swap_curve
+            .swap(
+                result.source_amount_swapped,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+                &fees,
+                None,
+            )
+            .unwrap();
+        assert_eq!(exact_in, result);
+
+        // more than the destination reserve
+        assert!(swap_curve
+            .swap_exact_out(
+                swap_destination_amount + 1,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+                &fees,
+                None,
+            )
+            .is_none());
+    }
+
     #[test]
     fn withdraw_single_token_owner_fee_on_pre_fee_amount() {
         // pool balances and exact-out request
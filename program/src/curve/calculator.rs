@@ -4,7 +4,12 @@
 
 #[cfg(feature = "fuzz")]
 use arbitrary::Arbitrary;
-use {crate::error::SwapError, spl_math::precise_number::PreciseNumber, std::fmt::Debug};
+use {
+    crate::error::SwapError,
+    solana_program::pubkey::Pubkey,
+    spl_math::precise_number::PreciseNumber,
+    std::fmt::Debug,
+};
 
 /// Initial amount of pool tokens for swap contract, hard-coded to something
 /// "sensible" given a maximum of u128.
@@ -97,6 +102,20 @@ pub trait CurveCalculator: Debug + DynPack {
         timestamp: Option<u128>,
     ) -> Option<SwapWithoutFeesResult>;
 
+    /// Calculate how much source token is required to get an exact amount of
+    /// destination token, rounding the source amount up. Curves without an
+    /// implementation don't support exact out swaps.
+    fn swap_without_fees_exact_out(
+        &self,
+        _destination_amount: u128,
+        _swap_source_amount: u128,
+        _swap_destination_amount: u128,
+        _trade_direction: TradeDirection,
+        _timestamp: Option<u128>,
+    ) -> Option<SwapWithoutFeesResult> {
+        None
+    }
+
     /// Get the supply for a new pool
     /// The default implementation is a Balancer-style fixed initial supply
     fn new_pool_supply(&self) -> u128 {
@@ -157,6 +176,29 @@ pub trait CurveCalculator: Debug + DynPack {
     /// Validate that the given curve has no invalid parameters
     fn validate(&self, timestamp: Option<u128>) -> Result<(), SwapError>;
 
+    /// Validate that the curve's pricing is no older than `max_staleness`
+    /// seconds at the given timestamp. Curves without time-based pricing are
+    /// never stale.
+    fn validate_staleness(
+        &self,
+        _timestamp: Option<u128>,
+        _max_staleness: u64,
+    ) -> Result<(), SwapError> {
+        Ok(())
+    }
+
+    /// Conversion rate of token B in token A at the given timestamp, for
+    /// curves that price with one
+    fn conversion_rate(&self, _timestamp: Option<u128>) -> Option<u128> {
+        None
+    }
+
+    /// Rate provider account holding the rates of the curve, for curves
+    /// reading them at instruction time
+    fn rate_provider(&self) -> Option<&Pubkey> {
+        None
+    }
+
     /// Validate the given supply on initialization. This is useful for curves
     /// that allow zero supply on one or both sides, since the standard constant
     /// product curve must have a non-zero supply on both sides.
@@ -452,6 +494,50 @@ pub mod test {
         assert!(difference <= epsilon);
     }
 
+    /// Test function checking that an exact out swap requires the smallest
+    /// source amount giving at least the destination amount when swapped in.
+    pub fn check_swap_exact_out(
+        curve: &dyn CurveCalculator,
+        destination_token_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        timestamp: Option<u128>
+    ) {
+        let results = curve
+            .swap_without_fees_exact_out(
+                destination_token_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                trade_direction,
+                timestamp
+            )
+            .unwrap();
+        assert_eq!(results.destination_amount_swapped, destination_token_amount);
+
+        let swapped_in = curve
+            .swap_without_fees(
+                results.source_amount_swapped,
+                swap_source_amount,
+                swap_destination_amount,
+                trade_direction,
+                timestamp
+            )
+            .unwrap();
+        assert!(swapped_in.destination_amount_swapped >= destination_token_amount);
+
+        let one_less = curve.swap_without_fees(
+            results.source_amount_swapped - 1,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+            timestamp
+        );
+        if let Some(one_less) = one_less {
+            assert!(one_less.destination_amount_swapped < destination_token_amount);
+        }
+    }
+
     /// Test function checking that a deposit never reduces the value of pool
     /// tokens.
     ///
@@ -116,6 +116,38 @@ impl CurveCalculator for ConstantPriceCurve {
         })
     }
 
+    /// Round the source amount up, so the pool never gives out more than
+    /// it is paid for.
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        _swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        _timestamp: Option<u128>
+    ) -> Option<SwapWithoutFeesResult> {
+        let token_b_price = U256::from(self.token_b_price);
+        let destination_amount = U256::from(destination_amount);
+        let scaling_factor = U256::from(RAY);
+
+        let (source_amount, _) = match trade_direction {
+            TradeDirection::BtoA => destination_amount
+                .checked_mul(scaling_factor)?
+                .checked_ceil_div(token_b_price)?,
+            TradeDirection::AtoB => destination_amount
+                .checked_mul(token_b_price)?
+                .checked_ceil_div(scaling_factor)?,
+        };
+
+        let source_amount_swapped = map_zero_to_none(source_amount.as_u128())?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount.as_u128())?;
+
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
     /// Convert `pool_tokens` into the proportional amounts of each trading token,
     /// given current pool reserves and total pool-token supply.
     /// Rounds according to `round_direction`.
@@ -206,6 +238,10 @@ impl CurveCalculator for ConstantPriceCurve {
         }
     }
 
+    fn conversion_rate(&self, _timestamp: Option<u128>) -> Option<u128> {
+        Some(self.token_b_price)
+    }
+
     fn validate_supply(&self, token_a_amount: u64, _token_b_amount: u64) -> Result<(), SwapError> {
         if token_a_amount == 0 {
             return Err(SwapError::EmptySupply);
@@ -281,7 +317,7 @@ mod tests {
         crate::curve::calculator::{
             test::{
                 check_curve_value_from_swap, check_deposit_token_conversion,
-                check_withdraw_token_conversion, total_and_intermediate,
+                check_swap_exact_out, check_withdraw_token_conversion, total_and_intermediate,
                 CONVERSION_BASIS_POINTS_GUARANTEE,
             },
             INITIAL_SWAP_POOL_AMOUNT,
@@ -630,6 +666,26 @@ mod tests {
         }
     }
 
+    proptest! {
+        #[test]
+        fn swap_exact_out_rounds_source_up(
+            destination_token_amount in 1..u64::MAX,
+            token_b_price in RAY / 1_000..1_000 * RAY,
+        ) {
+            let curve = ConstantPriceCurve { token_b_price };
+            for trade_direction in [TradeDirection::AtoB, TradeDirection::BtoA] {
+                check_swap_exact_out(
+                    &curve,
+                    destination_token_amount as u128,
+                    u64::MAX as u128,
+                    u64::MAX as u128,
+                    trade_direction,
+                    None
+                );
+            }
+        }
+    }
+
     proptest! {
         #[test]
         fn curve_value_does_not_decrease_from_deposit(
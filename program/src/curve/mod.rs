@@ -8,4 +8,5 @@ pub mod constant_price;
 pub mod constant_product;
 pub mod fees;
 pub mod offset;
+pub mod rate_provider;
 pub mod redemption_rate;
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Redemption rate curve reading its rates from a shared rate provider
+//! account, so that a single rate update reprices every pool using it.
+//!
+//! The calculator only stores the address of the rate provider. Instructions
+//! pricing with the curve take the rate provider account and replace this
+//! curve with a [RedemptionRateCurve](super::redemption_rate::RedemptionRateCurve)
+//! holding its rates, so the calculator itself doesn't price anything.
+
+use {
+    crate::{
+        curve::calculator::{
+            CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult, TradeDirection,
+            TradingTokenResult,
+        },
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+        pubkey::Pubkey,
+    },
+    spl_math::precise_number::PreciseNumber,
+};
+
+/// RateProviderCurve struct implementing CurveCalculator
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RateProviderCurve {
+    /// Rate provider account holding the rates of the curve
+    pub rate_provider: Pubkey,
+}
+
+impl CurveCalculator for RateProviderCurve {
+    fn swap_without_fees(
+        &self,
+        _source_amount: u128,
+        _swap_source_amount: u128,
+        _swap_destination_amount: u128,
+        _trade_direction: TradeDirection,
+        _timestamp: Option<u128>,
+    ) -> Option<SwapWithoutFeesResult> {
+        None
+    }
+
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        _pool_tokens: u128,
+        _pool_token_supply: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        None
+    }
+
+    fn deposit_single_token_type(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+        _timestamp: Option<u128>,
+    ) -> Option<u128> {
+        None
+    }
+
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+        _round_direction: RoundDirection,
+        _timestamp: Option<u128>,
+    ) -> Option<u128> {
+        None
+    }
+
+    fn validate(&self, _timestamp: Option<u128>) -> Result<(), SwapError> {
+        if self.rate_provider == Pubkey::default() {
+            Err(SwapError::InvalidCurve)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn rate_provider(&self) -> Option<&Pubkey> {
+        Some(&self.rate_provider)
+    }
+
+    fn validate_supply(
+        &self,
+        token_a_amount: u64,
+        _token_b_amount: u64
+    ) -> Result<(), SwapError> {
+        if token_a_amount == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+        Ok(())
+    }
+
+    fn normalized_value(
+        &self,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _timestamp: Option<u128>,
+    ) -> Option<PreciseNumber> {
+        None
+    }
+}
+
+impl IsInitialized for RateProviderCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for RateProviderCurve {}
+
+impl Pack for RateProviderCurve {
+    const LEN: usize = 32;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<RateProviderCurve, ProgramError> {
+        let rate_provider = array_ref![input, 0, 32];
+        Ok(Self {
+            rate_provider: Pubkey::new_from_array(*rate_provider),
+        })
+    }
+}
+
+impl DynPack for RateProviderCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let rate_provider = array_mut_ref![output, 0, 32];
+        rate_provider.copy_from_slice(self.rate_provider.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_curve() {
+        let curve = RateProviderCurve {
+            rate_provider: Pubkey::new_unique(),
+        };
+
+        let mut packed = [0u8; RateProviderCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        assert_eq!(packed, curve.rate_provider.to_bytes());
+        let unpacked = RateProviderCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+        assert_eq!(unpacked.rate_provider(), Some(&curve.rate_provider));
+    }
+
+    #[test]
+    fn validate_curve() {
+        let curve = RateProviderCurve::default();
+        assert_eq!(curve.validate(None), Err(SwapError::InvalidCurve));
+
+        let curve = RateProviderCurve {
+            rate_provider: Pubkey::new_unique(),
+        };
+        assert_eq!(curve.validate(None), Ok(()));
+        assert_eq!(curve.validate_supply(1, 0), Ok(()));
+        assert_eq!(curve.validate_supply(0, 1), Err(SwapError::EmptySupply));
+        // pricing is done with the rates of the rate provider
+        assert_eq!(
+            curve.swap_without_fees(100, 1_000, 1_000, TradeDirection::AtoB, Some(0)),
+            None
+        );
+    }
+}
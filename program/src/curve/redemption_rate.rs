@@ -65,6 +65,17 @@ pub fn trading_tokens_to_pool_tokens(
     }
 }
 
+/// Optional bounds on rate updates, configured per pool
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RateUpdateLimits {
+    /// Maximum change of ssr in a single update, zero if unbounded
+    pub max_ssr_delta: u128,
+    /// Minimum number of seconds between two updates, zero if unbounded
+    pub min_update_interval: u64,
+    /// Timestamp of the last update, zero if unknown
+    pub last_update: u64,
+}
+
 /// RedemptionRateCurve struct implementing CurveCalculator
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct RedemptionRateCurve {
@@ -158,6 +169,7 @@ impl RedemptionRateCurve {
         rho: u128,
         chi: u128,
         current_timestamp: u128,
+        limits: &RateUpdateLimits,
     ) -> Result<RedemptionRateCurve, ProgramError> {
         if rho > current_timestamp {
             return Err(SwapError::InvalidRho.into())
@@ -168,6 +180,15 @@ impl RedemptionRateCurve {
         if self.max_ssr != 0 && ssr > self.max_ssr {
             return Err(SwapError::InvalidSsr.into())
         }
+        if limits.min_update_interval != 0 && limits.last_update != 0 {
+            let elapsed = current_timestamp
+                .checked_sub(u128::from(limits.last_update))
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            if elapsed < u128::from(limits.min_update_interval) {
+                return Err(SwapError::RateUpdateTooFrequent.into())
+            }
+        }
 
         let new_calculator = if self.rho == 0 {
             RedemptionRateCurve {
@@ -183,6 +204,9 @@ impl RedemptionRateCurve {
             if chi < self.chi {
                 return Err(SwapError::InvalidChi.into())
             }
+            if limits.max_ssr_delta != 0 && ssr.abs_diff(self.ssr) > limits.max_ssr_delta {
+                return Err(SwapError::SsrDeltaExceeded.into())
+            }
             if self.max_ssr != 0 {
                 let duration = rho
                     .checked_sub(self.rho)
@@ -211,6 +235,24 @@ impl RedemptionRateCurve {
 
         Ok(new_calculator)
     }
+
+    /// Set a new max_ssr and returns a new RedemptionRateCurve,
+    /// zero removes the bound
+    pub fn set_max_ssr(
+        &self,
+        max_ssr: u128,
+    ) -> Result<RedemptionRateCurve, ProgramError> {
+        if max_ssr != 0 && (max_ssr < RAY || max_ssr < self.ssr) {
+            return Err(SwapError::InvalidSsr.into())
+        }
+
+        Ok(RedemptionRateCurve {
+            max_ssr,
+            ssr: self.ssr,
+            rho: self.rho,
+            chi: self.chi
+        })
+    }
 }
 
 impl CurveCalculator for RedemptionRateCurve {
@@ -255,6 +297,38 @@ impl CurveCalculator for RedemptionRateCurve {
         })
     }
 
+    /// Round the source amount up, so the pool never gives out more than
+    /// it is paid for.
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        _swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        timestamp: Option<u128>
+    ) -> Option<SwapWithoutFeesResult> {
+        let token_b_price = self.get_conversion_rate(timestamp?)?;
+        let destination_amount = U256::from(destination_amount);
+        let ray = U256::from(RAY);
+
+        let (source_amount, _) = match trade_direction {
+            TradeDirection::BtoA => destination_amount
+                .checked_mul(ray)?
+                .checked_ceil_div(token_b_price)?,
+            TradeDirection::AtoB => destination_amount
+                .checked_mul(token_b_price)?
+                .checked_ceil_div(ray)?,
+        };
+
+        let source_amount_swapped = map_zero_to_none(source_amount.as_u128())?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount.as_u128())?;
+
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
     /// Convert `pool_tokens` into the proportional amounts of each trading token,
     /// given current pool reserves and total pool-token supply.
     /// Rounds according to `round_direction`.
@@ -353,6 +427,33 @@ impl CurveCalculator for RedemptionRateCurve {
         }
     }
 
+    fn validate_staleness(
+        &self,
+        timestamp: Option<u128>,
+        max_staleness: u64
+    ) -> Result<(), SwapError> {
+        if max_staleness == 0 {
+            return Ok(());
+        }
+
+        let timestamp = timestamp
+            .ok_or(SwapError::MissingTimestamp)?;
+
+        let age = timestamp
+            .checked_sub(self.rho)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        if age > u128::from(max_staleness) {
+            Err(SwapError::StaleRate)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn conversion_rate(&self, timestamp: Option<u128>) -> Option<u128> {
+        self.get_conversion_rate(timestamp?)?.try_into().ok()
+    }
+
     fn validate_supply(
         &self, 
         token_a_amount: u64, 
@@ -774,6 +875,7 @@ mod tests {
             test::{
                 check_curve_value_from_swap, 
                 check_deposit_token_conversion, 
+                check_swap_exact_out,
                 check_withdraw_token_conversion, 
                 total_and_intermediate, 
                 CONVERSION_BASIS_POINTS_GUARANTEE
@@ -926,7 +1028,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP, 
             RAY, 
-            INITIAL_TIMESTAMP+1
+            INITIAL_TIMESTAMP+1, 
+            &RateUpdateLimits::default()
         ).unwrap();
         
         // Should fail when rho decreases
@@ -934,7 +1037,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP - 1,
             RAY, 
-            INITIAL_TIMESTAMP + 1
+            INITIAL_TIMESTAMP + 1, 
+            &RateUpdateLimits::default()
         )
         .is_err());
         
@@ -943,7 +1047,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP,
             RAY, 
-            INITIAL_TIMESTAMP+1
+            INITIAL_TIMESTAMP+1, 
+            &RateUpdateLimits::default()
         )
         .unwrap();
     }
@@ -961,7 +1066,8 @@ mod tests {
         assert!(curve.set_rates(
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP + 1, 1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         )
         .is_err());
         
@@ -970,7 +1076,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP, 
             1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
     }
 
@@ -988,7 +1095,8 @@ mod tests {
             RAY - 1, 
             INITIAL_TIMESTAMP, 
             1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).is_err());
         
         // Should succeed when ssr == RAY
@@ -996,7 +1104,8 @@ mod tests {
             RAY, 
             INITIAL_TIMESTAMP, 
             1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
     }
 
@@ -1014,7 +1123,8 @@ mod tests {
             ONE_HUNDRED_PCT_APY_SSR + 1, 
             INITIAL_TIMESTAMP, 
             1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         )
         .is_err());
         
@@ -1023,7 +1133,8 @@ mod tests {
             ONE_HUNDRED_PCT_APY_SSR, 
             INITIAL_TIMESTAMP, 
             1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         )
         .unwrap();
     }
@@ -1042,7 +1153,8 @@ mod tests {
             2 * RAY, 
             INITIAL_TIMESTAMP, 
             1_030_000_000_000_000_000_000_000_000, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         )
         .unwrap();
     }
@@ -1061,14 +1173,16 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP, 
             RAY, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
         
         // Should fail when chi decreases
         assert!(curve.set_rates(
             FIVE_PCT_APY_SSR, 
             SECOND_TIMESTAMP, RAY - 1, 
-            SECOND_TIMESTAMP
+            SECOND_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).is_err());
         
         // Should succeed when chi stays the same
@@ -1076,7 +1190,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             SECOND_TIMESTAMP, 
             RAY, 
-            SECOND_TIMESTAMP
+            SECOND_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
     }
 
@@ -1094,7 +1209,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP, 
             RAY, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
 
         // Calculate max chi growth for 1 year at max SSR
@@ -1110,7 +1226,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             SECOND_TIMESTAMP, 
             chi_max_u128 + 1, 
-            SECOND_TIMESTAMP
+            SECOND_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).is_err());
         
         // Should succeed at max allowed chi
@@ -1118,7 +1235,8 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             SECOND_TIMESTAMP, 
             chi_max_u128, 
-            SECOND_TIMESTAMP
+            SECOND_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
     }
 
@@ -1136,10 +1254,179 @@ mod tests {
             FIVE_PCT_APY_SSR, 
             INITIAL_TIMESTAMP, 
             100_000 * RAY, 
-            INITIAL_TIMESTAMP
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
         ).unwrap();
     }
 
+    #[test]
+    fn test_set_max_ssr_boundary() {
+        let curve = create_test_curve(
+            FIVE_PCT_APY_SSR, 
+            INITIAL_TIMESTAMP, 
+            RAY, 
+            ONE_HUNDRED_PCT_APY_SSR
+        );
+
+        // Should fail when max_ssr is below the current ssr
+        assert_eq!(
+            curve.set_max_ssr(FIVE_PCT_APY_SSR - 1).unwrap_err(),
+            SwapError::InvalidSsr.into()
+        );
+
+        // Should fail when max_ssr is below ray
+        let empty_curve = create_test_curve(0, 0, 0, 0);
+        assert_eq!(
+            empty_curve.set_max_ssr(RAY - 1).unwrap_err(),
+            SwapError::InvalidSsr.into()
+        );
+
+        // Should succeed when max_ssr equals the current ssr,
+        // keeping the rates unchanged
+        let updated = curve.set_max_ssr(FIVE_PCT_APY_SSR).unwrap();
+        assert_eq!(
+            updated,
+            create_test_curve(FIVE_PCT_APY_SSR, INITIAL_TIMESTAMP, RAY, FIVE_PCT_APY_SSR)
+        );
+
+        // Setting ssr above the new bound now fails
+        assert!(updated.set_rates(
+            FIVE_PCT_APY_SSR + 1, 
+            INITIAL_TIMESTAMP, 
+            RAY, 
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
+        )
+        .is_err());
+
+        // Should succeed when removing the bound
+        let unbounded = updated.set_max_ssr(0).unwrap();
+        assert_eq!(unbounded.max_ssr, 0);
+        unbounded.set_rates(
+            ONE_HUNDRED_PCT_APY_SSR, 
+            INITIAL_TIMESTAMP, 
+            RAY, 
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits::default()
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_set_rates_update_limits_boundary() {
+        let max_ssr_delta = 1_000_000_000;
+        let min_update_interval = 3_600;
+        let curve = create_test_curve(
+            FIVE_PCT_APY_SSR, 
+            INITIAL_TIMESTAMP, 
+            RAY, 
+            0
+        );
+        let limits = RateUpdateLimits {
+            max_ssr_delta,
+            min_update_interval,
+            last_update: INITIAL_TIMESTAMP as u64,
+        };
+        let next_update = INITIAL_TIMESTAMP + min_update_interval as u128;
+
+        // Should fail when the ssr moves more than the delta, either way
+        assert_eq!(
+            curve.set_rates(
+                FIVE_PCT_APY_SSR + max_ssr_delta + 1, 
+                next_update, 
+                RAY, 
+                next_update, 
+                &limits
+            ).unwrap_err(),
+            SwapError::SsrDeltaExceeded.into()
+        );
+        assert_eq!(
+            curve.set_rates(
+                FIVE_PCT_APY_SSR - max_ssr_delta - 1, 
+                next_update, 
+                RAY, 
+                next_update, 
+                &limits
+            ).unwrap_err(),
+            SwapError::SsrDeltaExceeded.into()
+        );
+
+        // Should fail before the minimum interval since the last update
+        assert_eq!(
+            curve.set_rates(
+                FIVE_PCT_APY_SSR, 
+                next_update - 1, 
+                RAY, 
+                next_update - 1, 
+                &limits
+            ).unwrap_err(),
+            SwapError::RateUpdateTooFrequent.into()
+        );
+
+        // Should succeed at both bounds
+        curve.set_rates(
+            FIVE_PCT_APY_SSR + max_ssr_delta, 
+            next_update, 
+            RAY, 
+            next_update, 
+            &limits
+        ).unwrap();
+
+        // The delta doesn't apply to the first rates of the curve
+        let empty_curve = create_test_curve(0, 0, 0, 0);
+        empty_curve.set_rates(
+            FIVE_PCT_APY_SSR, 
+            INITIAL_TIMESTAMP, 
+            RAY, 
+            INITIAL_TIMESTAMP, 
+            &RateUpdateLimits {
+                last_update: 0,
+                ..limits
+            }
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_validate_staleness_boundary() {
+        let max_staleness = 3_600;
+        let curve = create_test_curve(
+            FIVE_PCT_APY_SSR, 
+            INITIAL_TIMESTAMP, 
+            RAY, 
+            0
+        );
+
+        // Disabled when no limit is configured
+        curve.validate_staleness(
+            Some(INITIAL_TIMESTAMP + SECONDS_PER_YEAR), 
+            0
+        ).unwrap();
+
+        // Should succeed up to and including the limit
+        curve.validate_staleness(
+            Some(INITIAL_TIMESTAMP), 
+            max_staleness
+        ).unwrap();
+        curve.validate_staleness(
+            Some(INITIAL_TIMESTAMP + max_staleness as u128), 
+            max_staleness
+        ).unwrap();
+
+        // Should fail once the limit is exceeded
+        assert_eq!(
+            curve.validate_staleness(
+                Some(INITIAL_TIMESTAMP + max_staleness as u128 + 1), 
+                max_staleness
+            ),
+            Err(SwapError::StaleRate)
+        );
+
+        // Should fail without a timestamp
+        assert_eq!(
+            curve.validate_staleness(None, max_staleness),
+            Err(SwapError::MissingTimestamp)
+        );
+    }
+
     #[test]
     fn swap_calculation_no_price() {
         let swap_source_amount: u128 = 0;
@@ -1464,6 +1751,27 @@ mod tests {
         }
     }
 
+    proptest! {
+        #[test]
+        fn swap_exact_out_rounds_source_up(
+            destination_token_amount in 1..u64::MAX,
+            chi in RAY..2 * RAY,
+            elapsed in 0..SECONDS_PER_YEAR,
+        ) {
+            let curve = create_test_curve(FIVE_PCT_APY_SSR, 0, chi, 0);
+            for trade_direction in [TradeDirection::AtoB, TradeDirection::BtoA] {
+                check_swap_exact_out(
+                    &curve,
+                    destination_token_amount as u128,
+                    u64::MAX as u128,
+                    u64::MAX as u128,
+                    trade_direction,
+                    Some(elapsed)
+                );
+            }
+        }
+    }
+
     proptest! {
         #[test]
         fn curve_value_does_not_decrease_from_deposit(
@@ -152,7 +152,69 @@ pub enum SwapError {
     MismatchedMintDecimals,
     /// The token account is not associated with the expected mint.
     #[error("Account mint does not match expected mint")]
-    InvalidAccountMint
+    InvalidAccountMint,
+    /// The pool is paused.
+    #[error("Pool is paused")]
+    PoolPaused,
+
+    // 40.
+    /// The swap account has to be migrated to the latest version.
+    #[error("Swap account must be migrated to the latest version")]
+    SwapMigrationRequired,
+    /// The redemption rate has not been updated within the pool's maximum staleness.
+    #[error("Redemption rate is stale")]
+    StaleRate,
+    /// The super admin handover has no matching pending authority.
+    #[error("Invalid pending super admin")]
+    InvalidPendingSuperAdmin,
+    /// The change has to be queued while the pool has a timelock delay.
+    #[error("Change must go through the timelock")]
+    TimelockRequired,
+    /// The queued change can't be executed before its eta.
+    #[error("Timelock has not elapsed")]
+    TimelockNotElapsed,
+
+    // 45.
+    /// The ssr changes more than the pool's maximum delta in one update.
+    #[error("SSR change exceeds the maximum delta")]
+    SsrDeltaExceeded,
+    /// The rates were updated less than the pool's minimum interval ago.
+    #[error("Rates updated too frequently")]
+    RateUpdateTooFrequent,
+    /// The pool still has liquidity besides the locked initial supply.
+    #[error("Pool still has liquidity")]
+    PoolNotEmpty,
+    /// Conversions in this direction are disabled on the pool.
+    #[error("Swap direction is disabled")]
+    SwapDirectionDisabled,
+    /// The pool is allowlisted and the user has no valid allowlist entry.
+    #[error("User is not allowlisted")]
+    NotAllowlisted,
+
+    // 50.
+    /// The swap exceeds the volume currently allowed in its direction.
+    #[error("Swap rate limit exceeded")]
+    SwapRateLimitExceeded,
+    /// The deposit would push the pool value above its cap.
+    #[error("Pool value cap exceeded")]
+    MaxTvlExceeded,
+    /// The provided rate provider is not the one of the pool's curve.
+    #[error("Incorrect rate provider")]
+    IncorrectRateProvider,
+    /// The relayed rates aren't verified by the preceding Ed25519 instruction.
+    #[error("Invalid signed rates")]
+    InvalidSignedRates,
+    /// The nonce of the relayed rates isn't greater than the last one.
+    #[error("Nonce already used")]
+    NonceAlreadyUsed,
+
+    // 55.
+    /// The rates weren't sent by the configured bridge receiver.
+    #[error("Invalid bridge receiver")]
+    InvalidBridgeReceiver,
+    /// Super admin permissions can only be given up through the handover.
+    #[error("Super admin requires a handover")]
+    SuperAdminHandoverRequired,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
@@ -265,6 +327,60 @@ impl PrintProgramError for SwapError {
             },
             SwapError::InvalidAccountMint => {
                 msg!("Error: Account mint does not match expected mint")
+            },
+            SwapError::PoolPaused => {
+                msg!("Error: Pool is paused")
+            },
+            SwapError::SwapMigrationRequired => {
+                msg!("Error: Swap account must be migrated to the latest version")
+            },
+            SwapError::StaleRate => {
+                msg!("Error: Redemption rate is stale")
+            },
+            SwapError::InvalidPendingSuperAdmin => {
+                msg!("Error: Invalid pending super admin")
+            },
+            SwapError::TimelockRequired => {
+                msg!("Error: Change must go through the timelock")
+            },
+            SwapError::TimelockNotElapsed => {
+                msg!("Error: Timelock has not elapsed")
+            },
+            SwapError::SsrDeltaExceeded => {
+                msg!("Error: SSR change exceeds the maximum delta")
+            },
+            SwapError::RateUpdateTooFrequent => {
+                msg!("Error: Rates updated too frequently")
+            },
+            SwapError::PoolNotEmpty => {
+                msg!("Error: Pool still has liquidity")
+            },
+            SwapError::SwapDirectionDisabled => {
+                msg!("Error: Swap direction is disabled")
+            },
+            SwapError::NotAllowlisted => {
+                msg!("Error: User is not allowlisted")
+            },
+            SwapError::SwapRateLimitExceeded => {
+                msg!("Error: Swap rate limit exceeded")
+            },
+            SwapError::MaxTvlExceeded => {
+                msg!("Error: Pool value cap exceeded")
+            },
+            SwapError::IncorrectRateProvider => {
+                msg!("Error: Address of the provided rate provider is incorrect")
+            },
+            SwapError::InvalidSignedRates => {
+                msg!("Error: Rates aren't verified by the preceding Ed25519 instruction")
+            },
+            SwapError::NonceAlreadyUsed => {
+                msg!("Error: Nonce of the signed rates was already used")
+            },
+            SwapError::InvalidBridgeReceiver => {
+                msg!("Error: Rates must be sent through CPI by the configured bridge receiver")
+            },
+            SwapError::SuperAdminHandoverRequired => {
+                msg!("Error: Super admin permissions can only be handed over with ProposeSuperAdmin and AcceptSuperAdmin")
             }
         }
     }
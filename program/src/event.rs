@@ -0,0 +1,734 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Structured events emitted with `sol_log_data`, so indexers can follow
+//! swaps, liquidity and administration of pools without scraping logs.
+//!
+//! Every event is a single data field: the event version, the event tag and
+//! the packed event. RPC nodes show it base64 encoded in a `Program data:`
+//! log line, and [Event::unpack](Event::unpack) decodes the raw bytes.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    log::sol_log_data,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use crate::{curve::calculator::TradeDirection, psm3::Psm3Asset};
+
+/// Swap executed by `Swap` or `SwapExactAmountOut`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapEvent {
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// User transfer authority signing the swap
+    pub user: Pubkey,
+    /// Direction of the swap
+    pub trade_direction: TradeDirection,
+    /// Amount of source tokens transferred from the user, fees included
+    pub amount_in: u64,
+    /// Amount of destination tokens transferred to the user
+    pub amount_out: u64,
+    /// Amount of source tokens going to pool holders
+    pub trade_fee: u64,
+    /// Amount of source tokens going to the owner
+    pub owner_fee: u64,
+    /// Conversion rate used by the curve, zero if it has none
+    pub conversion_rate: u128,
+    /// Referral code of the integrator
+    pub referral_code: Option<u64>,
+}
+
+/// Deposit of one or both token types
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositEvent {
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// User transfer authority signing the deposit
+    pub user: Pubkey,
+    /// Amount of token A deposited
+    pub token_a_amount: u64,
+    /// Amount of token B deposited
+    pub token_b_amount: u64,
+    /// Amount of pool tokens minted to the user
+    pub pool_token_amount: u64,
+    /// Amount of deposited tokens going to the owner
+    pub owner_fee: u64,
+    /// Conversion rate used by the curve, zero if it has none
+    pub conversion_rate: u128,
+    /// Referral code of the integrator
+    pub referral_code: Option<u64>,
+}
+
+/// Withdrawal of one or both token types
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithdrawEvent {
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// User transfer authority signing the withdrawal
+    pub user: Pubkey,
+    /// Amount of token A withdrawn
+    pub token_a_amount: u64,
+    /// Amount of token B withdrawn
+    pub token_b_amount: u64,
+    /// Amount of pool tokens taken from the user, withdraw fee included
+    pub pool_token_amount: u64,
+    /// Amount of pool tokens going to the owner
+    pub withdraw_fee: u64,
+    /// Conversion rate used by the curve, zero if it has none
+    pub conversion_rate: u128,
+}
+
+/// Update of the rates of a `RedemptionRate` curve
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetRatesEvent {
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// Signer of the update, or the rate multisig account when its signers
+    /// updated the rates
+    pub authority: Pubkey,
+    /// New ssr
+    pub ssr: u128,
+    /// New rho
+    pub rho: u128,
+    /// New chi
+    pub chi: u128,
+}
+
+/// Creation or update of a permission account
+#[derive(Clone, Debug, PartialEq)]
+pub struct PermissionEvent {
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// The permission account address
+    pub permission: Pubkey,
+    /// The authority of the permission
+    pub authority: Pubkey,
+    /// Bitmask of the granted `roles`
+    pub roles: u64,
+    /// Super admin signing the instruction
+    pub signer: Pubkey,
+}
+
+/// Swap executed by `Psm3Swap`, the three-asset counterpart of `SwapEvent`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psm3SwapEvent {
+    /// The three-asset pool address
+    pub pool: Pubkey,
+    /// User transfer authority signing the swap
+    pub user: Pubkey,
+    /// Asset transferred from the user
+    pub source: Psm3Asset,
+    /// Asset transferred to the user
+    pub destination: Psm3Asset,
+    /// Amount of source tokens transferred from the user
+    pub amount_in: u64,
+    /// Amount of destination tokens transferred to the user
+    pub amount_out: u64,
+    /// Conversion rate of sUSDS used by the pool
+    pub conversion_rate: u128,
+}
+
+/// Deposit or withdrawal of one asset of a three-asset pool
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psm3LiquidityEvent {
+    /// The three-asset pool address
+    pub pool: Pubkey,
+    /// User transfer authority signing the deposit or withdrawal
+    pub user: Pubkey,
+    /// Asset deposited or withdrawn
+    pub asset: Psm3Asset,
+    /// Amount of the asset deposited or withdrawn
+    pub asset_amount: u64,
+    /// Amount of pool tokens minted to or burned from the user
+    pub pool_token_amount: u64,
+    /// Conversion rate of sUSDS used by the pool
+    pub conversion_rate: u128,
+}
+
+/// Events emitted by the program
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// Tag 0
+    Swap(SwapEvent),
+    /// Tag 1
+    Deposit(DepositEvent),
+    /// Tag 2
+    Withdraw(WithdrawEvent),
+    /// Tag 3
+    SetRates(SetRatesEvent),
+    /// Tag 4
+    InitializePermission(PermissionEvent),
+    /// Tag 5
+    UpdatePermission(PermissionEvent),
+    /// Tag 6
+    Psm3Swap(Psm3SwapEvent),
+    /// Tag 7
+    Psm3Deposit(Psm3LiquidityEvent),
+    /// Tag 8
+    Psm3Withdraw(Psm3LiquidityEvent),
+}
+
+impl Event {
+    /// Version of the event layouts, bumped whenever one of them changes
+    pub const VERSION: u8 = 1;
+
+    /// Packs the event with its version and tag
+    pub fn pack(&self) -> Vec<u8> {
+        let (tag, len) = match self {
+            Self::Swap(_) => (0, SwapEvent::LEN),
+            Self::Deposit(_) => (1, DepositEvent::LEN),
+            Self::Withdraw(_) => (2, WithdrawEvent::LEN),
+            Self::SetRates(_) => (3, SetRatesEvent::LEN),
+            Self::InitializePermission(_) => (4, PermissionEvent::LEN),
+            Self::UpdatePermission(_) => (5, PermissionEvent::LEN),
+            Self::Psm3Swap(_) => (6, Psm3SwapEvent::LEN),
+            Self::Psm3Deposit(_) => (7, Psm3LiquidityEvent::LEN),
+            Self::Psm3Withdraw(_) => (8, Psm3LiquidityEvent::LEN),
+        };
+
+        let mut buf = vec![0u8; 2 + len];
+        buf[0] = Self::VERSION;
+        buf[1] = tag;
+        let output = &mut buf[2..];
+        match self {
+            Self::Swap(event) => event.pack_into_slice(output),
+            Self::Deposit(event) => event.pack_into_slice(output),
+            Self::Withdraw(event) => event.pack_into_slice(output),
+            Self::SetRates(event) => event.pack_into_slice(output),
+            Self::InitializePermission(event) => event.pack_into_slice(output),
+            Self::UpdatePermission(event) => event.pack_into_slice(output),
+            Self::Psm3Swap(event) => event.pack_into_slice(output),
+            Self::Psm3Deposit(event) => event.pack_into_slice(output),
+            Self::Psm3Withdraw(event) => event.pack_into_slice(output),
+        }
+        buf
+    }
+
+    /// Unpacks an event from the data logged by the program
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&version, rest) = input.split_first().ok_or(ProgramError::InvalidAccountData)?;
+        if version != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        let (&tag, rest) = rest.split_first().ok_or(ProgramError::InvalidAccountData)?;
+
+        Ok(match tag {
+            0 => Self::Swap(SwapEvent::unpack_from_slice(checked_slice::<SwapEvent>(rest)?)?),
+            1 => Self::Deposit(DepositEvent::unpack_from_slice(checked_slice::<DepositEvent>(rest)?)?),
+            2 => Self::Withdraw(WithdrawEvent::unpack_from_slice(checked_slice::<WithdrawEvent>(rest)?)?),
+            3 => Self::SetRates(SetRatesEvent::unpack_from_slice(checked_slice::<SetRatesEvent>(rest)?)?),
+            4 => Self::InitializePermission(
+                PermissionEvent::unpack_from_slice(checked_slice::<PermissionEvent>(rest)?)?
+            ),
+            5 => Self::UpdatePermission(
+                PermissionEvent::unpack_from_slice(checked_slice::<PermissionEvent>(rest)?)?
+            ),
+            6 => Self::Psm3Swap(Psm3SwapEvent::unpack_from_slice(checked_slice::<Psm3SwapEvent>(rest)?)?),
+            7 => Self::Psm3Deposit(
+                Psm3LiquidityEvent::unpack_from_slice(checked_slice::<Psm3LiquidityEvent>(rest)?)?
+            ),
+            8 => Self::Psm3Withdraw(
+                Psm3LiquidityEvent::unpack_from_slice(checked_slice::<Psm3LiquidityEvent>(rest)?)?
+            ),
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+
+    /// Logs the event with `sol_log_data`
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+}
+
+fn checked_slice<T: Pack>(input: &[u8]) -> Result<&[u8], ProgramError> {
+    if input.len() != T::LEN {
+        return Err(ProgramError::InvalidAccountData)
+    }
+    Ok(input)
+}
+
+fn pack_trade_direction(trade_direction: TradeDirection) -> u8 {
+    match trade_direction {
+        TradeDirection::AtoB => 0,
+        TradeDirection::BtoA => 1,
+    }
+}
+
+fn unpack_trade_direction(input: u8) -> Result<TradeDirection, ProgramError> {
+    match input {
+        0 => Ok(TradeDirection::AtoB),
+        1 => Ok(TradeDirection::BtoA),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn unpack_psm3_asset(input: u8) -> Result<Psm3Asset, ProgramError> {
+    Psm3Asset::ALL
+        .get(usize::from(input))
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn pack_referral_code(referral_code: Option<u64>, output: &mut [u8; 9]) {
+    let (is_some, code) = mut_array_refs![output, 1, 8];
+    is_some[0] = referral_code.is_some() as u8;
+    *code = referral_code.unwrap_or_default().to_le_bytes();
+}
+
+fn unpack_referral_code(input: &[u8; 9]) -> Result<Option<u64>, ProgramError> {
+    let (is_some, code) = array_refs![input, 1, 8];
+    match is_some[0] {
+        0 => Ok(None),
+        1 => Ok(Some(u64::from_le_bytes(*code))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+impl IsInitialized for SwapEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for SwapEvent {}
+impl Pack for SwapEvent {
+    const LEN: usize = 122;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 122];
+        let (
+            swap,
+            user,
+            trade_direction,
+            amount_in,
+            amount_out,
+            trade_fee,
+            owner_fee,
+            conversion_rate,
+            referral_code,
+        ) = mut_array_refs![output, 32, 32, 1, 8, 8, 8, 8, 16, 9];
+        swap.copy_from_slice(self.swap.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        trade_direction[0] = pack_trade_direction(self.trade_direction);
+        *amount_in = self.amount_in.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+        *trade_fee = self.trade_fee.to_le_bytes();
+        *owner_fee = self.owner_fee.to_le_bytes();
+        *conversion_rate = self.conversion_rate.to_le_bytes();
+        pack_referral_code(self.referral_code, referral_code);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<SwapEvent, ProgramError> {
+        let input = array_ref![input, 0, 122];
+        let (
+            swap,
+            user,
+            trade_direction,
+            amount_in,
+            amount_out,
+            trade_fee,
+            owner_fee,
+            conversion_rate,
+            referral_code,
+        ) = array_refs![input, 32, 32, 1, 8, 8, 8, 8, 16, 9];
+        Ok(Self {
+            swap: Pubkey::new_from_array(*swap),
+            user: Pubkey::new_from_array(*user),
+            trade_direction: unpack_trade_direction(trade_direction[0])?,
+            amount_in: u64::from_le_bytes(*amount_in),
+            amount_out: u64::from_le_bytes(*amount_out),
+            trade_fee: u64::from_le_bytes(*trade_fee),
+            owner_fee: u64::from_le_bytes(*owner_fee),
+            conversion_rate: u128::from_le_bytes(*conversion_rate),
+            referral_code: unpack_referral_code(referral_code)?,
+        })
+    }
+}
+
+impl IsInitialized for DepositEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for DepositEvent {}
+impl Pack for DepositEvent {
+    const LEN: usize = 121;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 121];
+        let (
+            swap,
+            user,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            owner_fee,
+            conversion_rate,
+            referral_code,
+        ) = mut_array_refs![output, 32, 32, 8, 8, 8, 8, 16, 9];
+        swap.copy_from_slice(self.swap.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        *token_a_amount = self.token_a_amount.to_le_bytes();
+        *token_b_amount = self.token_b_amount.to_le_bytes();
+        *pool_token_amount = self.pool_token_amount.to_le_bytes();
+        *owner_fee = self.owner_fee.to_le_bytes();
+        *conversion_rate = self.conversion_rate.to_le_bytes();
+        pack_referral_code(self.referral_code, referral_code);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<DepositEvent, ProgramError> {
+        let input = array_ref![input, 0, 121];
+        let (
+            swap,
+            user,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            owner_fee,
+            conversion_rate,
+            referral_code,
+        ) = array_refs![input, 32, 32, 8, 8, 8, 8, 16, 9];
+        Ok(Self {
+            swap: Pubkey::new_from_array(*swap),
+            user: Pubkey::new_from_array(*user),
+            token_a_amount: u64::from_le_bytes(*token_a_amount),
+            token_b_amount: u64::from_le_bytes(*token_b_amount),
+            pool_token_amount: u64::from_le_bytes(*pool_token_amount),
+            owner_fee: u64::from_le_bytes(*owner_fee),
+            conversion_rate: u128::from_le_bytes(*conversion_rate),
+            referral_code: unpack_referral_code(referral_code)?,
+        })
+    }
+}
+
+impl IsInitialized for WithdrawEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for WithdrawEvent {}
+impl Pack for WithdrawEvent {
+    const LEN: usize = 112;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 112];
+        let (
+            swap,
+            user,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            withdraw_fee,
+            conversion_rate,
+        ) = mut_array_refs![output, 32, 32, 8, 8, 8, 8, 16];
+        swap.copy_from_slice(self.swap.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        *token_a_amount = self.token_a_amount.to_le_bytes();
+        *token_b_amount = self.token_b_amount.to_le_bytes();
+        *pool_token_amount = self.pool_token_amount.to_le_bytes();
+        *withdraw_fee = self.withdraw_fee.to_le_bytes();
+        *conversion_rate = self.conversion_rate.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<WithdrawEvent, ProgramError> {
+        let input = array_ref![input, 0, 112];
+        let (
+            swap,
+            user,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            withdraw_fee,
+            conversion_rate,
+        ) = array_refs![input, 32, 32, 8, 8, 8, 8, 16];
+        Ok(Self {
+            swap: Pubkey::new_from_array(*swap),
+            user: Pubkey::new_from_array(*user),
+            token_a_amount: u64::from_le_bytes(*token_a_amount),
+            token_b_amount: u64::from_le_bytes(*token_b_amount),
+            pool_token_amount: u64::from_le_bytes(*pool_token_amount),
+            withdraw_fee: u64::from_le_bytes(*withdraw_fee),
+            conversion_rate: u128::from_le_bytes(*conversion_rate),
+        })
+    }
+}
+
+impl IsInitialized for SetRatesEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for SetRatesEvent {}
+impl Pack for SetRatesEvent {
+    const LEN: usize = 112;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 112];
+        let (swap, authority, ssr, rho, chi) = mut_array_refs![output, 32, 32, 16, 16, 16];
+        swap.copy_from_slice(self.swap.as_ref());
+        authority.copy_from_slice(self.authority.as_ref());
+        *ssr = self.ssr.to_le_bytes();
+        *rho = self.rho.to_le_bytes();
+        *chi = self.chi.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<SetRatesEvent, ProgramError> {
+        let input = array_ref![input, 0, 112];
+        let (swap, authority, ssr, rho, chi) = array_refs![input, 32, 32, 16, 16, 16];
+        Ok(Self {
+            swap: Pubkey::new_from_array(*swap),
+            authority: Pubkey::new_from_array(*authority),
+            ssr: u128::from_le_bytes(*ssr),
+            rho: u128::from_le_bytes(*rho),
+            chi: u128::from_le_bytes(*chi),
+        })
+    }
+}
+
+impl IsInitialized for PermissionEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for PermissionEvent {}
+impl Pack for PermissionEvent {
+    const LEN: usize = 136;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 136];
+        let (swap, permission, authority, roles, signer) =
+            mut_array_refs![output, 32, 32, 32, 8, 32];
+        swap.copy_from_slice(self.swap.as_ref());
+        permission.copy_from_slice(self.permission.as_ref());
+        authority.copy_from_slice(self.authority.as_ref());
+        *roles = self.roles.to_le_bytes();
+        signer.copy_from_slice(self.signer.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<PermissionEvent, ProgramError> {
+        let input = array_ref![input, 0, 136];
+        let (swap, permission, authority, roles, signer) =
+            array_refs![input, 32, 32, 32, 8, 32];
+        Ok(Self {
+            swap: Pubkey::new_from_array(*swap),
+            permission: Pubkey::new_from_array(*permission),
+            authority: Pubkey::new_from_array(*authority),
+            roles: u64::from_le_bytes(*roles),
+            signer: Pubkey::new_from_array(*signer),
+        })
+    }
+}
+
+impl IsInitialized for Psm3SwapEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for Psm3SwapEvent {}
+impl Pack for Psm3SwapEvent {
+    const LEN: usize = 98;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 98];
+        let (pool, user, source, destination, amount_in, amount_out, conversion_rate) =
+            mut_array_refs![output, 32, 32, 1, 1, 8, 8, 16];
+        pool.copy_from_slice(self.pool.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        source[0] = self.source as u8;
+        destination[0] = self.destination as u8;
+        *amount_in = self.amount_in.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+        *conversion_rate = self.conversion_rate.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Psm3SwapEvent, ProgramError> {
+        let input = array_ref![input, 0, 98];
+        let (pool, user, source, destination, amount_in, amount_out, conversion_rate) =
+            array_refs![input, 32, 32, 1, 1, 8, 8, 16];
+        Ok(Self {
+            pool: Pubkey::new_from_array(*pool),
+            user: Pubkey::new_from_array(*user),
+            source: unpack_psm3_asset(source[0])?,
+            destination: unpack_psm3_asset(destination[0])?,
+            amount_in: u64::from_le_bytes(*amount_in),
+            amount_out: u64::from_le_bytes(*amount_out),
+            conversion_rate: u128::from_le_bytes(*conversion_rate),
+        })
+    }
+}
+
+impl IsInitialized for Psm3LiquidityEvent {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for Psm3LiquidityEvent {}
+impl Pack for Psm3LiquidityEvent {
+    const LEN: usize = 97;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 97];
+        let (pool, user, asset, asset_amount, pool_token_amount, conversion_rate) =
+            mut_array_refs![output, 32, 32, 1, 8, 8, 16];
+        pool.copy_from_slice(self.pool.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        asset[0] = self.asset as u8;
+        *asset_amount = self.asset_amount.to_le_bytes();
+        *pool_token_amount = self.pool_token_amount.to_le_bytes();
+        *conversion_rate = self.conversion_rate.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Psm3LiquidityEvent, ProgramError> {
+        let input = array_ref![input, 0, 97];
+        let (pool, user, asset, asset_amount, pool_token_amount, conversion_rate) =
+            array_refs![input, 32, 32, 1, 8, 8, 16];
+        Ok(Self {
+            pool: Pubkey::new_from_array(*pool),
+            user: Pubkey::new_from_array(*user),
+            asset: unpack_psm3_asset(asset[0])?,
+            asset_amount: u64::from_le_bytes(*asset_amount),
+            pool_token_amount: u64::from_le_bytes(*pool_token_amount),
+            conversion_rate: u128::from_le_bytes(*conversion_rate),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<Event> {
+        let swap = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let permission = PermissionEvent {
+            swap,
+            permission: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            roles: 0b1010,
+            signer: user,
+        };
+        vec![
+            Event::Swap(SwapEvent {
+                swap,
+                user,
+                trade_direction: TradeDirection::BtoA,
+                amount_in: 1_000,
+                amount_out: 1_050,
+                trade_fee: 3,
+                owner_fee: 1,
+                conversion_rate: 1_050_000_000_000_000_000_000_000_000,
+                referral_code: Some(42),
+            }),
+            Event::Deposit(DepositEvent {
+                swap,
+                user,
+                token_a_amount: 1_000,
+                token_b_amount: 0,
+                pool_token_amount: 500,
+                owner_fee: 1,
+                conversion_rate: 0,
+                referral_code: None,
+            }),
+            Event::Withdraw(WithdrawEvent {
+                swap,
+                user,
+                token_a_amount: 1_000,
+                token_b_amount: 950,
+                pool_token_amount: 1_001,
+                withdraw_fee: 1,
+                conversion_rate: 1_050_000_000_000_000_000_000_000_000,
+            }),
+            Event::SetRates(SetRatesEvent {
+                swap,
+                authority: user,
+                ssr: 1_000_000_001_547_125_957_863_212_448,
+                rho: 1_700_000_000,
+                chi: 1_050_000_000_000_000_000_000_000_000,
+            }),
+            Event::InitializePermission(permission.clone()),
+            Event::UpdatePermission(permission),
+            Event::Psm3Swap(Psm3SwapEvent {
+                pool: swap,
+                user,
+                source: Psm3Asset::Susds,
+                destination: Psm3Asset::Usdc,
+                amount_in: 1_000,
+                amount_out: 1_050,
+                conversion_rate: 1_050_000_000_000_000_000_000_000_000,
+            }),
+            Event::Psm3Deposit(Psm3LiquidityEvent {
+                pool: swap,
+                user,
+                asset: Psm3Asset::Usds,
+                asset_amount: 1_000,
+                pool_token_amount: 500,
+                conversion_rate: 1_050_000_000_000_000_000_000_000_000,
+            }),
+            Event::Psm3Withdraw(Psm3LiquidityEvent {
+                pool: swap,
+                user,
+                asset: Psm3Asset::Susds,
+                asset_amount: 1_000,
+                pool_token_amount: 525,
+                conversion_rate: 1_050_000_000_000_000_000_000_000_000,
+            }),
+        ]
+    }
+
+    #[test]
+    fn event_pack() {
+        for (tag, event) in events().into_iter().enumerate() {
+            let packed = event.pack();
+            assert_eq!(packed[0], Event::VERSION);
+            assert_eq!(packed[1], tag as u8);
+            assert_eq!(Event::unpack(&packed).unwrap(), event);
+        }
+
+        let swap_event = events().remove(0);
+        let packed = swap_event.pack();
+        let mut expect = vec![Event::VERSION, 0];
+        let Event::Swap(event) = &swap_event else { unreachable!() };
+        expect.extend_from_slice(event.swap.as_ref());
+        expect.extend_from_slice(event.user.as_ref());
+        expect.push(1);
+        expect.extend_from_slice(&event.amount_in.to_le_bytes());
+        expect.extend_from_slice(&event.amount_out.to_le_bytes());
+        expect.extend_from_slice(&event.trade_fee.to_le_bytes());
+        expect.extend_from_slice(&event.owner_fee.to_le_bytes());
+        expect.extend_from_slice(&event.conversion_rate.to_le_bytes());
+        expect.push(1);
+        expect.extend_from_slice(&42u64.to_le_bytes());
+        assert_eq!(packed, expect);
+    }
+
+    #[test]
+    fn event_unpack_invalid() {
+        let packed = events()[0].pack();
+
+        let mut wrong_version = packed.clone();
+        wrong_version[0] = Event::VERSION + 1;
+        assert_eq!(Event::unpack(&wrong_version), Err(ProgramError::InvalidAccountData));
+
+        let mut wrong_tag = packed.clone();
+        wrong_tag[1] = 9;
+        assert_eq!(Event::unpack(&wrong_tag), Err(ProgramError::InvalidAccountData));
+
+        assert_eq!(
+            Event::unpack(&packed[..packed.len() - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(Event::unpack(&[]), Err(ProgramError::InvalidAccountData));
+
+        let mut wrong_asset = events()[6].pack();
+        wrong_asset[2 + 64] = 3;
+        assert_eq!(Event::unpack(&wrong_asset), Err(ProgramError::InvalidAccountData));
+    }
+}
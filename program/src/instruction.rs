@@ -10,14 +10,18 @@ use crate::curve::base::CurveType;
 
 use {
     crate::{
-        curve::{base::SwapCurve, fees::Fees},
+        curve::{base::SwapCurve, fees::Fees, redemption_rate::RedemptionRateCurve},
         error::SwapError,
+        permission::roles,
+        timelock::ParameterChange,
     },
     solana_program::{
         instruction::{AccountMeta, Instruction},
         program_error::ProgramError,
         program_pack::Pack,
         pubkey::Pubkey,
+        system_program,
+        sysvar,
     },
     std::{convert::TryInto, mem::size_of},
 };
@@ -44,6 +48,25 @@ pub struct Swap {
     /// Minimum amount of DESTINATION token to output, prevents excessive
     /// slippage
     pub minimum_amount_out: u64,
+    /// Referral code of the integrator, logged with the swapped amounts.
+    /// Optional trailing field, absent from legacy instruction data
+    pub referral_code: Option<u64>,
+}
+
+/// SwapExactAmountOut instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapExactAmountOut {
+    /// Exact amount of DESTINATION token to output, input from SOURCE is
+    /// based on the exchange rate
+    pub amount_out: u64,
+    /// Maximum amount of SOURCE token to transfer, prevents excessive
+    /// slippage
+    pub maximum_amount_in: u64,
+    /// Referral code of the integrator, logged with the swapped amounts.
+    /// Optional trailing field
+    pub referral_code: Option<u64>,
 }
 
 /// DepositAllTokenTypes instruction data
@@ -58,6 +81,9 @@ pub struct DepositAllTokenTypes {
     pub maximum_token_a_amount: u64,
     /// Maximum token B amount to deposit, prevents excessive slippage
     pub maximum_token_b_amount: u64,
+    /// Referral code of the integrator, logged with the deposited amounts.
+    /// Optional trailing field, absent from legacy instruction data
+    pub referral_code: Option<u64>,
 }
 
 /// WithdrawAllTokenTypes instruction data
@@ -84,6 +110,9 @@ pub struct DepositSingleTokenTypeExactAmountIn {
     /// Pool token amount to receive in exchange. The amount is set by
     /// the current exchange rate and size of the pool
     pub minimum_pool_token_amount: u64,
+    /// Referral code of the integrator, logged with the deposited amounts.
+    /// Optional trailing field, absent from legacy instruction data
+    pub referral_code: Option<u64>,
 }
 
 /// WithdrawSingleTokenTypeExactAmountOut instruction data
@@ -118,10 +147,8 @@ pub struct SetRates {
 pub struct InitializePermission {
     /// the pubkey of the new permission authority
     pub permission_authority: [u8; 32],
-    /// is the new permission super admin
-    pub is_super_admin: bool,
-    /// can it update curve parameters
-    pub can_update_parameters: bool
+    /// bitmask of the granted `permission::roles`
+    pub roles: u64
 }
 
 /// Instruction data for updating a permission account
@@ -129,10 +156,274 @@ pub struct InitializePermission {
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct UpdatePermission {
-    /// is super admin
-    pub is_super_admin: bool,
-    /// can it update curve parameters
-    pub can_update_parameters: bool
+    /// bitmask of the granted `permission::roles`
+    pub roles: u64
+}
+
+/// Instruction data for pausing or unpausing a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetPaused {
+    /// pause the pool if true, unpause it otherwise
+    pub is_paused: bool
+}
+
+/// Instruction data for enabling or disabling each swap direction of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetSwapDirections {
+    /// allow conversions from token A to token B
+    pub a_to_b_enabled: bool,
+    /// allow conversions from token B to token A
+    pub b_to_a_enabled: bool,
+}
+
+/// Instruction data for switching the allowlist mode of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetAllowlistMode {
+    /// deposits and withdrawals require an allowlisted user
+    pub liquidity_allowlisted: bool,
+    /// swaps require an allowlisted user
+    pub swaps_allowlisted: bool,
+}
+
+/// Instruction data for setting the swap volume limits of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetSwapRateLimits {
+    /// maximum amount of token A swapped to token B at once, zero if unbounded
+    pub a_to_b_max_amount: u64,
+    /// amount of token A the A to B limit refills by every second
+    pub a_to_b_slope: u64,
+    /// maximum amount of token B swapped to token A at once, zero if unbounded
+    pub b_to_a_max_amount: u64,
+    /// amount of token B the B to A limit refills by every second
+    pub b_to_a_slope: u64,
+}
+
+/// Instruction data for setting the value cap of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetMaxTvl {
+    /// maximum normalized value of the pool after a deposit, zero if unbounded
+    pub max_tvl: u128,
+}
+
+/// Instruction data for previewing a swap
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewSwap {
+    /// Amount of SOURCE token swapped in, or of DESTINATION token swapped
+    /// out if `exact_out`
+    pub amount: u64,
+    /// Preview a `SwapExactAmountOut` instead of a `Swap`
+    pub exact_out: bool,
+}
+
+/// Instruction data for previewing a single token type deposit
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewDeposit {
+    /// Amount of token A or B to deposit
+    pub source_token_amount: u64,
+}
+
+/// Instruction data for previewing a single token type withdrawal
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewWithdraw {
+    /// Amount of token A or B to receive
+    pub destination_token_amount: u64,
+}
+
+/// Instruction data for getting the conversion rate of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetConversionRate {
+    /// Unix timestamp to extrapolate the rate to, the current one if absent.
+    /// Optional trailing field
+    pub timestamp: Option<u64>,
+}
+
+/// InitializePsm3 instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct InitializePsm3 {
+    /// rates pricing sUSDS in USDS, with the maximum ssr of the pool
+    pub rates: RedemptionRateCurve,
+}
+
+/// Psm3Swap instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psm3Swap {
+    /// SOURCE amount to transfer, output to DESTINATION is based on the
+    /// conversion rate
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token to output, prevents excessive
+    /// slippage
+    pub minimum_amount_out: u64,
+}
+
+/// Psm3Deposit instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psm3Deposit {
+    /// Amount of USDC, USDS or sUSDS to deposit
+    pub source_token_amount: u64,
+    /// Minimum amount of pool tokens to receive, prevents excessive slippage
+    pub minimum_pool_token_amount: u64,
+}
+
+/// Psm3Withdraw instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psm3Withdraw {
+    /// Amount of USDC, USDS or sUSDS to receive
+    pub destination_token_amount: u64,
+    /// Maximum amount of pool tokens to burn, prevents excessive slippage
+    pub maximum_pool_token_amount: u64,
+}
+
+/// InitializeRateProvider instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct InitializeRateProvider {
+    /// rates shared by the pools using the rate provider, with their
+    /// maximum ssr
+    pub rates: RedemptionRateCurve,
+}
+
+/// RelaySignedRates instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelaySignedRates {
+    /// new ssr
+    pub ssr: u128,
+    /// new rho
+    pub rho: u128,
+    /// new chi
+    pub chi: u128,
+    /// nonce signed with the rates, greater than the last one relayed
+    /// for the signer
+    pub nonce: u64,
+}
+
+/// Instruction data for setting the bridge receiver of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetBridgeReceiver {
+    /// the program id of the bridge receiver, default pubkey to remove it
+    pub bridge_receiver: [u8; 32],
+}
+
+/// BridgeSetRates instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BridgeSetRates {
+    /// new ssr
+    pub ssr: u128,
+    /// new rho
+    pub rho: u128,
+    /// new chi
+    pub chi: u128
+}
+
+/// Instruction data for adding a user to the allowlist of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddToAllowlist {
+    /// the pubkey of the allowed user
+    pub user: [u8; 32],
+}
+
+/// Instruction data for updating the maximum rate staleness of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetMaxStaleness {
+    /// maximum seconds since the rate was last updated, zero to disable
+    pub max_staleness: u64
+}
+
+/// Instruction data for updating max_ssr of RedemptionRateCurve
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetMaxSsr {
+    /// new max ssr, zero to remove the bound
+    pub max_ssr: u128
+}
+
+/// Instruction data for nominating a new super admin
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposeSuperAdmin {
+    /// the pubkey of the nominated authority, default pubkey to cancel
+    pub new_authority: [u8; 32],
+}
+
+/// Instruction data for updating the pool fees
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateFees {
+    /// new swap fees
+    pub fees: Fees,
+}
+
+/// Instruction data for setting the timelock delay of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetTimelockDelay {
+    /// minimum seconds between queueing and executing a change, zero to disable
+    pub timelock_delay: u64
+}
+
+/// Instruction data for setting the bounds on rate updates of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetRateUpdateLimits {
+    /// maximum change of the ssr in a single update, zero if unbounded
+    pub max_ssr_delta: u128,
+    /// minimum seconds between two updates, zero if unbounded
+    pub min_update_interval: u64,
+}
+
+/// Instruction data for setting the rate multisig of a pool
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetRateMultisig {
+    /// number of signers required to update the rates, zero to remove the multisig
+    pub threshold: u8,
+}
+
+/// Instruction data for queueing a change behind the timelock
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueChange {
+    /// the queued change
+    pub change: ParameterChange,
 }
 
 /// Instructions supported by the token swap program.
@@ -156,17 +447,22 @@ pub enum SwapInstruction {
     ///      supply. Must be empty, not owned by swap authority.
     ///   9. `[]` Pool Token program id
     /// 
-    /// *Extra accounts for `RedemptionRateCurve`:*
+    /// *Extra accounts for `RedemptionRateCurve` and `RateProviderCurve`:*
     /// 
     ///  10.  `[writable]` Permission account to be created.
     ///  11.  `[]` Super admin pubkey, linked to Permission account.
     ///  12.  `[signer, writable]` Payer (funds `create_account` for the permission account).
     ///  13.  `[]` System program id.
+    /// 
+    /// *Extra account for `RateProviderCurve`:*
+    /// 
+    ///  14.  `[]` Rate provider of the curve.
     Initialize(Initialize),
 
     ///   Swap the tokens in the pool.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap, written when the direction has a
+    ///      volume limit
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by
@@ -184,8 +480,17 @@ pub enum SwapInstruction {
     ///   11. `[]` Token (A|B) SOURCE program id
     ///   12. `[]` Token (A|B) DESTINATION program id
     ///   13. `[]` Pool Token program id
-    ///   14. `[optional, writable]` Host fee account to receive additional
-    ///       trading fees
+    ///   14. `[]` Rate provider of the curve, only for pools with a
+    ///       `RateProviderCurve`
+    ///
+    ///   Optional accounts, after the ones above:
+    ///
+    ///   * `[]` Allowlist entry of the user transfer authority, at 14 or at
+    ///     15 after a rate provider. Required while swaps are
+    ///     allowlisted, see `SetAllowlistMode`
+    ///   * `[writable]` Host fee account to receive additional trading
+    ///     fees, at 14 to 16 after the rate provider and the allowlist entry
+    ///     when they are present
     Swap(Swap),
 
     ///   Deposit both types of tokens into the pool.  The output is a "pool"
@@ -207,6 +512,14 @@ pub enum SwapInstruction {
     ///   11. `[]` Token A program id
     ///   12. `[]` Token B program id
     ///   13. `[]` Pool Token program id
+    ///   14. `[]` Rate provider of the curve, only for pools with a
+    ///       `RateProviderCurve`
+    ///
+    ///   Optional accounts, after the ones above:
+    ///
+    ///   * `[]` Allowlist entry of the user transfer authority, at 14 or at
+    ///     15 after a rate provider. Required while liquidity is
+    ///     allowlisted, see `SetAllowlistMode`
     DepositAllTokenTypes(DepositAllTokenTypes),
 
     ///   Withdraw both types of tokens from the pool at the current ratio,
@@ -229,6 +542,14 @@ pub enum SwapInstruction {
     ///   12. `[]` Pool Token program id
     ///   13. `[]` Token A program id
     ///   14. `[]` Token B program id
+    ///   15. `[]` Rate provider of the curve, only for pools with a
+    ///       `RateProviderCurve`
+    ///
+    ///   Optional accounts, after the ones above:
+    ///
+    ///   * `[]` Allowlist entry of the user transfer authority, at 15 or at
+    ///     16 after a rate provider. Required while liquidity is
+    ///     allowlisted, see `SetAllowlistMode`
     WithdrawAllTokenTypes(WithdrawAllTokenTypes),
 
     ///   Deposit one type of tokens into the pool. The output is a "pool"
@@ -248,10 +569,20 @@ pub enum SwapInstruction {
     ///   8. `[]` Token (A|B) SOURCE mint
     ///   9. `[]` Token (A|B) SOURCE program id
     ///   10. `[]` Pool Token program id
-    ///   11. `[writable]` Pool fee account *(optional)*  
-    ///   12. `[writable]` Host-fee account *(optional)*  
+    ///   11. `[]` Rate provider of the curve, only for pools with a
+    ///       `RateProviderCurve`
+    ///
+    ///   Optional accounts, after the ones above:
     ///
-    ///   If accounts 11–12 are supplied, the owner-fee portion of the deposit is
+    ///   * `[]` Allowlist entry of the user transfer authority, at 11 or at
+    ///     12 after a rate provider. Required while liquidity is
+    ///     allowlisted, see `SetAllowlistMode`
+    ///   * `[writable]` Pool fee account, at 11 to 13 after the rate provider
+    ///     and the allowlist entry when they are present
+    ///   * `[writable]` Host-fee account, at 12 to 14 right after the pool
+    ///     fee account
+    ///
+    ///   If the fee accounts are supplied, the owner-fee portion of the deposit is
     ///   converted to pool-tokens and minted:
     ///   * A share to the host-fee account (if present and non-zero)  
     ///   * The remainder to the pool-fee account.  
@@ -275,16 +606,29 @@ pub enum SwapInstruction {
     ///   9. `[]` Token (A|B) DESTINATION mint
     ///   10. `[]` Pool Token program id
     ///   11. `[]` Token (A|B) DESTINATION program id
-    ///   12. `[writable]` Host-fee account *(optional)*
+    ///   12. `[]` Rate provider of the curve, only for pools with a
+    ///       `RateProviderCurve`
+    ///
+    ///   Optional accounts, after the ones above:
+    ///
+    ///   * `[]` Allowlist entry of the user transfer authority, at 12 or at
+    ///     13 after a rate provider. Required while liquidity is
+    ///     allowlisted, see `SetAllowlistMode`
+    ///   * `[writable]` Host-fee account, at 12 to 14 after the rate
+    ///     provider and the allowlist entry when they are present
     WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut),
 
-    /// Updates rho, chi and ssr in RedemptionRateCurve
+    /// Updates rho, chi and ssr in RedemptionRateCurve, or in the rates of
+    /// a three-asset pool or of a rate provider
     /// Permission account has to be provided and signer
-    /// needs to have the corresponding permissions
+    /// needs to have the corresponding permissions.
+    /// The rate multisig of the pool can be provided instead,
+    /// followed by at least `threshold` of its signers.
     /// 
-    /// 0. `[writable]` Token-swap
-    /// 1. `[]` Permission account
-    /// 2. `[]` Signer, linked to permission account
+    /// 0. `[writable]` Token-swap, three-asset pool or rate provider
+    /// 1. `[]` Permission account, or rate multisig account
+    /// 2. `[signer]` Signer, linked to permission account
+    ///    or `[signer]` M signers of the rate multisig
     SetRates(SetRates),
 
     /// Initialize a new permission
@@ -304,108 +648,817 @@ pub enum SwapInstruction {
     /// 1. `[]` Permission account authorized to update another permission
     /// 2. `[writable]` Permission account being updated
     /// 3. `[signer]` Signer, associated to 1. permission account
-    UpdatePermission(UpdatePermission)
-}
+    UpdatePermission(UpdatePermission),
 
-impl SwapInstruction {
-    /// Unpacks a byte buffer into a
-    /// [SwapInstruction](enum.SwapInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
-        Ok(match tag {
-            0 => {
-                if rest.len() >= Fees::LEN {
-                    let (fees, rest) = rest.split_at(Fees::LEN);
-                    let fees = Fees::unpack_unchecked(fees)?;
-                    let swap_curve = SwapCurve::unpack_unchecked(rest)?;
-                    Self::Initialize(Initialize { fees, swap_curve })
-                } else {
-                    return Err(SwapError::InvalidInstruction.into());
-                }
-            }
-            1 => {
-                let (amount_in, rest) = Self::unpack_u64(rest)?;
-                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
-                Self::Swap(Swap {
-                    amount_in,
-                    minimum_amount_out,
-                })
-            }
-            2 => {
-                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
-                let (maximum_token_a_amount, rest) = Self::unpack_u64(rest)?;
-                let (maximum_token_b_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::DepositAllTokenTypes(DepositAllTokenTypes {
-                    pool_token_amount,
-                    maximum_token_a_amount,
-                    maximum_token_b_amount,
-                })
-            }
-            3 => {
-                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
-                let (minimum_token_a_amount, rest) = Self::unpack_u64(rest)?;
-                let (minimum_token_b_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                })
-            }
-            4 => {
-                let (source_token_amount, rest) = Self::unpack_u64(rest)?;
-                let (minimum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn {
-                    source_token_amount,
-                    minimum_pool_token_amount,
-                })
-            }
-            5 => {
-                let (destination_token_amount, rest) = Self::unpack_u64(rest)?;
-                let (maximum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut {
-                    destination_token_amount,
-                    maximum_pool_token_amount,
-                })
-            }
-            6 => {
-                let (ssr, rest) = Self::unpack_u128(rest)?;
-                let (rho, rest) = Self::unpack_u128(rest)?;
-                let (chi, _rest) = Self::unpack_u128(rest)?;
+    /// Pauses or unpauses the pool. While paused, swaps, deposits and
+    /// single token withdrawals are rejected, WithdrawAllTokenTypes is
+    /// still allowed. A paused three-asset pool rejects its swaps and
+    /// deposits, withdrawals stay open.
+    /// Signer must have the PAUSE role
+    /// 
+    /// 0. `[writable]` Token-swap or three-asset pool
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetPaused(SetPaused),
 
-                Self::SetRates(SetRates { 
-                    ssr, 
-                    rho, 
-                    chi 
-                })
-            },
-            7 => {
-                let (permission_authority, rest) = rest.split_at(32);
-                let (is_super_admin, rest) = Self::unpack_bool(rest)?;
-                let (can_update_parameters, _rest) = Self::unpack_bool(rest)?;
+    /// Migrates a swap account to the latest version, reallocating it to
+    /// make room for pool controls. Anyone can pay for the migration.
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[writable, signer]` Payer of the additional rent
+    /// 2. `[]` System program
+    MigrateSwap,
 
-                let permission_authority: [u8; 32] = permission_authority
-                    .try_into()
-                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+    /// Updates the maximum number of seconds since the last rate update
+    /// after which swaps and single token deposits / withdrawals fail.
+    /// Goes through the timelock when the pool has a delay.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap or three-asset pool
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetMaxStaleness(SetMaxStaleness),
 
-                Self::InitializePermission(InitializePermission { 
-                    permission_authority, 
-                    is_super_admin, 
-                    can_update_parameters 
+    /// Updates max_ssr in RedemptionRateCurve or in the rates of a
+    /// three-asset pool or of a rate provider, it can't be lower than the
+    /// current ssr. Goes through the timelock when the swap, three-asset
+    /// pool or rate provider has a delay.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap, three-asset pool or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetMaxSsr(SetMaxSsr),
+
+    /// Updates the pool fees, which are validated against the program's
+    /// fee constraints if any.
+    /// Signer must have the UPDATE_FEES role
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    UpdateFees(UpdateFees),
+
+    /// Nominates a new super admin, replacing any pending nomination.
+    /// Nothing changes until the nominated authority accepts.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    ProposeSuperAdmin(ProposeSuperAdmin),
+
+    /// Accepts a pending super admin nomination. The permission account
+    /// of the nominated authority is created if needed and made super admin,
+    /// the permission account of the proposer is revoked.
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[writable]` Permission account of the proposer
+    /// 2. `[writable]` Permission account of the nominated authority
+    /// 3. `[signer]` Nominated authority
+    /// 4. `[writable, signer]` Payer
+    /// 5. `[]` System program
+    AcceptSuperAdmin,
+
+    /// Closes a permission account, sending its lamports to the recipient.
+    /// Signer must be super admin or the authority of the closed permission,
+    /// in which case 1. and 2. are the same account. Super admin permissions
+    /// can't be closed, they are handed over with `ProposeSuperAdmin`.
+    /// 
+    /// 0. `[]` Token-swap
+    /// 1. `[]` Permission account of the signer
+    /// 2. `[writable]` Permission account being closed
+    /// 3. `[signer]` Signer, linked to 1. permission account
+    /// 4. `[writable]` Recipient of the lamports
+    ClosePermission,
+
+    /// Migrates a legacy permission account to the latest version,
+    /// reallocating it to make room for the roles bitmask, roles are kept.
+    /// Anyone can pay for the migration.
+    /// 
+    /// 0. `[writable]` Permission account
+    /// 1. `[writable, signer]` Payer of the additional rent
+    /// 2. `[]` System program
+    MigratePermission,
+
+    /// Sets the timelock delay of the pool. While it is not zero, changes
+    /// of max_ssr, max_staleness, fees and the fee account have to be queued with
+    /// `QueueChange`, including changes of the delay itself. The delay of a
+    /// rate provider covers max_ssr, its rate update limits, its bridge
+    /// receiver and the delay itself. The delay of a three-asset pool
+    /// covers max_ssr, its rate update limits, max_staleness and the delay
+    /// itself.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap, three-asset pool or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetTimelockDelay(SetTimelockDelay),
+
+    /// Queues a change, executable with `ExecuteChange` once the timelock
+    /// delay of the pool has elapsed. Only one change of each kind can be
+    /// queued at a time. Rate providers and three-asset pools only accept
+    /// the kinds of change they hold.
+    /// Signer must have the role needed to apply the change
+    /// 
+    /// 0. `[]` Token-swap, three-asset pool or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[writable]` Queued change account to be created
+    /// 3. `[signer]` Signer, linked to permission account
+    /// 4. `[writable, signer]` Payer
+    /// 5. `[]` System program
+    QueueChange(QueueChange),
+
+    /// Applies a queued change once its eta has passed and closes the
+    /// queued change account. Anyone can execute it.
+    /// 
+    /// 0. `[writable]` Token-swap, three-asset pool or rate provider
+    /// 1. `[writable]` Queued change account
+    /// 2. `[writable]` Payer of the queued change account, receiving its lamports
+    /// 3. `[]` New pool fee account, only for fee account changes
+    ExecuteChange,
+
+    /// Cancels a queued change and closes the queued change account.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[]` Token-swap, three-asset pool or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[writable]` Queued change account
+    /// 3. `[signer]` Signer, linked to permission account
+    /// 4. `[writable]` Payer of the queued change account, receiving its lamports
+    CancelChange,
+
+    /// Sets the bounds on `SetRates` updates: the maximum change of the ssr
+    /// in a single update and the minimum number of seconds between updates.
+    /// Goes through the timelock when the swap, three-asset pool or rate
+    /// provider has a delay.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap, three-asset pool or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetRateUpdateLimits(SetRateUpdateLimits),
+
+    /// Sets the signers and threshold of the rate multisig of the pool or
+    /// rate provider, creating its account if needed. A threshold of zero
+    /// without signers closes the account, sending its lamports to the payer.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[]` Token-swap or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[writable]` Rate multisig account
+    /// 3. `[signer]` Signer, linked to permission account
+    /// 4. `[writable, signer]` Payer
+    /// 5. `[]` System program
+    /// 6. ..6+N `[]` Signers of the rate multisig, 1 <= N <= 11
+    SetRateMultisig(SetRateMultisig),
+
+    /// Changes the pool token account receiving the fees. The new account
+    /// has to hold pool tokens and can't be owned by the swap authority.
+    /// Signer must have the UPDATE_FEE_ACCOUNT role
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    /// 3. `[]` New pool fee account
+    SetFeeAccount,
+
+    /// Closes a pool whose only remaining pool tokens are the initial
+    /// supply locked at initialization. The remaining reserves are sent
+    /// to the given token accounts, the reserve token accounts and the
+    /// swap account are closed and their rent sent to the recipient.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` swap authority
+    /// 2. `[]` Permission account
+    /// 3. `[signer]` Signer, linked to permission account
+    /// 4. `[writable]` token_a Base Account to close
+    /// 5. `[writable]` token_b Base Account to close
+    /// 6. `[]` Pool mint account
+    /// 7. `[]` Pool token account holding the locked initial supply
+    /// 8. `[writable]` token_a user Account to receive the remaining reserve
+    /// 9. `[writable]` token_b user Account to receive the remaining reserve
+    /// 10. `[]` Token A mint
+    /// 11. `[]` Token B mint
+    /// 12. `[writable]` Recipient of the rent
+    /// 13. `[]` Token A program id
+    /// 14. `[]` Token B program id
+    ClosePool,
+
+    /// Enables or disables each swap direction of the pool. A disabled
+    /// direction also blocks the single-sided deposits and withdrawals
+    /// converting the same way: depositing token A or withdrawing token B
+    /// for A to B, and the reverse for B to A.
+    /// Signer must have the SET_SWAP_DIRECTIONS role
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetSwapDirections(SetSwapDirections),
+
+    /// Switches the allowlist mode of the pool. While deposits and
+    /// withdrawals or swaps are allowlisted, those instructions require the
+    /// allowlist entry of their user transfer authority, right after their
+    /// required accounts and rate provider and before any optional fee
+    /// account. The entry is recognized by its owner and size rather than
+    /// its position, so it can be passed whatever the mode. When it is left
+    /// out, the fee accounts after it move up by one index.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetAllowlistMode(SetAllowlistMode),
+
+    /// Adds a user to the allowlist of the pool
+    /// Signer must have the MANAGE_ALLOWLIST role
+    /// 
+    /// 0. `[]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[writable]` Allowlist entry account to be created
+    /// 3. `[signer]` Signer, linked to permission account
+    /// 4. `[writable, signer]` Payer
+    /// 5. `[]` System program
+    AddToAllowlist(AddToAllowlist),
+
+    /// Removes a user from the allowlist of the pool, closing its entry
+    /// Signer must have the MANAGE_ALLOWLIST role
+    /// 
+    /// 0. `[]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[writable]` Allowlist entry account
+    /// 3. `[signer]` Signer, linked to permission account
+    /// 4. `[writable]` Recipient of the entry's lamports
+    RemoveFromAllowlist,
+
+    /// Sets the volume limit of each swap direction of the pool. Swaps
+    /// consume the limit of their direction by the amount of source tokens
+    /// swapped, and it refills by its slope every second up to its maximum.
+    /// Both limits are reset to their maximum.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetSwapRateLimits(SetSwapRateLimits),
+
+    /// Sets the value cap of the pool. Deposits leaving the pool reserves
+    /// above it are rejected, the reserves valued in token A as
+    /// `a + b * conversion_rate / RAY` at the current timestamp, or twice
+    /// the curve's `normalized_value` for curves without a conversion rate.
+    /// Zero removes the cap.
+    /// Signer must have the MANAGE_LIQUIDITY role
+    /// 
+    /// 0. `[writable]` Token-swap
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetMaxTvl(SetMaxTvl),
+
+    ///   Swap the tokens in the pool for an exact amount of DESTINATION
+    ///   tokens, transferring the SOURCE tokens it requires including fees.
+    ///
+    ///   0. `[writable]` Token-swap, written when the direction has a
+    ///      volume limit
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by
+    ///      user transfer authority,
+    ///   4. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the
+    ///      SOURCE token.
+    ///   5. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the
+    ///      DESTINATION token.
+    ///   6. `[writable]` token_(A|B) DESTINATION Account assigned to USER as
+    ///      the owner.
+    ///   7. `[writable]` Pool token mint, to generate trading fees
+    ///   8. `[writable]` Fee account, to receive trading fees
+    ///   9. `[]` Token (A|B) SOURCE mint
+    ///   10. `[]` Token (A|B) DESTINATION mint
+    ///   11. `[]` Token (A|B) SOURCE program id
+    ///   12. `[]` Token (A|B) DESTINATION program id
+    ///   13. `[]` Pool Token program id
+    ///   14. `[]` Rate provider of the curve, only for pools with a
+    ///       `RateProviderCurve`
+    ///
+    ///   Optional accounts, after the ones above:
+    ///
+    ///   * `[]` Allowlist entry of the user transfer authority, at 14 or at
+    ///     15 after a rate provider. Required while swaps are
+    ///     allowlisted, see `SetAllowlistMode`
+    ///   * `[writable]` Host fee account to receive additional trading
+    ///     fees, at 14 to 16 after the rate provider and the allowlist entry
+    ///     when they are present
+    SwapExactAmountOut(SwapExactAmountOut),
+
+    /// Previews a `Swap`, or a `SwapExactAmountOut` if `exact_out`, with the
+    /// curve and fees of the pool at the current timestamp, without moving
+    /// tokens. Returns a [SwapPreview](crate::preview::SwapPreview) through
+    /// the return data.
+    ///
+    /// 0. `[]` Token-swap
+    /// 1. `[]` token_(A|B) Base Account to swap INTO
+    /// 2. `[]` token_(A|B) Base Account to swap FROM
+    PreviewSwap(PreviewSwap),
+
+    /// Previews a `DepositSingleTokenTypeExactAmountIn` at the current
+    /// timestamp, without moving tokens. Returns the amount of pool tokens
+    /// minted to the depositor as a little-endian u64 through the return data.
+    ///
+    /// 0. `[]` Token-swap
+    /// 1. `[]` token_a Swap Account
+    /// 2. `[]` token_b Swap Account
+    /// 3. `[]` Pool MINT account
+    /// 4. `[]` Token (A|B) mint of the deposited token
+    PreviewDeposit(PreviewDeposit),
+
+    /// Previews a `WithdrawSingleTokenTypeExactAmountOut` at the current
+    /// timestamp, without moving tokens. Returns the amount of pool tokens
+    /// burned from the withdrawer, withdraw fee included, as a little-endian
+    /// u64 through the return data.
+    ///
+    /// 0. `[]` Token-swap
+    /// 1. `[]` token_a Swap Account
+    /// 2. `[]` token_b Swap Account
+    /// 3. `[]` Pool MINT account
+    /// 4. `[]` Fee account, to receive withdrawal fees
+    /// 5. `[]` Token (A|B) mint of the withdrawn token
+    PreviewWithdraw(PreviewWithdraw),
+
+    /// Returns the conversion rate of a `RedemptionRate` pool, chi scaled by
+    /// RAY and extrapolated with the ssr to the current timestamp or to a
+    /// later `timestamp`, as a little-endian u128 through the return data.
+    /// Fails once the rate is stale.
+    ///
+    /// 0. `[]` Token-swap or rate provider
+    GetConversionRate(GetConversionRate),
+
+    ///   Initializes a three-asset pool holding USDC, USDS and sUSDS behind a
+    ///   single pool token. The initial pool tokens, one per USD of reserves,
+    ///   go to an account owned by the `init_destination` PDA so that they
+    ///   can never be withdrawn. All three mints must have the same decimals.
+    ///   Rates are updated with `SetRates`.
+    ///
+    ///   0. `[writable, signer]` New three-asset pool, `Psm3Pool::LEN` bytes
+    ///      owned by the program
+    ///   1. `[]` pool authority derived from `create_program_address(&[pool
+    ///      account])`
+    ///   2. `[]` USDC reserve Account, owned by the pool authority
+    ///   3. `[]` USDS reserve Account, owned by the pool authority
+    ///   4. `[]` sUSDS reserve Account, owned by the pool authority
+    ///   5. `[]` USDC mint
+    ///   6. `[]` USDS mint
+    ///   7. `[]` sUSDS mint
+    ///   8. `[writable]` Pool Token Mint, with the pool authority as mint
+    ///      authority and no supply
+    ///   9. `[writable]` Pool Token Account to deposit the initial pool token
+    ///      supply, owned by the `init_destination` PDA
+    ///   10. `[]` Pool Token program id
+    ///   11. `[writable]` Permission account to be created
+    ///   12. `[]` Super admin pubkey, linked to Permission account
+    ///   13. `[signer, writable]` Payer of the permission account
+    ///   14. `[]` System program id
+    InitializePsm3(InitializePsm3),
+
+    ///   Swaps between two assets of a three-asset pool. USDC and USDS swap
+    ///   1:1 and sUSDS at the conversion rate of the current timestamp.
+    ///
+    ///   0. `[]` three-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` SOURCE Account, amount is transferable by user
+    ///      transfer authority
+    ///   4. `[writable]` reserve Account to swap INTO. Must be the SOURCE
+    ///      token.
+    ///   5. `[writable]` reserve Account to swap FROM. Must be the
+    ///      DESTINATION token.
+    ///   6. `[writable]` DESTINATION Account assigned to USER as the owner
+    ///   7. `[]` SOURCE mint
+    ///   8. `[]` DESTINATION mint
+    ///   9. `[]` SOURCE program id
+    ///   10. `[]` DESTINATION program id
+    Psm3Swap(Psm3Swap),
+
+    ///   Deposits one asset into a three-asset pool, minting pool tokens for
+    ///   its value in USD.
+    ///
+    ///   0. `[]` three-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` SOURCE Account, amount is transferable by user
+    ///      transfer authority
+    ///   4. `[writable]` USDC reserve Account
+    ///   5. `[writable]` USDS reserve Account
+    ///   6. `[writable]` sUSDS reserve Account
+    ///   7. `[writable]` Pool MINT account, pool authority is the owner
+    ///   8. `[writable]` Pool Account to deposit the generated tokens, user is
+    ///      the owner
+    ///   9. `[]` SOURCE mint
+    ///   10. `[]` SOURCE program id
+    ///   11. `[]` Pool Token program id
+    Psm3Deposit(Psm3Deposit),
+
+    ///   Withdraws an exact amount of one asset from a three-asset pool,
+    ///   burning pool tokens for its value in USD. Allowed while the pool is
+    ///   paused or its rates are stale, valuing sUSDS at the last known rates.
+    ///
+    ///   0. `[]` three-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` Pool mint account, pool authority is the owner
+    ///   4. `[writable]` SOURCE Pool account, amount is transferable by user
+    ///      transfer authority
+    ///   5. `[writable]` USDC reserve Account
+    ///   6. `[writable]` USDS reserve Account
+    ///   7. `[writable]` sUSDS reserve Account
+    ///   8. `[writable]` DESTINATION Account assigned to USER as the owner
+    ///   9. `[]` DESTINATION mint
+    ///   10. `[]` Pool Token program id
+    ///   11. `[]` DESTINATION program id
+    Psm3Withdraw(Psm3Withdraw),
+
+    ///   Initializes a rate provider, holding rates shared by the pools
+    ///   created with a `RateProviderCurve` referencing it. Its rates are
+    ///   updated with `SetRates` and `SetMaxSsr`, with permissions derived
+    ///   from the rate provider, and bounded by its own rate update limits,
+    ///   rate multisig and timelock delay.
+    ///
+    ///   Swaps, deposits, withdrawals, previews and `GetConversionRate` of
+    ///   those pools take the rate provider right after their required
+    ///   accounts, before the allowlist entry and optional accounts.
+    ///
+    ///   0. `[writable, signer]` New rate provider, `RateProvider::LEN` bytes
+    ///      owned by the program
+    ///   1. `[writable]` Permission account to be created
+    ///   2. `[]` Super admin pubkey, linked to Permission account
+    ///   3. `[signer, writable]` Payer of the permission account
+    ///   4. `[]` System program id
+    InitializeRateProvider(InitializeRateProvider),
+
+    ///   Updates rates signed off-chain by the authority of a permission with
+    ///   the UPDATE_RATES role, submitted by anyone. The instruction right
+    ///   before must be an Ed25519 program instruction verifying a single
+    ///   signature of `relay::signed_rates_message`, with its data in that
+    ///   instruction. The rates are validated like `SetRates` ones.
+    ///
+    ///   0. `[writable]` Token-swap, three-asset pool or rate provider
+    ///   1. `[]` Permission account of the signing authority
+    ///   2. `[writable]` Relay nonce account of the signing authority,
+    ///      created by its first relayed update
+    ///   3. `[signer, writable]` Payer of the relay nonce account
+    ///   4. `[]` System program id
+    ///   5. `[]` Instructions sysvar
+    RelaySignedRates(RelaySignedRates),
+
+    /// Sets the bridge receiver program, allowed to update the rates with
+    /// `BridgeSetRates`. Keepers with the UPDATE_RATES role can still use
+    /// `SetRates`. Goes through the timelock when the swap or rate provider
+    /// has a delay.
+    /// Signer must have a super_admin associated permission account
+    /// 
+    /// 0. `[writable]` Token-swap or rate provider
+    /// 1. `[]` Permission account
+    /// 2. `[signer]` Signer, linked to permission account
+    SetBridgeReceiver(SetBridgeReceiver),
+
+    /// Updates the rates like `SetRates`, invoked through CPI by the bridge
+    /// receiver of the pool. The transaction instruction being processed
+    /// must be one of the bridge receiver, which signs with its bridge
+    /// authority, a PDA derived from `bridge::BRIDGE_AUTHORITY_SEED`.
+    /// 
+    /// 0. `[writable]` Token-swap or rate provider
+    /// 1. `[signer]` Bridge authority of the bridge receiver
+    /// 2. `[]` Instructions sysvar
+    BridgeSetRates(BridgeSetRates),
+}
+
+impl SwapInstruction {
+    /// Unpacks a byte buffer into a
+    /// [SwapInstruction](enum.SwapInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+        Ok(match tag {
+            0 => {
+                if rest.len() >= Fees::LEN {
+                    let (fees, rest) = rest.split_at(Fees::LEN);
+                    let fees = Fees::unpack_unchecked(fees)?;
+                    let swap_curve = SwapCurve::unpack_unchecked(rest)?;
+                    Self::Initialize(Initialize { fees, swap_curve })
+                } else {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+            }
+            1 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = Self::unpack_u64(rest)?;
+                let referral_code = Self::unpack_referral_code(rest);
+                Self::Swap(Swap {
+                    amount_in,
+                    minimum_amount_out,
+                    referral_code,
+                })
+            }
+            2 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_token_b_amount, rest) = Self::unpack_u64(rest)?;
+                let referral_code = Self::unpack_referral_code(rest);
+                Self::DepositAllTokenTypes(DepositAllTokenTypes {
+                    pool_token_amount,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    referral_code,
+                })
+            }
+            3 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_token_b_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                })
+            }
+            4 => {
+                let (source_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let referral_code = Self::unpack_referral_code(rest);
+                Self::DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                    referral_code,
+                })
+            }
+            5 => {
+                let (destination_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                })
+            }
+            6 => {
+                let (ssr, rest) = Self::unpack_u128(rest)?;
+                let (rho, rest) = Self::unpack_u128(rest)?;
+                let (chi, _rest) = Self::unpack_u128(rest)?;
+
+                Self::SetRates(SetRates { 
+                    ssr, 
+                    rho, 
+                    chi 
+                })
+            },
+            7 => {
+                let (permission_authority, rest) = rest.split_at(32);
+                let roles = Self::unpack_roles(rest)?;
+
+                let permission_authority: [u8; 32] = permission_authority
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+                Self::InitializePermission(InitializePermission { 
+                    permission_authority, 
+                    roles
                 })
             },
             8 => {
-                let (is_super_admin, rest) = Self::unpack_bool(rest)?;
-                let (can_update_parameters, _rest) = Self::unpack_bool(rest)?;
+                let roles = Self::unpack_roles(rest)?;
+
+                Self::UpdatePermission(UpdatePermission { roles })
+            }
+            9 => {
+                let (is_paused, _rest) = Self::unpack_bool(rest)?;
+
+                Self::SetPaused(SetPaused { is_paused })
+            }
+            10 => Self::MigrateSwap,
+            11 => {
+                let (max_staleness, _rest) = Self::unpack_u64(rest)?;
+
+                Self::SetMaxStaleness(SetMaxStaleness { max_staleness })
+            }
+            12 => {
+                let (max_ssr, _rest) = Self::unpack_u128(rest)?;
+
+                Self::SetMaxSsr(SetMaxSsr { max_ssr })
+            }
+            13 => {
+                let fees = rest
+                    .get(..Fees::LEN)
+                    .ok_or(SwapError::InvalidInstruction)?;
+                let fees = Fees::unpack_unchecked(fees)?;
+
+                Self::UpdateFees(UpdateFees { fees })
+            }
+            14 => {
+                let new_authority: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(SwapError::InvalidInstruction)?;
+
+                Self::ProposeSuperAdmin(ProposeSuperAdmin { new_authority })
+            }
+            15 => Self::AcceptSuperAdmin,
+            16 => Self::ClosePermission,
+            17 => Self::MigratePermission,
+            18 => {
+                let (timelock_delay, _rest) = Self::unpack_u64(rest)?;
+
+                Self::SetTimelockDelay(SetTimelockDelay { timelock_delay })
+            }
+            19 => {
+                let change = rest
+                    .get(..ParameterChange::LEN)
+                    .ok_or(SwapError::InvalidInstruction)?;
+                let change = ParameterChange::unpack(change)
+                    .map_err(|_| SwapError::InvalidInstruction)?;
 
-                Self::UpdatePermission(UpdatePermission { 
-                    is_super_admin, 
-                    can_update_parameters 
+                Self::QueueChange(QueueChange { change })
+            }
+            20 => Self::ExecuteChange,
+            21 => Self::CancelChange,
+            22 => {
+                let (max_ssr_delta, rest) = Self::unpack_u128(rest)?;
+                let (min_update_interval, _rest) = Self::unpack_u64(rest)?;
+
+                Self::SetRateUpdateLimits(SetRateUpdateLimits {
+                    max_ssr_delta,
+                    min_update_interval,
+                })
+            }
+            23 => {
+                let (&threshold, _rest) = rest
+                    .split_first()
+                    .ok_or(SwapError::InvalidInstruction)?;
+
+                Self::SetRateMultisig(SetRateMultisig { threshold })
+            }
+            24 => Self::SetFeeAccount,
+            25 => Self::ClosePool,
+            26 => {
+                let (a_to_b_enabled, rest) = Self::unpack_bool(rest)?;
+                let (b_to_a_enabled, _rest) = Self::unpack_bool(rest)?;
+
+                Self::SetSwapDirections(SetSwapDirections {
+                    a_to_b_enabled,
+                    b_to_a_enabled,
                 })
             }
+            27 => {
+                let (liquidity_allowlisted, rest) = Self::unpack_bool(rest)?;
+                let (swaps_allowlisted, _rest) = Self::unpack_bool(rest)?;
+
+                Self::SetAllowlistMode(SetAllowlistMode {
+                    liquidity_allowlisted,
+                    swaps_allowlisted,
+                })
+            }
+            28 => {
+                let user: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(SwapError::InvalidInstruction)?;
+
+                Self::AddToAllowlist(AddToAllowlist { user })
+            }
+            29 => Self::RemoveFromAllowlist,
+            30 => {
+                let (a_to_b_max_amount, rest) = Self::unpack_u64(rest)?;
+                let (a_to_b_slope, rest) = Self::unpack_u64(rest)?;
+                let (b_to_a_max_amount, rest) = Self::unpack_u64(rest)?;
+                let (b_to_a_slope, _rest) = Self::unpack_u64(rest)?;
+
+                Self::SetSwapRateLimits(SetSwapRateLimits {
+                    a_to_b_max_amount,
+                    a_to_b_slope,
+                    b_to_a_max_amount,
+                    b_to_a_slope,
+                })
+            }
+            31 => {
+                let (max_tvl, _rest) = Self::unpack_u128(rest)?;
+
+                Self::SetMaxTvl(SetMaxTvl { max_tvl })
+            }
+            32 => {
+                let (amount_out, rest) = Self::unpack_u64(rest)?;
+                let (maximum_amount_in, rest) = Self::unpack_u64(rest)?;
+                let referral_code = Self::unpack_referral_code(rest);
+                Self::SwapExactAmountOut(SwapExactAmountOut {
+                    amount_out,
+                    maximum_amount_in,
+                    referral_code,
+                })
+            }
+            33 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (exact_out, _rest) = Self::unpack_bool(rest)?;
+
+                Self::PreviewSwap(PreviewSwap { amount, exact_out })
+            }
+            34 => {
+                let (source_token_amount, _rest) = Self::unpack_u64(rest)?;
+
+                Self::PreviewDeposit(PreviewDeposit { source_token_amount })
+            }
+            35 => {
+                let (destination_token_amount, _rest) = Self::unpack_u64(rest)?;
+
+                Self::PreviewWithdraw(PreviewWithdraw { destination_token_amount })
+            }
+            36 => {
+                let timestamp = if rest.is_empty() {
+                    None
+                } else {
+                    Some(Self::unpack_u64(rest)?.0)
+                };
+
+                Self::GetConversionRate(GetConversionRate { timestamp })
+            }
+            37 => {
+                let rates = rest
+                    .get(..RedemptionRateCurve::LEN)
+                    .ok_or(SwapError::InvalidInstruction)?;
+                let rates = RedemptionRateCurve::unpack_from_slice(rates)?;
+
+                Self::InitializePsm3(InitializePsm3 { rates })
+            }
+            38 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
+
+                Self::Psm3Swap(Psm3Swap {
+                    amount_in,
+                    minimum_amount_out,
+                })
+            }
+            39 => {
+                let (source_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+
+                Self::Psm3Deposit(Psm3Deposit {
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                })
+            }
+            40 => {
+                let (destination_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+
+                Self::Psm3Withdraw(Psm3Withdraw {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                })
+            }
+            41 => {
+                let rates = rest
+                    .get(..RedemptionRateCurve::LEN)
+                    .ok_or(SwapError::InvalidInstruction)?;
+                let rates = RedemptionRateCurve::unpack_from_slice(rates)?;
+
+                Self::InitializeRateProvider(InitializeRateProvider { rates })
+            }
+            42 => {
+                let (ssr, rest) = Self::unpack_u128(rest)?;
+                let (rho, rest) = Self::unpack_u128(rest)?;
+                let (chi, rest) = Self::unpack_u128(rest)?;
+                let (nonce, _rest) = Self::unpack_u64(rest)?;
+
+                Self::RelaySignedRates(RelaySignedRates {
+                    ssr,
+                    rho,
+                    chi,
+                    nonce,
+                })
+            }
+            43 => {
+                let bridge_receiver: [u8; 32] = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(SwapError::InvalidInstruction)?;
+
+                Self::SetBridgeReceiver(SetBridgeReceiver { bridge_receiver })
+            }
+            44 => {
+                let (ssr, rest) = Self::unpack_u128(rest)?;
+                let (rho, rest) = Self::unpack_u128(rest)?;
+                let (chi, _rest) = Self::unpack_u128(rest)?;
+
+                Self::BridgeSetRates(BridgeSetRates { ssr, rho, chi })
+            }
             _ => return Err(SwapError::InvalidInstruction.into()),
         })
     }
 
+    /// The referral code is an optional trailing u64, anything shorter is
+    /// ignored as it was before the code was parsed
+    fn unpack_referral_code(input: &[u8]) -> Option<u64> {
+        input
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+    }
+
     fn unpack_bool(input: &[u8]) -> Result<(bool, &[u8]), ProgramError> {
         if let Some((&byte, rest)) = input.split_first() {
             match byte {
@@ -418,6 +1471,30 @@ impl SwapInstruction {
         }
     }
 
+    /// Unpacks a roles bitmask, or the booleans sent by older clients:
+    /// is_super_admin and can_update_parameters
+    fn unpack_roles(input: &[u8]) -> Result<u64, ProgramError> {
+        match input.len() {
+            8 => {
+                let (roles, _rest) = Self::unpack_u64(input)?;
+                Ok(roles)
+            }
+            2 => {
+                let (is_super_admin, rest) = Self::unpack_bool(input)?;
+                let (can_update_parameters, _rest) = Self::unpack_bool(rest)?;
+
+                Ok([
+                    (is_super_admin, roles::SUPER_ADMIN),
+                    (can_update_parameters, roles::UPDATE_RATES),
+                ]
+                .into_iter()
+                .filter(|(granted, _)| *granted)
+                .fold(0, |roles, (_, role)| roles | role))
+            }
+            _ => Err(SwapError::InvalidInstruction.into()),
+        }
+    }
+
     fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
         if input.len() >= 8 {
             let (amount, rest) = input.split_at(8);
@@ -459,145 +1536,606 @@ impl SwapInstruction {
                 Pack::pack_into_slice(swap_curve, &mut swap_curve_slice[..]);
                 buf.extend_from_slice(&swap_curve_slice);
             }
-            Self::Swap(Swap {
+            Self::Swap(Swap {
+                amount_in,
+                minimum_amount_out,
+                referral_code,
+            }) => {
+                buf.push(1);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                if let Some(referral_code) = referral_code {
+                    buf.extend_from_slice(&referral_code.to_le_bytes());
+                }
+            }
+            Self::DepositAllTokenTypes(DepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+                referral_code,
+            }) => {
+                buf.push(2);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_token_b_amount.to_le_bytes());
+                if let Some(referral_code) = referral_code {
+                    buf.extend_from_slice(&referral_code.to_le_bytes());
+                }
+            }
+            Self::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            }) => {
+                buf.push(3);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+            }
+            Self::DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn {
+                source_token_amount,
+                minimum_pool_token_amount,
+                referral_code,
+            }) => {
+                buf.push(4);
+                buf.extend_from_slice(&source_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
+                if let Some(referral_code) = referral_code {
+                    buf.extend_from_slice(&referral_code.to_le_bytes());
+                }
+            }
+            Self::WithdrawSingleTokenTypeExactAmountOut(
+                WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                buf.push(5);
+                buf.extend_from_slice(&destination_token_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
+            }
+            Self::SetRates(
+                SetRates { ssr, rho, chi }
+            ) => {
+                buf.push(6);
+                buf.extend_from_slice(&ssr.to_le_bytes());
+                buf.extend_from_slice(&rho.to_le_bytes());
+                buf.extend_from_slice(&chi.to_le_bytes());
+            }
+            Self::InitializePermission(
+                InitializePermission { 
+                    permission_authority, 
+                    roles
+                }
+            ) => {
+                buf.push(7);
+                buf.extend_from_slice(permission_authority);
+                buf.extend_from_slice(&roles.to_le_bytes());
+            },
+            Self::UpdatePermission(UpdatePermission { roles }) => {
+                buf.push(8);
+                buf.extend_from_slice(&roles.to_le_bytes());
+            }
+            Self::SetPaused(SetPaused { is_paused }) => {
+                buf.push(9);
+                buf.push(*is_paused as u8);
+            }
+            Self::MigrateSwap => {
+                buf.push(10);
+            }
+            Self::SetMaxStaleness(SetMaxStaleness { max_staleness }) => {
+                buf.push(11);
+                buf.extend_from_slice(&max_staleness.to_le_bytes());
+            }
+            Self::SetMaxSsr(SetMaxSsr { max_ssr }) => {
+                buf.push(12);
+                buf.extend_from_slice(&max_ssr.to_le_bytes());
+            }
+            Self::UpdateFees(UpdateFees { fees }) => {
+                buf.push(13);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+            }
+            Self::ProposeSuperAdmin(ProposeSuperAdmin { new_authority }) => {
+                buf.push(14);
+                buf.extend_from_slice(new_authority);
+            }
+            Self::AcceptSuperAdmin => {
+                buf.push(15);
+            }
+            Self::ClosePermission => {
+                buf.push(16);
+            }
+            Self::MigratePermission => {
+                buf.push(17);
+            }
+            Self::SetTimelockDelay(SetTimelockDelay { timelock_delay }) => {
+                buf.push(18);
+                buf.extend_from_slice(&timelock_delay.to_le_bytes());
+            }
+            Self::QueueChange(QueueChange { change }) => {
+                buf.push(19);
+                let mut change_slice = [0u8; ParameterChange::LEN];
+                change.pack_into_slice(&mut change_slice);
+                buf.extend_from_slice(&change_slice);
+            }
+            Self::ExecuteChange => {
+                buf.push(20);
+            }
+            Self::CancelChange => {
+                buf.push(21);
+            }
+            Self::SetRateUpdateLimits(SetRateUpdateLimits {
+                max_ssr_delta,
+                min_update_interval,
+            }) => {
+                buf.push(22);
+                buf.extend_from_slice(&max_ssr_delta.to_le_bytes());
+                buf.extend_from_slice(&min_update_interval.to_le_bytes());
+            }
+            Self::SetRateMultisig(SetRateMultisig { threshold }) => {
+                buf.push(23);
+                buf.push(*threshold);
+            }
+            Self::SetFeeAccount => {
+                buf.push(24);
+            }
+            Self::ClosePool => {
+                buf.push(25);
+            }
+            Self::SetSwapDirections(SetSwapDirections {
+                a_to_b_enabled,
+                b_to_a_enabled,
+            }) => {
+                buf.push(26);
+                buf.push(*a_to_b_enabled as u8);
+                buf.push(*b_to_a_enabled as u8);
+            }
+            Self::SetAllowlistMode(SetAllowlistMode {
+                liquidity_allowlisted,
+                swaps_allowlisted,
+            }) => {
+                buf.push(27);
+                buf.push(*liquidity_allowlisted as u8);
+                buf.push(*swaps_allowlisted as u8);
+            }
+            Self::AddToAllowlist(AddToAllowlist { user }) => {
+                buf.push(28);
+                buf.extend_from_slice(user);
+            }
+            Self::RemoveFromAllowlist => {
+                buf.push(29);
+            }
+            Self::SetSwapRateLimits(SetSwapRateLimits {
+                a_to_b_max_amount,
+                a_to_b_slope,
+                b_to_a_max_amount,
+                b_to_a_slope,
+            }) => {
+                buf.push(30);
+                buf.extend_from_slice(&a_to_b_max_amount.to_le_bytes());
+                buf.extend_from_slice(&a_to_b_slope.to_le_bytes());
+                buf.extend_from_slice(&b_to_a_max_amount.to_le_bytes());
+                buf.extend_from_slice(&b_to_a_slope.to_le_bytes());
+            }
+            Self::SetMaxTvl(SetMaxTvl { max_tvl }) => {
+                buf.push(31);
+                buf.extend_from_slice(&max_tvl.to_le_bytes());
+            }
+            Self::SwapExactAmountOut(SwapExactAmountOut {
+                amount_out,
+                maximum_amount_in,
+                referral_code,
+            }) => {
+                buf.push(32);
+                buf.extend_from_slice(&amount_out.to_le_bytes());
+                buf.extend_from_slice(&maximum_amount_in.to_le_bytes());
+                if let Some(referral_code) = referral_code {
+                    buf.extend_from_slice(&referral_code.to_le_bytes());
+                }
+            }
+            Self::PreviewSwap(PreviewSwap { amount, exact_out }) => {
+                buf.push(33);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*exact_out as u8);
+            }
+            Self::PreviewDeposit(PreviewDeposit { source_token_amount }) => {
+                buf.push(34);
+                buf.extend_from_slice(&source_token_amount.to_le_bytes());
+            }
+            Self::PreviewWithdraw(PreviewWithdraw { destination_token_amount }) => {
+                buf.push(35);
+                buf.extend_from_slice(&destination_token_amount.to_le_bytes());
+            }
+            Self::GetConversionRate(GetConversionRate { timestamp }) => {
+                buf.push(36);
+                if let Some(timestamp) = timestamp {
+                    buf.extend_from_slice(&timestamp.to_le_bytes());
+                }
+            }
+            Self::InitializePsm3(InitializePsm3 { rates }) => {
+                buf.push(37);
+                let mut rates_slice = [0u8; RedemptionRateCurve::LEN];
+                Pack::pack_into_slice(rates, &mut rates_slice[..]);
+                buf.extend_from_slice(&rates_slice);
+            }
+            Self::Psm3Swap(Psm3Swap {
                 amount_in,
                 minimum_amount_out,
             }) => {
-                buf.push(1);
+                buf.push(38);
                 buf.extend_from_slice(&amount_in.to_le_bytes());
                 buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
             }
-            Self::DepositAllTokenTypes(DepositAllTokenTypes {
-                pool_token_amount,
-                maximum_token_a_amount,
-                maximum_token_b_amount,
-            }) => {
-                buf.push(2);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
-                buf.extend_from_slice(&maximum_token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&maximum_token_b_amount.to_le_bytes());
-            }
-            Self::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                buf.push(3);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
-            }
-            Self::DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn {
+            Self::Psm3Deposit(Psm3Deposit {
                 source_token_amount,
                 minimum_pool_token_amount,
             }) => {
-                buf.push(4);
+                buf.push(39);
                 buf.extend_from_slice(&source_token_amount.to_le_bytes());
                 buf.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
             }
-            Self::WithdrawSingleTokenTypeExactAmountOut(
-                WithdrawSingleTokenTypeExactAmountOut {
-                    destination_token_amount,
-                    maximum_pool_token_amount,
-                },
-            ) => {
-                buf.push(5);
+            Self::Psm3Withdraw(Psm3Withdraw {
+                destination_token_amount,
+                maximum_pool_token_amount,
+            }) => {
+                buf.push(40);
                 buf.extend_from_slice(&destination_token_amount.to_le_bytes());
                 buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
             }
-            Self::SetRates(
-                SetRates { ssr, rho, chi }
-            ) => {
-                buf.push(6);
+            Self::InitializeRateProvider(InitializeRateProvider { rates }) => {
+                buf.push(41);
+                let mut rates_slice = [0u8; RedemptionRateCurve::LEN];
+                Pack::pack_into_slice(rates, &mut rates_slice[..]);
+                buf.extend_from_slice(&rates_slice);
+            }
+            Self::RelaySignedRates(RelaySignedRates { ssr, rho, chi, nonce }) => {
+                buf.push(42);
                 buf.extend_from_slice(&ssr.to_le_bytes());
                 buf.extend_from_slice(&rho.to_le_bytes());
                 buf.extend_from_slice(&chi.to_le_bytes());
+                buf.extend_from_slice(&nonce.to_le_bytes());
             }
-            Self::InitializePermission(
-                InitializePermission { 
-                    permission_authority, 
-                    is_super_admin, 
-                    can_update_parameters 
-                }
-            ) => {
-                buf.push(7);
-                buf.extend_from_slice(permission_authority);
-                buf.push(*is_super_admin as u8);
-                buf.push(*can_update_parameters as u8);
-            },
-            Self::UpdatePermission(
-                UpdatePermission { 
-                    is_super_admin, 
-                    can_update_parameters 
-                }
-            ) => {
-                buf.push(8);
-                buf.push(*is_super_admin as u8);
-                buf.push(*can_update_parameters as u8);
+            Self::SetBridgeReceiver(SetBridgeReceiver { bridge_receiver }) => {
+                buf.push(43);
+                buf.extend_from_slice(bridge_receiver);
+            }
+            Self::BridgeSetRates(BridgeSetRates { ssr, rho, chi }) => {
+                buf.push(44);
+                buf.extend_from_slice(&ssr.to_le_bytes());
+                buf.extend_from_slice(&rho.to_le_bytes());
+                buf.extend_from_slice(&chi.to_le_bytes());
             }
         }
         buf
     }
-}
+}
+
+/// Extra accounts needed for RedemptionRate curve
+pub struct RedemptionRateExtraAccounts<'a> {
+    /// pda created at initialization
+    pub permission_account: &'a Pubkey,
+    /// authority of permission account
+    pub authority: &'a Pubkey,
+    /// payer of account creation
+    pub payer: &'a Pubkey,
+    /// system program
+    pub system_program: &'a Pubkey
+}
+
+/// Creates an 'initialize' instruction.
+pub fn initialize(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    fees: Fees,
+    swap_curve: SwapCurve,
+    redemption_rate_extra_accounts: Option<RedemptionRateExtraAccounts>
+) -> Result<Instruction, ProgramError> {
+    let curve_type = swap_curve.curve_type.clone();
+    let rate_provider = swap_curve.calculator.rate_provider().copied();
+    let init_data = SwapInstruction::Initialize(Initialize { fees, swap_curve });
+    let data = init_data.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*token_a_pubkey, false),
+        AccountMeta::new_readonly(*token_b_pubkey, false),
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*fee_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    if curve_type == CurveType::RedemptionRateCurve || curve_type == CurveType::RateProviderCurve {
+        let RedemptionRateExtraAccounts {
+            permission_account,
+            authority,
+            payer,
+            system_program
+        } = redemption_rate_extra_accounts.ok_or(ProgramError::InvalidInstructionData)?;
+
+        accounts.push(AccountMeta::new(*permission_account, false));
+        accounts.push(AccountMeta::new_readonly(*authority, false));
+        accounts.push(AccountMeta::new(*payer, true));
+        accounts.push(AccountMeta::new_readonly(*system_program, false));
+    }
+
+    if let Some(rate_provider) = rate_provider {
+        accounts.push(AccountMeta::new_readonly(rate_provider, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_all_token_types' instruction.
+pub fn deposit_all_token_types(
+    program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    deposit_token_a_pubkey: &Pubkey,
+    deposit_token_b_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    rate_provider_pubkey: Option<&Pubkey>,
+    allowlist_entry_pubkey: Option<&Pubkey>,
+    instruction: DepositAllTokenTypes,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositAllTokenTypes(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*deposit_token_a_pubkey, false),
+        AccountMeta::new(*deposit_token_b_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    if let Some(rate_provider_pubkey) = rate_provider_pubkey {
+        accounts.push(AccountMeta::new_readonly(*rate_provider_pubkey, false));
+    }
+    if let Some(allowlist_entry_pubkey) = allowlist_entry_pubkey {
+        accounts.push(AccountMeta::new_readonly(*allowlist_entry_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_all_token_types' instruction.
+pub fn withdraw_all_token_types(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_token_a_pubkey: &Pubkey,
+    destination_token_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    rate_provider_pubkey: Option<&Pubkey>,
+    allowlist_entry_pubkey: Option<&Pubkey>,
+    instruction: WithdrawAllTokenTypes,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawAllTokenTypes(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_token_a_pubkey, false),
+        AccountMeta::new(*destination_token_b_pubkey, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+    ];
+    if let Some(rate_provider_pubkey) = rate_provider_pubkey {
+        accounts.push(AccountMeta::new_readonly(*rate_provider_pubkey, false));
+    }
+    if let Some(allowlist_entry_pubkey) = allowlist_entry_pubkey {
+        accounts.push(AccountMeta::new_readonly(*allowlist_entry_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_single_token_type_exact_amount_in' instruction.
+pub fn deposit_single_token_type_exact_amount_in(
+    program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_token_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    rate_provider_pubkey: Option<&Pubkey>,
+    allowlist_entry_pubkey: Option<&Pubkey>,
+    instruction: DepositSingleTokenTypeExactAmountIn,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositSingleTokenTypeExactAmountIn(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_token_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    if let Some(rate_provider_pubkey) = rate_provider_pubkey {
+        accounts.push(AccountMeta::new_readonly(*rate_provider_pubkey, false));
+    }
+    if let Some(allowlist_entry_pubkey) = allowlist_entry_pubkey {
+        accounts.push(AccountMeta::new_readonly(*allowlist_entry_pubkey, false));
+    }
 
-/// Extra accounts needed for RedemptionRate curve
-pub struct RedemptionRateExtraAccounts<'a> {
-    /// pda created at initialization
-    pub permission_account: &'a Pubkey,
-    /// authority of permission account
-    pub authority: &'a Pubkey,
-    /// payer of account creation
-    pub payer: &'a Pubkey,
-    /// system program
-    pub system_program: &'a Pubkey
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
 }
 
-/// Creates an 'initialize' instruction.
-pub fn initialize(
+/// Creates a 'withdraw_single_token_type_exact_amount_out' instruction.
+pub fn withdraw_single_token_type_exact_amount_out(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
-    token_a_pubkey: &Pubkey,
-    token_b_pubkey: &Pubkey,
-    pool_pubkey: &Pubkey,
-    token_a_mint_pubkey: &Pubkey,
-    token_b_mint_pubkey: &Pubkey,
-    fee_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    pool_token_source_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
-    fees: Fees,
-    swap_curve: SwapCurve,
-    redemption_rate_extra_accounts: Option<RedemptionRateExtraAccounts>
+    destination_mint_pubkey: &Pubkey,
+    rate_provider_pubkey: Option<&Pubkey>,
+    allowlist_entry_pubkey: Option<&Pubkey>,
+    instruction: WithdrawSingleTokenTypeExactAmountOut,
 ) -> Result<Instruction, ProgramError> {
-    let curve_type = swap_curve.curve_type.clone();
-    let init_data = SwapInstruction::Initialize(Initialize { fees, swap_curve });
-    let data = init_data.pack();
+    let data = SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(instruction).pack();
 
     let mut accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new_readonly(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
-        AccountMeta::new_readonly(*token_a_pubkey, false),
-        AccountMeta::new_readonly(*token_b_pubkey, false),
-        AccountMeta::new(*pool_pubkey, false),
-        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
-        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
-        AccountMeta::new_readonly(*fee_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_token_source_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
         AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
     ];
+    if let Some(rate_provider_pubkey) = rate_provider_pubkey {
+        accounts.push(AccountMeta::new_readonly(*rate_provider_pubkey, false));
+    }
+    if let Some(allowlist_entry_pubkey) = allowlist_entry_pubkey {
+        accounts.push(AccountMeta::new_readonly(*allowlist_entry_pubkey, false));
+    }
 
-    if curve_type == CurveType::RedemptionRateCurve {
-        let RedemptionRateExtraAccounts {
-            permission_account,
-            authority,
-            payer,
-            system_program
-        } = redemption_rate_extra_accounts.ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
 
-        accounts.push(AccountMeta::new(*permission_account, false));
-        accounts.push(AccountMeta::new_readonly(*authority, false));
-        accounts.push(AccountMeta::new(*payer, true));
-        accounts.push(AccountMeta::new_readonly(*system_program, false));
+/// Creates a 'swap' instruction.
+pub fn swap(
+    program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    rate_provider_pubkey: Option<&Pubkey>,
+    allowlist_entry_pubkey: Option<&Pubkey>,
+    host_fee_pubkey: Option<&Pubkey>,
+    instruction: Swap,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Swap(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_fee_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    if let Some(rate_provider_pubkey) = rate_provider_pubkey {
+        accounts.push(AccountMeta::new_readonly(*rate_provider_pubkey, false));
+    }
+    if let Some(allowlist_entry_pubkey) = allowlist_entry_pubkey {
+        accounts.push(AccountMeta::new_readonly(*allowlist_entry_pubkey, false));
+    }
+    if let Some(host_fee_pubkey) = host_fee_pubkey {
+        accounts.push(AccountMeta::new(*host_fee_pubkey, false));
     }
 
     Ok(Instruction {
@@ -607,42 +2145,202 @@ pub fn initialize(
     })
 }
 
-/// Creates a 'deposit_all_token_types' instruction.
-pub fn deposit_all_token_types(
+/// Creates a 'swap_exact_amount_out' instruction.
+pub fn swap_exact_amount_out(
     program_id: &Pubkey,
-    token_a_program_id: &Pubkey,
-    token_b_program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
     pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
-    deposit_token_a_pubkey: &Pubkey,
-    deposit_token_b_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    rate_provider_pubkey: Option<&Pubkey>,
+    allowlist_entry_pubkey: Option<&Pubkey>,
+    host_fee_pubkey: Option<&Pubkey>,
+    instruction: SwapExactAmountOut,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SwapExactAmountOut(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_fee_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    if let Some(rate_provider_pubkey) = rate_provider_pubkey {
+        accounts.push(AccountMeta::new_readonly(*rate_provider_pubkey, false));
+    }
+    if let Some(allowlist_entry_pubkey) = allowlist_entry_pubkey {
+        accounts.push(AccountMeta::new_readonly(*allowlist_entry_pubkey, false));
+    }
+    if let Some(host_fee_pubkey) = host_fee_pubkey {
+        accounts.push(AccountMeta::new(*host_fee_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'preview_swap' instruction.
+pub fn preview_swap(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    instruction: PreviewSwap,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::PreviewSwap(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*swap_source_pubkey, false),
+        AccountMeta::new_readonly(*swap_destination_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'preview_deposit' instruction.
+pub fn preview_deposit(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
     swap_token_a_pubkey: &Pubkey,
     swap_token_b_pubkey: &Pubkey,
     pool_mint_pubkey: &Pubkey,
-    destination_pubkey: &Pubkey,
-    token_a_mint_pubkey: &Pubkey,
-    token_b_mint_pubkey: &Pubkey,
-    instruction: DepositAllTokenTypes,
+    source_mint_pubkey: &Pubkey,
+    instruction: PreviewDeposit,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::DepositAllTokenTypes(instruction).pack();
+    let data = SwapInstruction::PreviewDeposit(instruction).pack();
 
     let accounts = vec![
         AccountMeta::new_readonly(*swap_pubkey, false),
-        AccountMeta::new_readonly(*authority_pubkey, false),
-        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
-        AccountMeta::new(*deposit_token_a_pubkey, false),
-        AccountMeta::new(*deposit_token_b_pubkey, false),
-        AccountMeta::new(*swap_token_a_pubkey, false),
-        AccountMeta::new(*swap_token_b_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_a_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_b_pubkey, false),
+        AccountMeta::new_readonly(*pool_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'preview_withdraw' instruction.
+pub fn preview_withdraw(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    instruction: PreviewWithdraw,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::PreviewWithdraw(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_a_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_b_pubkey, false),
+        AccountMeta::new_readonly(*pool_mint_pubkey, false),
+        AccountMeta::new_readonly(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'get_conversion_rate' instruction.
+pub fn get_conversion_rate(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    instruction: GetConversionRate,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::GetConversionRate(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Accounts of a three-asset pool, indexed by `Psm3Asset`
+pub struct Psm3Accounts<'a> {
+    /// three-asset pool
+    pub pool: &'a Pubkey,
+    /// pool authority
+    pub authority: &'a Pubkey,
+    /// USDC, USDS and sUSDS reserve accounts
+    pub reserves: [&'a Pubkey; 3],
+    /// pool token mint
+    pub pool_mint: &'a Pubkey,
+    /// pool token program
+    pub pool_token_program_id: &'a Pubkey,
+}
+
+/// Creates an 'initialize_psm3' instruction.
+pub fn initialize_psm3(
+    program_id: &Pubkey,
+    pool: Psm3Accounts,
+    mints: [&Pubkey; 3],
+    destination_pubkey: &Pubkey,
+    extra_accounts: RedemptionRateExtraAccounts,
+    instruction: InitializePsm3,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::InitializePsm3(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*pool.pool, true),
+        AccountMeta::new_readonly(*pool.authority, false),
+        AccountMeta::new_readonly(*pool.reserves[0], false),
+        AccountMeta::new_readonly(*pool.reserves[1], false),
+        AccountMeta::new_readonly(*pool.reserves[2], false),
+        AccountMeta::new_readonly(*mints[0], false),
+        AccountMeta::new_readonly(*mints[1], false),
+        AccountMeta::new_readonly(*mints[2], false),
+        AccountMeta::new(*pool.pool_mint, false),
         AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
-        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
-        AccountMeta::new_readonly(*token_a_program_id, false),
-        AccountMeta::new_readonly(*token_b_program_id, false),
-        AccountMeta::new_readonly(*pool_token_program_id, false),
+        AccountMeta::new_readonly(*pool.pool_token_program_id, false),
+        AccountMeta::new(*extra_accounts.permission_account, false),
+        AccountMeta::new_readonly(*extra_accounts.authority, false),
+        AccountMeta::new(*extra_accounts.payer, true),
+        AccountMeta::new_readonly(*extra_accounts.system_program, false),
     ];
 
     Ok(Instruction {
@@ -652,44 +2350,36 @@ pub fn deposit_all_token_types(
     })
 }
 
-/// Creates a 'withdraw_all_token_types' instruction.
-pub fn withdraw_all_token_types(
+/// Creates a 'psm3_swap' instruction.
+pub fn psm3_swap(
     program_id: &Pubkey,
-    pool_token_program_id: &Pubkey,
-    token_a_program_id: &Pubkey,
-    token_b_program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    fee_account_pubkey: &Pubkey,
     source_pubkey: &Pubkey,
-    swap_token_a_pubkey: &Pubkey,
-    swap_token_b_pubkey: &Pubkey,
-    destination_token_a_pubkey: &Pubkey,
-    destination_token_b_pubkey: &Pubkey,
-    token_a_mint_pubkey: &Pubkey,
-    token_b_mint_pubkey: &Pubkey,
-    instruction: WithdrawAllTokenTypes,
+    pool_source_pubkey: &Pubkey,
+    pool_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    instruction: Psm3Swap,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::WithdrawAllTokenTypes(instruction).pack();
+    let data = SwapInstruction::Psm3Swap(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*pool_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
-        AccountMeta::new(*pool_mint_pubkey, false),
         AccountMeta::new(*source_pubkey, false),
-        AccountMeta::new(*swap_token_a_pubkey, false),
-        AccountMeta::new(*swap_token_b_pubkey, false),
-        AccountMeta::new(*destination_token_a_pubkey, false),
-        AccountMeta::new(*destination_token_b_pubkey, false),
-        AccountMeta::new(*fee_account_pubkey, false),
-        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
-        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
-        AccountMeta::new_readonly(*pool_token_program_id, false),
-        AccountMeta::new_readonly(*token_a_program_id, false),
-        AccountMeta::new_readonly(*token_b_program_id, false),
+        AccountMeta::new(*pool_source_pubkey, false),
+        AccountMeta::new(*pool_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
     ];
 
     Ok(Instruction {
@@ -699,36 +2389,32 @@ pub fn withdraw_all_token_types(
     })
 }
 
-/// Creates a 'deposit_single_token_type_exact_amount_in' instruction.
-pub fn deposit_single_token_type_exact_amount_in(
+/// Creates a 'psm3_deposit' instruction.
+pub fn psm3_deposit(
     program_id: &Pubkey,
     source_token_program_id: &Pubkey,
-    pool_token_program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
+    pool: Psm3Accounts,
     user_transfer_authority_pubkey: &Pubkey,
-    source_token_pubkey: &Pubkey,
-    swap_token_a_pubkey: &Pubkey,
-    swap_token_b_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
     source_mint_pubkey: &Pubkey,
-    instruction: DepositSingleTokenTypeExactAmountIn,
+    instruction: Psm3Deposit,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::DepositSingleTokenTypeExactAmountIn(instruction).pack();
+    let data = SwapInstruction::Psm3Deposit(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
-        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*pool.pool, false),
+        AccountMeta::new_readonly(*pool.authority, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
-        AccountMeta::new(*source_token_pubkey, false),
-        AccountMeta::new(*swap_token_a_pubkey, false),
-        AccountMeta::new(*swap_token_b_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*pool.reserves[0], false),
+        AccountMeta::new(*pool.reserves[1], false),
+        AccountMeta::new(*pool.reserves[2], false),
+        AccountMeta::new(*pool.pool_mint, false),
         AccountMeta::new(*destination_pubkey, false),
         AccountMeta::new_readonly(*source_mint_pubkey, false),
         AccountMeta::new_readonly(*source_token_program_id, false),
-        AccountMeta::new_readonly(*pool_token_program_id, false),
+        AccountMeta::new_readonly(*pool.pool_token_program_id, false),
     ];
 
     Ok(Instruction {
@@ -738,37 +2424,31 @@ pub fn deposit_single_token_type_exact_amount_in(
     })
 }
 
-/// Creates a 'withdraw_single_token_type_exact_amount_out' instruction.
-pub fn withdraw_single_token_type_exact_amount_out(
+/// Creates a 'psm3_withdraw' instruction.
+pub fn psm3_withdraw(
     program_id: &Pubkey,
-    pool_token_program_id: &Pubkey,
     destination_token_program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
+    pool: Psm3Accounts,
     user_transfer_authority_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    fee_account_pubkey: &Pubkey,
-    pool_token_source_pubkey: &Pubkey,
-    swap_token_a_pubkey: &Pubkey,
-    swap_token_b_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
     destination_mint_pubkey: &Pubkey,
-    instruction: WithdrawSingleTokenTypeExactAmountOut,
+    instruction: Psm3Withdraw,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(instruction).pack();
+    let data = SwapInstruction::Psm3Withdraw(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
-        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*pool.pool, false),
+        AccountMeta::new_readonly(*pool.authority, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
-        AccountMeta::new(*pool_mint_pubkey, false),
-        AccountMeta::new(*pool_token_source_pubkey, false),
-        AccountMeta::new(*swap_token_a_pubkey, false),
-        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*pool.pool_mint, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*pool.reserves[0], false),
+        AccountMeta::new(*pool.reserves[1], false),
+        AccountMeta::new(*pool.reserves[2], false),
         AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new(*fee_account_pubkey, false),
         AccountMeta::new_readonly(*destination_mint_pubkey, false),
-        AccountMeta::new_readonly(*pool_token_program_id, false),
+        AccountMeta::new_readonly(*pool.pool_token_program_id, false),
         AccountMeta::new_readonly(*destination_token_program_id, false),
     ];
 
@@ -779,47 +2459,73 @@ pub fn withdraw_single_token_type_exact_amount_out(
     })
 }
 
-/// Creates a 'swap' instruction.
-pub fn swap(
+/// Creates an 'initialize_rate_provider' instruction.
+pub fn initialize_rate_provider(
+    program_id: &Pubkey,
+    rate_provider_pubkey: &Pubkey,
+    extra_accounts: RedemptionRateExtraAccounts,
+    instruction: InitializeRateProvider,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::InitializeRateProvider(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*rate_provider_pubkey, true),
+        AccountMeta::new(*extra_accounts.permission_account, false),
+        AccountMeta::new_readonly(*extra_accounts.authority, false),
+        AccountMeta::new(*extra_accounts.payer, true),
+        AccountMeta::new_readonly(*extra_accounts.system_program, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'RelaySignedRates' instruction, to be preceded by the Ed25519
+/// program instruction verifying the signature of the permission's authority.
+pub fn relay_signed_rates(
     program_id: &Pubkey,
-    source_token_program_id: &Pubkey,
-    destination_token_program_id: &Pubkey,
-    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    user_transfer_authority_pubkey: &Pubkey,
-    source_pubkey: &Pubkey,
-    swap_source_pubkey: &Pubkey,
-    swap_destination_pubkey: &Pubkey,
-    destination_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    pool_fee_pubkey: &Pubkey,
-    source_mint_pubkey: &Pubkey,
-    destination_mint_pubkey: &Pubkey,
-    host_fee_pubkey: Option<&Pubkey>,
-    instruction: Swap,
+    permission_pubkey: &Pubkey,
+    relay_nonce_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    instruction: RelaySignedRates,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Swap(instruction).pack();
+    let data = SwapInstruction::RelaySignedRates(instruction).pack();
 
-    let mut accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
-        AccountMeta::new_readonly(*authority_pubkey, false),
-        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
-        AccountMeta::new(*source_pubkey, false),
-        AccountMeta::new(*swap_source_pubkey, false),
-        AccountMeta::new(*swap_destination_pubkey, false),
-        AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
-        AccountMeta::new(*pool_fee_pubkey, false),
-        AccountMeta::new_readonly(*source_mint_pubkey, false),
-        AccountMeta::new_readonly(*destination_mint_pubkey, false),
-        AccountMeta::new_readonly(*source_token_program_id, false),
-        AccountMeta::new_readonly(*destination_token_program_id, false),
-        AccountMeta::new_readonly(*pool_token_program_id, false),
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*permission_pubkey, false),
+        AccountMeta::new(*relay_nonce_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(sysvar::instructions::ID, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'BridgeSetRates' instruction, to be invoked by the bridge
+/// receiver signing with `bridge_authority_pubkey`.
+pub fn bridge_set_rates(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    bridge_authority_pubkey: &Pubkey,
+    instruction: BridgeSetRates,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::BridgeSetRates(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*bridge_authority_pubkey, true),
+        AccountMeta::new_readonly(sysvar::instructions::ID, false),
     ];
-    if let Some(host_fee_pubkey) = host_fee_pubkey {
-        accounts.push(AccountMeta::new(*host_fee_pubkey, false));
-    }
 
     Ok(Instruction {
         program_id: *program_id,
@@ -876,38 +2582,264 @@ mod tests {
         };
         let check = SwapInstruction::Initialize(Initialize { fees, swap_curve });
         let packed = check.pack();
-        let mut expect = vec![0u8];
-        expect.extend_from_slice(&trade_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&trade_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&owner_trade_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&owner_trade_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&owner_withdraw_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&owner_withdraw_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&host_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&host_fee_denominator.to_le_bytes());
-        expect.push(curve_type as u8);
-        expect.extend_from_slice(&token_b_offset.to_le_bytes());
-        expect.extend_from_slice(&[0u8; 56]);
+        let mut expect = vec![0u8];
+        expect.extend_from_slice(&trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&owner_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&owner_trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&owner_withdraw_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&owner_withdraw_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&host_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&host_fee_denominator.to_le_bytes());
+        expect.push(curve_type as u8);
+        expect.extend_from_slice(&token_b_offset.to_le_bytes());
+        expect.extend_from_slice(&[0u8; 56]);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_swap() {
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let check = SwapInstruction::Swap(Swap {
+            amount_in,
+            minimum_amount_out,
+            referral_code: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![1];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_swap_exact_amount_out() {
+        let amount_out: u64 = 10;
+        let maximum_amount_in: u64 = 2;
+        let check = SwapInstruction::SwapExactAmountOut(SwapExactAmountOut {
+            amount_out,
+            maximum_amount_in,
+            referral_code: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![32];
+        expect.extend_from_slice(&amount_out.to_le_bytes());
+        expect.extend_from_slice(&maximum_amount_in.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_previews() {
+        let amount: u64 = 1_000;
+        let check = SwapInstruction::PreviewSwap(PreviewSwap {
+            amount,
+            exact_out: true,
+        });
+        let packed = check.pack();
+        let mut expect = vec![33];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.push(1);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::PreviewDeposit(PreviewDeposit {
+            source_token_amount: amount,
+        });
+        let packed = check.pack();
+        let mut expect = vec![34];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::PreviewWithdraw(PreviewWithdraw {
+            destination_token_amount: amount,
+        });
+        let packed = check.pack();
+        let mut expect = vec![35];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_get_conversion_rate() {
+        let check = SwapInstruction::GetConversionRate(GetConversionRate { timestamp: None });
+        let packed = check.pack();
+        assert_eq!(packed, vec![36]);
+        let unpacked = SwapInstruction::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+
+        let timestamp = 1_700_000_000u64;
+        let check = SwapInstruction::GetConversionRate(GetConversionRate {
+            timestamp: Some(timestamp),
+        });
+        let packed = check.pack();
+        let mut expect = vec![36];
+        expect.extend_from_slice(&timestamp.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // a truncated timestamp is rejected
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..5]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+    }
+
+    #[test]
+    fn pack_psm3() {
+        let rates = RedemptionRateCurve {
+            max_ssr: 1_000_000_003_022_265_980_097_387_650,
+            ssr: 1_000_000_001_547_125_957_863_212_448,
+            rho: 1_700_000_000,
+            chi: 1_050_000_000_000_000_000_000_000_000,
+        };
+        let check = SwapInstruction::InitializePsm3(InitializePsm3 { rates: rates.clone() });
+        let packed = check.pack();
+        let mut expect = vec![37];
+        expect.extend_from_slice(&rates.max_ssr.to_le_bytes());
+        expect.extend_from_slice(&rates.ssr.to_le_bytes());
+        expect.extend_from_slice(&rates.rho.to_le_bytes());
+        expect.extend_from_slice(&rates.chi.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..64]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+
+        let check = SwapInstruction::Psm3Swap(Psm3Swap {
+            amount_in: 1_000,
+            minimum_amount_out: 950,
+        });
+        let packed = check.pack();
+        let mut expect = vec![38];
+        expect.extend_from_slice(&1_000u64.to_le_bytes());
+        expect.extend_from_slice(&950u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::Psm3Deposit(Psm3Deposit {
+            source_token_amount: 1_000,
+            minimum_pool_token_amount: 500,
+        });
+        let packed = check.pack();
+        let mut expect = vec![39];
+        expect.extend_from_slice(&1_000u64.to_le_bytes());
+        expect.extend_from_slice(&500u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::Psm3Withdraw(Psm3Withdraw {
+            destination_token_amount: 1_000,
+            maximum_pool_token_amount: 501,
+        });
+        let packed = check.pack();
+        let mut expect = vec![40];
+        expect.extend_from_slice(&1_000u64.to_le_bytes());
+        expect.extend_from_slice(&501u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_bridge_receiver() {
+        let bridge_receiver = [6u8; 32];
+        let check = SwapInstruction::SetBridgeReceiver(SetBridgeReceiver { bridge_receiver });
+        let packed = check.pack();
+        let mut expect = vec![43];
+        expect.extend_from_slice(&bridge_receiver);
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..32]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
     }
 
     #[test]
-    fn pack_swap() {
-        let amount_in: u64 = 2;
-        let minimum_amount_out: u64 = 10;
-        let check = SwapInstruction::Swap(Swap {
-            amount_in,
-            minimum_amount_out,
+    fn pack_bridge_set_rates() {
+        let ssr = 1_000_000_001_547_125_957_863_212_448;
+        let rho = 1_700_000_000;
+        let chi = 1_050_000_000_000_000_000_000_000_000;
+        let check = SwapInstruction::BridgeSetRates(BridgeSetRates { ssr, rho, chi });
+        let packed = check.pack();
+        let mut expect = vec![44];
+        expect.extend_from_slice(&ssr.to_le_bytes());
+        expect.extend_from_slice(&rho.to_le_bytes());
+        expect.extend_from_slice(&chi.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_relay_signed_rates() {
+        let ssr = 1_000_000_001_547_125_957_863_212_448;
+        let rho = 1_700_000_000;
+        let chi = 1_050_000_000_000_000_000_000_000_000;
+        let nonce = 42;
+        let check = SwapInstruction::RelaySignedRates(RelaySignedRates {
+            ssr,
+            rho,
+            chi,
+            nonce,
         });
         let packed = check.pack();
-        let mut expect = vec![1];
-        expect.extend_from_slice(&amount_in.to_le_bytes());
-        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        let mut expect = vec![42];
+        expect.extend_from_slice(&ssr.to_le_bytes());
+        expect.extend_from_slice(&rho.to_le_bytes());
+        expect.extend_from_slice(&chi.to_le_bytes());
+        expect.extend_from_slice(&nonce.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..expect.len() - 1]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+    }
+
+    #[test]
+    fn pack_initialize_rate_provider() {
+        let rates = RedemptionRateCurve {
+            max_ssr: 1_000_000_003_022_265_980_097_387_650,
+            ssr: 1_000_000_001_547_125_957_863_212_448,
+            rho: 1_700_000_000,
+            chi: 1_050_000_000_000_000_000_000_000_000,
+        };
+        let check = SwapInstruction::InitializeRateProvider(InitializeRateProvider {
+            rates: rates.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![41];
+        expect.extend_from_slice(&rates.max_ssr.to_le_bytes());
+        expect.extend_from_slice(&rates.ssr.to_le_bytes());
+        expect.extend_from_slice(&rates.rho.to_le_bytes());
+        expect.extend_from_slice(&rates.chi.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..64]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
     }
 
     #[test]
@@ -919,6 +2851,7 @@ mod tests {
             pool_token_amount,
             maximum_token_a_amount,
             maximum_token_b_amount,
+            referral_code: None,
         });
         let packed = check.pack();
         let mut expect = vec![2];
@@ -930,6 +2863,60 @@ mod tests {
         assert_eq!(unpacked, check);
     }
 
+    #[test]
+    fn pack_referral_code() {
+        let referral_code = 42u64;
+        let checks = [
+            SwapInstruction::Swap(Swap {
+                amount_in: 2,
+                minimum_amount_out: 10,
+                referral_code: Some(referral_code),
+            }),
+            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
+                pool_token_amount: 5,
+                maximum_token_a_amount: 10,
+                maximum_token_b_amount: 20,
+                referral_code: Some(referral_code),
+            }),
+            SwapInstruction::DepositSingleTokenTypeExactAmountIn(
+                DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount: 10,
+                    minimum_pool_token_amount: 5,
+                    referral_code: Some(referral_code),
+                },
+            ),
+            SwapInstruction::SwapExactAmountOut(SwapExactAmountOut {
+                amount_out: 10,
+                maximum_amount_in: 2,
+                referral_code: Some(referral_code),
+            }),
+        ];
+
+        for check in checks {
+            let packed = check.pack();
+            assert_eq!(&packed[packed.len() - 8..], &referral_code.to_le_bytes());
+            let unpacked = SwapInstruction::unpack(&packed).unwrap();
+            assert_eq!(unpacked, check);
+        }
+
+        // trailing bytes too short for a code are ignored, as before
+        let mut packed = SwapInstruction::Swap(Swap {
+            amount_in: 2,
+            minimum_amount_out: 10,
+            referral_code: None,
+        })
+        .pack();
+        packed.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(
+            SwapInstruction::unpack(&packed).unwrap(),
+            SwapInstruction::Swap(Swap {
+                amount_in: 2,
+                minimum_amount_out: 10,
+                referral_code: None,
+            })
+        );
+    }
+
     #[test]
     fn pack_withdraw() {
         let pool_token_amount: u64 = 1212438012089;
@@ -958,6 +2945,7 @@ mod tests {
             DepositSingleTokenTypeExactAmountIn {
                 source_token_amount,
                 minimum_pool_token_amount,
+                referral_code: None,
             },
         );
         let packed = check.pack();
@@ -987,4 +2975,375 @@ mod tests {
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
+
+    #[test]
+    fn pack_initialize_permission() {
+        let permission_authority = [3u8; 32];
+        let roles = roles::UPDATE_RATES | roles::PAUSE | roles::UPDATE_FEE_ACCOUNT;
+        let check = SwapInstruction::InitializePermission(InitializePermission {
+            permission_authority,
+            roles,
+        });
+        let packed = check.pack();
+        let mut expect = vec![7];
+        expect.extend_from_slice(&permission_authority);
+        expect.extend_from_slice(&roles.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // instruction data with legacy booleans is still accepted
+        let mut legacy = vec![7];
+        legacy.extend_from_slice(&permission_authority);
+        legacy.extend_from_slice(&[0, 1]);
+        let unpacked = SwapInstruction::unpack(&legacy).unwrap();
+        assert_eq!(
+            unpacked,
+            SwapInstruction::InitializePermission(InitializePermission {
+                permission_authority,
+                roles: roles::UPDATE_RATES,
+            })
+        );
+
+        // only the two booleans of the original layout
+        legacy.push(1);
+        assert!(SwapInstruction::unpack(&legacy).is_err());
+    }
+
+    #[test]
+    fn pack_update_permission() {
+        let check = SwapInstruction::UpdatePermission(UpdatePermission {
+            roles: roles::SUPER_ADMIN | roles::MANAGE_LIQUIDITY,
+        });
+        let packed = check.pack();
+        let mut expect = vec![8];
+        expect.extend_from_slice(&(roles::SUPER_ADMIN | roles::MANAGE_LIQUIDITY).to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let unpacked = SwapInstruction::unpack(&[8, 1, 0]).unwrap();
+        assert_eq!(
+            unpacked,
+            SwapInstruction::UpdatePermission(UpdatePermission {
+                roles: roles::SUPER_ADMIN,
+            })
+        );
+
+        assert_eq!(
+            SwapInstruction::unpack(&[8, 1, 0, 2]),
+            Err(SwapError::InvalidInstruction.into())
+        );
+        assert_eq!(
+            SwapInstruction::unpack(&[8, 1, 0, 1, 2]),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn pack_set_paused() {
+        let check = SwapInstruction::SetPaused(SetPaused { is_paused: true });
+        let packed = check.pack();
+        let expect = vec![9, 1];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_migrate_swap() {
+        let check = SwapInstruction::MigrateSwap;
+        let packed = check.pack();
+        let expect = vec![10];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_max_staleness() {
+        let max_staleness: u64 = 86_400;
+        let check = SwapInstruction::SetMaxStaleness(SetMaxStaleness { max_staleness });
+        let packed = check.pack();
+        let mut expect = vec![11];
+        expect.extend_from_slice(&max_staleness.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        assert_eq!(
+            SwapInstruction::unpack(&[11, 1, 2, 3]),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn pack_set_max_ssr() {
+        let max_ssr: u128 = 1_000_000_021_979_553_151_239_153_020;
+        let check = SwapInstruction::SetMaxSsr(SetMaxSsr { max_ssr });
+        let packed = check.pack();
+        let mut expect = vec![12];
+        expect.extend_from_slice(&max_ssr.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_update_fees() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 2,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 3,
+            owner_withdraw_fee_denominator: 10_000,
+            host_fee_numerator: 4,
+            host_fee_denominator: 100,
+        };
+        let check = SwapInstruction::UpdateFees(UpdateFees { fees: fees.clone() });
+        let packed = check.pack();
+        let mut expect = vec![13];
+        let mut fees_slice = [0u8; Fees::LEN];
+        Pack::pack_into_slice(&fees, &mut fees_slice[..]);
+        expect.extend_from_slice(&fees_slice);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..Fees::LEN]),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn pack_propose_super_admin() {
+        let new_authority = [5u8; 32];
+        let check = SwapInstruction::ProposeSuperAdmin(ProposeSuperAdmin { new_authority });
+        let packed = check.pack();
+        let mut expect = vec![14];
+        expect.extend_from_slice(&new_authority);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..32]),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn pack_accept_super_admin() {
+        let check = SwapInstruction::AcceptSuperAdmin;
+        let packed = check.pack();
+        let expect = vec![15];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_close_permission() {
+        let check = SwapInstruction::ClosePermission;
+        let packed = check.pack();
+        let expect = vec![16];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_migrate_permission() {
+        let check = SwapInstruction::MigratePermission;
+        let packed = check.pack();
+        let expect = vec![17];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_timelock_delay() {
+        let timelock_delay = 172_800u64;
+        let check = SwapInstruction::SetTimelockDelay(SetTimelockDelay { timelock_delay });
+        let packed = check.pack();
+        let mut expect = vec![18];
+        expect.extend_from_slice(&timelock_delay.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_queue_change() {
+        let fee_account = Pubkey::new_unique();
+        let check = SwapInstruction::QueueChange(QueueChange {
+            change: ParameterChange::FeeAccount(fee_account),
+        });
+        let packed = check.pack();
+        let mut expect = vec![19, 2];
+        expect.extend_from_slice(fee_account.as_ref());
+        expect.extend_from_slice(&[0u8; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        expect.pop();
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn pack_execute_change() {
+        let check = SwapInstruction::ExecuteChange;
+        let packed = check.pack();
+        let expect = vec![20];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_cancel_change() {
+        let check = SwapInstruction::CancelChange;
+        let packed = check.pack();
+        let expect = vec![21];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_rate_update_limits() {
+        let max_ssr_delta = 1_000_000_000u128;
+        let min_update_interval = 3_600u64;
+        let check = SwapInstruction::SetRateUpdateLimits(SetRateUpdateLimits {
+            max_ssr_delta,
+            min_update_interval,
+        });
+        let packed = check.pack();
+        let mut expect = vec![22];
+        expect.extend_from_slice(&max_ssr_delta.to_le_bytes());
+        expect.extend_from_slice(&min_update_interval.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_rate_multisig() {
+        let check = SwapInstruction::SetRateMultisig(SetRateMultisig { threshold: 2 });
+        let packed = check.pack();
+        let expect = vec![23, 2];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_fee_account() {
+        let check = SwapInstruction::SetFeeAccount;
+        let packed = check.pack();
+        let expect = vec![24];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_close_pool() {
+        let check = SwapInstruction::ClosePool;
+        let packed = check.pack();
+        let expect = vec![25];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_swap_directions() {
+        let check = SwapInstruction::SetSwapDirections(SetSwapDirections {
+            a_to_b_enabled: true,
+            b_to_a_enabled: false,
+        });
+        let packed = check.pack();
+        let expect = vec![26, 1, 0];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_allowlist_mode() {
+        let check = SwapInstruction::SetAllowlistMode(SetAllowlistMode {
+            liquidity_allowlisted: true,
+            swaps_allowlisted: false,
+        });
+        let packed = check.pack();
+        let expect = vec![27, 1, 0];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_add_to_allowlist() {
+        let user = [5u8; 32];
+        let check = SwapInstruction::AddToAllowlist(AddToAllowlist { user });
+        let packed = check.pack();
+        let mut expect = vec![28];
+        expect.extend_from_slice(&user);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_remove_from_allowlist() {
+        let check = SwapInstruction::RemoveFromAllowlist;
+        let packed = check.pack();
+        let expect = vec![29];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_swap_rate_limits() {
+        let a_to_b_max_amount = 1_000_000u64;
+        let a_to_b_slope = 10u64;
+        let b_to_a_max_amount = 0u64;
+        let b_to_a_slope = 0u64;
+        let check = SwapInstruction::SetSwapRateLimits(SetSwapRateLimits {
+            a_to_b_max_amount,
+            a_to_b_slope,
+            b_to_a_max_amount,
+            b_to_a_slope,
+        });
+        let packed = check.pack();
+        let mut expect = vec![30];
+        expect.extend_from_slice(&a_to_b_max_amount.to_le_bytes());
+        expect.extend_from_slice(&a_to_b_slope.to_le_bytes());
+        expect.extend_from_slice(&b_to_a_max_amount.to_le_bytes());
+        expect.extend_from_slice(&b_to_a_slope.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_max_tvl() {
+        let max_tvl = 1_000_000_000_000u128;
+        let check = SwapInstruction::SetMaxTvl(SetMaxTvl { max_tvl });
+        let packed = check.pack();
+        let mut expect = vec![31];
+        expect.extend_from_slice(&max_tvl.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
 }
@@ -13,6 +13,18 @@ pub mod processor;
 pub mod state;
 pub mod redemption_rate_processor;
 pub mod permission;
+pub mod admin_processor;
+pub mod timelock;
+pub mod multisig;
+pub mod allowlist;
+pub mod rate_limit;
+pub mod preview;
+pub mod event;
+pub mod psm3;
+pub mod rate_provider;
+pub mod relay;
+pub mod bridge;
+pub mod utils;
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! M-of-N multisig allowed to update the rates of a pool,
+//! so that rate updates don't depend on a single keeper key.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    permission::{roles, Permission},
+    utils::{close_account, create_pda_account},
+    ID as PROGRAM_ID,
+};
+
+/// Maximum number of signers of a rate multisig
+pub const MAX_SIGNERS: usize = 11;
+
+/// Multisig whose signers can update the rates of a swap
+/// once `threshold` of them sign the same transaction
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RateMultisig {
+    /// Boolean set to true after a RateMultisig is created
+    pub is_initialized: bool,
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// Number of signers required
+    pub threshold: u8,
+    /// Number of valid signers
+    pub signers_count: u8,
+    /// Signer public keys
+    pub signers: [Pubkey; MAX_SIGNERS],
+}
+
+impl IsInitialized for RateMultisig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RateMultisig {}
+
+impl Pack for RateMultisig {
+    const LEN: usize = 387;
+
+    fn unpack_from_slice(input: &[u8]) -> Result<RateMultisig, ProgramError> {
+        let input = array_ref![input, 0, RateMultisig::LEN];
+        let (
+            is_initialized,
+            swap,
+            threshold,
+            signers_count,
+            signers_flat
+        ) = array_refs![input, 1, 32, 1, 1, 32 * MAX_SIGNERS];
+
+        let mut signers = [Pubkey::default(); MAX_SIGNERS];
+        for (signer, bytes) in signers.iter_mut().zip(signers_flat.chunks_exact(32)) {
+            *signer = Pubkey::try_from(bytes).map_err(|_| ProgramError::InvalidAccountData)?;
+        }
+
+        Ok(Self {
+            is_initialized: is_initialized[0] != 0,
+            swap: Pubkey::new_from_array(*swap),
+            threshold: threshold[0],
+            signers_count: signers_count[0],
+            signers,
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RateMultisig::LEN];
+        let (
+            is_initialized,
+            swap,
+            threshold,
+            signers_count,
+            signers_flat
+        ) = mut_array_refs![output, 1, 32, 1, 1, 32 * MAX_SIGNERS];
+
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        threshold[0] = self.threshold;
+        signers_count[0] = self.signers_count;
+        for (signer, bytes) in self.signers.iter().zip(signers_flat.chunks_exact_mut(32)) {
+            bytes.copy_from_slice(signer.as_ref());
+        }
+    }
+}
+
+impl RateMultisig {
+    /// Seed for PDA
+    pub const RATE_MULTISIG_SEED: &'static [u8] = b"rate_multisig";
+
+    /// Derives the rate multisig account of a swap
+    pub fn derive_rate_multisig_pubkey_and_bump(swap: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::RATE_MULTISIG_SEED,
+                &swap.to_bytes()
+            ],
+            &PROGRAM_ID
+        )
+    }
+
+    /// Returns true if `account_info` has the size of a rate multisig
+    pub fn is_rate_multisig(account_info: &AccountInfo) -> bool {
+        account_info.data_len() == Self::LEN
+    }
+
+    /// Validates the rate multisig of the swap and checks that
+    /// at least `threshold` of its signers signed.
+    /// Each signer is only counted once.
+    pub fn validate_signers(
+        multisig_info: &AccountInfo,
+        swap_info: &AccountInfo,
+        signer_infos: &[AccountInfo],
+        program_id: &Pubkey
+    ) -> Result<(), ProgramError> {
+        if multisig_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+
+        let (multisig_address, _) = Self::derive_rate_multisig_pubkey_and_bump(swap_info.key);
+
+        if *multisig_info.key != multisig_address {
+            return Err(SwapError::InvalidUpdatePermission.into())
+        }
+
+        let multisig = Self::unpack(&multisig_info.data.borrow())?;
+
+        if multisig.swap != *swap_info.key {
+            return Err(SwapError::InvalidUpdatePermission.into())
+        }
+
+        let signers = &multisig.signers[..multisig.signers_count as usize];
+        let mut matched = [false; MAX_SIGNERS];
+        let mut num_signers = 0;
+        for signer_info in signer_infos.iter().filter(|info| info.is_signer) {
+            for (position, key) in signers.iter().enumerate() {
+                if key == signer_info.key && !matched[position] {
+                    matched[position] = true;
+                    num_signers += 1;
+                }
+            }
+        }
+
+        if num_signers < multisig.threshold {
+            return Err(ProgramError::MissingRequiredSignature)
+        }
+
+        Ok(())
+    }
+}
+
+/// Processes setting the rate multisig of a swap, creating its account if needed.
+/// A threshold of zero without signers closes the account instead.
+pub fn process_set_rate_multisig(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    threshold: u8
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let multisig_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+    let multisig_signer_infos = accounts_info_iter.as_slice();
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info,
+        swap_info,
+        signer_info,
+        program_id
+    )?;
+
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    let (
+        multisig_address,
+        multisig_bump
+    ) = RateMultisig::derive_rate_multisig_pubkey_and_bump(swap_info.key);
+
+    if *multisig_info.key != multisig_address {
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    let is_multisig_created = multisig_info.owner == program_id;
+
+    if threshold == 0 && multisig_signer_infos.is_empty() {
+        if !is_multisig_created {
+            return Err(ProgramError::UninitializedAccount)
+        }
+
+        return close_account(multisig_info, payer_info)
+    }
+
+    if threshold == 0
+        || multisig_signer_infos.len() > MAX_SIGNERS
+        || threshold as usize > multisig_signer_infos.len()
+    {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let mut signers = [Pubkey::default(); MAX_SIGNERS];
+    for (position, signer_info) in multisig_signer_infos.iter().enumerate() {
+        if signers[..position].contains(signer_info.key) {
+            return Err(ProgramError::InvalidArgument)
+        }
+        signers[position] = *signer_info.key;
+    }
+
+    if !is_multisig_created {
+        create_pda_account(
+            payer_info.clone(),
+            multisig_info.clone(),
+            system_program_info.clone(),
+            RateMultisig::LEN,
+            &[&[
+                RateMultisig::RATE_MULTISIG_SEED,
+                swap_info.key.as_ref(),
+                &[multisig_bump]
+            ]]
+        )?;
+    }
+
+    let multisig = RateMultisig {
+        is_initialized: true,
+        swap: *swap_info.key,
+        threshold,
+        signers_count: multisig_signer_infos.len() as u8,
+        signers,
+    };
+
+    RateMultisig::pack(multisig, &mut multisig_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_multisig_pack() {
+        let mut signers = [Pubkey::default(); MAX_SIGNERS];
+        signers[0] = Pubkey::new_from_array([2u8; 32]);
+        signers[1] = Pubkey::new_from_array([3u8; 32]);
+
+        let multisig = RateMultisig {
+            is_initialized: true,
+            swap: Pubkey::new_from_array([1u8; 32]),
+            threshold: 2,
+            signers_count: 2,
+            signers,
+        };
+
+        let mut packed = [0u8; RateMultisig::LEN];
+        RateMultisig::pack(multisig.clone(), &mut packed).unwrap();
+        assert_eq!(packed[33], 2);
+        assert_eq!(&packed[35..67], &[2u8; 32]);
+        assert_eq!(RateMultisig::unpack(&packed).unwrap(), multisig);
+
+        // a multisig can't be mistaken for a permission
+        assert_ne!(RateMultisig::LEN, Permission::LEN);
+    }
+}
@@ -6,16 +6,51 @@ use solana_program::{
     pubkey::Pubkey,
     program_pack::{IsInitialized, Pack, Sealed},
     program_error::ProgramError,
-    system_instruction::{create_account, transfer, assign, allocate},
+    system_instruction::transfer,
     sysvar::{Sysvar, rent::Rent},
     account_info::AccountInfo,
-    program::{invoke_signed, invoke},
+    program::invoke,
     system_program::ID as SYSTEM_PROGRAM_ID,
     account_info::next_account_info,
 };
 use arrayref::array_ref;
 
-use crate::{error::SwapError, ID as PROGRAM_ID};
+use crate::{
+    error::SwapError, 
+    event::{Event, PermissionEvent}, 
+    state::SwapVersion, 
+    utils::{close_account, create_pda_account}, 
+    ID as PROGRAM_ID
+};
+
+/// Roles that can be granted to a permission, as bits of `Permission::roles`
+pub mod roles {
+    /// Grant or revoke other permissions, implies every other role
+    pub const SUPER_ADMIN: u64 = 1 << 0;
+    /// Update the rates and rate parameters of the curve
+    pub const UPDATE_RATES: u64 = 1 << 1;
+    /// Pause and unpause the pool
+    pub const PAUSE: u64 = 1 << 2;
+    /// Update the pool fees
+    pub const UPDATE_FEES: u64 = 1 << 3;
+    /// Change the account receiving the pool fees
+    pub const UPDATE_FEE_ACCOUNT: u64 = 1 << 4;
+    /// Manage the pool liquidity
+    pub const MANAGE_LIQUIDITY: u64 = 1 << 5;
+    /// Add and remove users of allowlisted pools
+    pub const MANAGE_ALLOWLIST: u64 = 1 << 6;
+    /// Enable and disable each swap direction of the pool
+    pub const SET_SWAP_DIRECTIONS: u64 = 1 << 7;
+    /// Every role currently defined
+    pub const ALL: u64 = SUPER_ADMIN
+        | UPDATE_RATES
+        | PAUSE
+        | UPDATE_FEES
+        | UPDATE_FEE_ACCOUNT
+        | MANAGE_LIQUIDITY
+        | MANAGE_ALLOWLIST
+        | SET_SWAP_DIRECTIONS;
+}
 
 /// Permission struct that allows a more flexiple permission system
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -26,10 +61,8 @@ pub struct Permission {
     pub swap: Pubkey,
     /// The pubkey that is granted these permissions and must sign relevant instructions
     pub authority: Pubkey,
-    /// A permission to grant or revoke other permissions
-    pub is_super_admin: bool,
-    /// A permission allowing authority to update curve parameters
-    pub can_update_parameters: bool,
+    /// Bitmask of the granted `roles`
+    pub roles: u64,
 }
 
 impl IsInitialized for Permission {
@@ -41,21 +74,24 @@ impl IsInitialized for Permission {
 impl Sealed for Permission {}
 
 impl Pack for Permission {
-    const LEN: usize = 67;
+    const LEN: usize = 74;
 
     fn unpack_from_slice(input: &[u8]) -> Result<Permission, ProgramError> {
         let is_initialized = array_ref![input, 0, 1];
         let swap = array_ref![input, 1, 32];
         let authority = array_ref![input, 33, 32];
-        let is_super_admin = array_ref![input, 65, 1];
-        let can_update_parameters = array_ref![input, 66, 1];
+        let version = array_ref![input, 65, 1];
+        let roles = array_ref![input, 66, 8];
+
+        if version[0] != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData)
+        }
 
         Ok(Self {
             is_initialized: is_initialized[0] != 0,
             swap: Pubkey::new_from_array(*swap),
             authority: Pubkey::new_from_array(*authority),
-            is_super_admin: is_super_admin[0] != 0,
-            can_update_parameters: can_update_parameters[0] != 0,
+            roles: u64::from_le_bytes(*roles),
         })
     }
 
@@ -63,14 +99,14 @@ impl Pack for Permission {
         let (is_initialized, rest) = output.split_at_mut(1);
         let (swap, rest) = rest.split_at_mut(32);
         let (authority, rest) = rest.split_at_mut(32);
-        let (is_super_admin, rest) = rest.split_at_mut(1);
-        let (can_update_parameters, _) = rest.split_at_mut(1);
+        let (version, rest) = rest.split_at_mut(1);
+        let (roles, _) = rest.split_at_mut(8);
 
         is_initialized[0] = self.is_initialized as u8;
         swap.copy_from_slice(&self.swap.to_bytes());
         authority.copy_from_slice(&self.authority.to_bytes());
-        is_super_admin[0] = self.is_super_admin as u8;
-        can_update_parameters[0] = self.can_update_parameters as u8;
+        version[0] = Self::VERSION;
+        roles.copy_from_slice(&self.roles.to_le_bytes());
     }
 }
 
@@ -79,6 +115,89 @@ impl Permission {
     /// Seed for PDA
     pub const PERMISSION_SEED: &'static [u8] = b"permission";
 
+    /// Version of the current layout, stored where legacy accounts
+    /// have their `is_super_admin` boolean
+    pub const VERSION: u8 = 2;
+
+    /// Size of legacy accounts,
+    /// with `is_super_admin` and `can_update_parameters` booleans
+    pub const LEGACY_LEN: usize = 67;
+
+    /// Roles stored as one boolean each by legacy accounts, from offset 65
+    const LEGACY_ROLES: [u64; 2] = [
+        roles::SUPER_ADMIN,
+        roles::UPDATE_RATES,
+    ];
+
+    /// Returns true if `input` is a legacy permission account
+    pub fn is_legacy(input: &[u8]) -> bool {
+        input.len() == Self::LEGACY_LEN
+    }
+
+    /// Unpacks permission account data, in the current or a legacy layout.
+    pub fn unpack_account_data(input: &[u8]) -> Result<Self, ProgramError> {
+        if !Self::is_legacy(input) {
+            return Self::unpack(input)
+        }
+
+        let is_initialized = array_ref![input, 0, 1];
+        let swap = array_ref![input, 1, 32];
+        let authority = array_ref![input, 33, 32];
+
+        let roles = input[65..]
+            .iter()
+            .zip(Self::LEGACY_ROLES)
+            .filter(|(flag, _)| **flag != 0)
+            .fold(0, |roles, (_, role)| roles | role);
+
+        let permission = Self {
+            is_initialized: is_initialized[0] != 0,
+            swap: Pubkey::new_from_array(*swap),
+            authority: Pubkey::new_from_array(*authority),
+            roles,
+        };
+
+        if !permission.is_initialized {
+            return Err(ProgramError::UninitializedAccount)
+        }
+
+        Ok(permission)
+    }
+
+    /// Packs permission account data, in the current or a legacy layout.
+    /// Legacy accounts have no room for roles added after their creation,
+    /// so those can't be granted to them before `MigratePermission`.
+    pub fn pack_account_data(self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if !Self::is_legacy(dst) {
+            return Self::pack(self, dst)
+        }
+
+        let supported_roles = Self::LEGACY_ROLES.iter().fold(0, |roles, role| roles | role);
+        if self.roles & !supported_roles != 0 {
+            return Err(ProgramError::AccountDataTooSmall)
+        }
+
+        let (header, flags) = dst.split_at_mut(65);
+        header[0] = self.is_initialized as u8;
+        header[1..33].copy_from_slice(&self.swap.to_bytes());
+        header[33..65].copy_from_slice(&self.authority.to_bytes());
+        for (flag, role) in flags.iter_mut().zip(Self::LEGACY_ROLES) {
+            *flag = self.has_role(role) as u8;
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if `input` holds an initialized permission,
+    /// `is_initialized` being the first byte of every layout
+    pub fn is_initialized_account(input: &[u8]) -> bool {
+        input.first().is_some_and(|is_initialized| *is_initialized != 0)
+    }
+
+    /// Returns true if the permission holds all of the given roles
+    pub fn has_role(&self, role: u64) -> bool {
+        self.roles & role == role
+    }
     /// Unpacks and validates a permission.
     /// It checks: `permission_info` owner,
     /// `permission` PDA, `permission.authority` and `permission.swap`.
@@ -87,6 +206,22 @@ impl Permission {
         swap_info: &AccountInfo,
         signer_info: &AccountInfo,
         program_id: &Pubkey
+    ) -> Result<Self, ProgramError> {
+        Self::unpack_authority_permission(
+            permission_info, 
+            swap_info, 
+            signer_info.key, 
+            program_id
+        )
+    }
+
+    /// Unpacks and validates the permission of `authority`,
+    /// which doesn't have to sign.
+    pub fn unpack_authority_permission(
+        permission_info: &AccountInfo,
+        swap_info: &AccountInfo,
+        authority: &Pubkey,
+        program_id: &Pubkey
     ) -> Result<Self, ProgramError> {
         if permission_info.owner != program_id {
             return Err(ProgramError::IllegalOwner)
@@ -94,16 +229,16 @@ impl Permission {
 
         let (permission_pda, _) = Self::derive_permission_pubkey_and_bump(
             swap_info.key, 
-            signer_info.key
+            authority
         );
 
         if permission_pda != *permission_info.key {
             return Err(SwapError::InvalidUpdatePermission.into())
         }
 
-        let permission = Permission::unpack(&permission_info.data.borrow())?;
+        let permission = Permission::unpack_account_data(&permission_info.data.borrow())?;
 
-        if permission.authority != *signer_info.key {
+        if permission.authority != *authority {
             return Err(SwapError::InvalidUpdatePermission.into())
         }
 
@@ -133,22 +268,28 @@ impl Permission {
         (pubkey, bump)
     }
 
-    /// validates that the signer and its permission can update params
-    pub fn validate_update_params_permission(
-        &self,
-    ) -> Result<(), ProgramError> {
-        if !self.can_update_parameters {
-            return Err(SwapError::InvalidUpdatePermission.into())
+    /// Checks that `permission_info` is the PDA of the permission's swap and
+    /// authority, like on creation
+    fn check_address(&self, permission_info: &AccountInfo) -> Result<(), ProgramError> {
+        let (permission_address, _) = Self::derive_permission_pubkey_and_bump(
+            &self.swap, 
+            &self.authority
+        );
+
+        if *permission_info.key != permission_address {
+            return Err(SwapError::InvalidPermissionAddress.into())
         }
-        
+
         Ok(())
     }
 
-    /// validates that the signer and its permission is super admin
-    pub fn validate_super_admin_permission(
+    /// validates that the signer and its permission holds the given role,
+    /// super admins hold every role
+    pub fn validate_role(
         &self,
+        role: u64,
     ) -> Result<(), ProgramError> {
-        if !self.is_super_admin {
+        if !self.has_role(roles::SUPER_ADMIN) && !self.has_role(role) {
             return Err(SwapError::InvalidUpdatePermission.into())
         }
 
@@ -175,24 +316,13 @@ impl Permission {
             return Err(SwapError::InvalidPermissionAddress.into())
         }
 
-        if *system_program.key != SYSTEM_PROGRAM_ID {
-            return Err(ProgramError::IncorrectProgramId)
-        }
-
         // If it is owned by solana PSM and is initialized, we revert
-        if permission_account.owner == program_id {
-            let existing_permission = Permission::unpack_unchecked(&permission_account.data.borrow())?;
-            if existing_permission.is_initialized {
-                return Err(ProgramError::AccountAlreadyInitialized)
-            }
+        if permission_account.owner == program_id
+            && Self::is_initialized_account(&permission_account.data.borrow())
+        {
+            return Err(ProgramError::AccountAlreadyInitialized)
         }
 
-        let rent = Rent::get()?;
-        let space = Permission::LEN;
-        let lamports = rent.minimum_balance(space);
-
-        let current_lamports = permission_account.lamports();
-
         let signers_seeds: &[&[&[u8]]] = &[&[
             Self::PERMISSION_SEED,
             swap.as_ref(),
@@ -200,83 +330,13 @@ impl Permission {
             &[permission_bump]
         ]];
 
-        // Permission account has no lamports, normal account creation
-        if current_lamports == 0 {
-            let ix = create_account(
-                payer.key, 
-                &permission_address, 
-                lamports, 
-                space as u64, 
-                &PROGRAM_ID
-            );
-
-            invoke_signed(
-                &ix, 
-                &[
-                    payer, 
-                    permission_account, 
-                    system_program
-                ], 
-                signers_seeds
-            )?;
-
-            return Ok(())
-        }
-
-        // Permission account has a balance, so we have to:
-        // 1. Transfer required lamports for rent exempt (if needed)
-        // 2. Allocate space for Permission account
-        // 3. Assign to current program
-
-        let required_lamports = lamports.max(1)
-            .saturating_sub(current_lamports);
-
-        if required_lamports > 0 {
-            let ix = transfer(
-                payer.key, 
-                &permission_address, 
-                required_lamports
-            );
-
-            invoke(
-                &ix, 
-                &[
-                    payer.clone(), 
-                    permission_account.clone(), 
-                    system_program.clone()
-                ]
-            )?;
-        }
-
-        let allocate_ix = allocate(
-            &permission_address, 
-            space as u64
-        );
-
-        invoke_signed(
-            &allocate_ix, 
-            &[
-                permission_account.clone(), 
-                system_program.clone()
-            ], 
-            signers_seeds
-        )?;
-
-        let assign_ix = assign(
-            &permission_address, 
-            &PROGRAM_ID
-        );
-
-        invoke_signed(
-            &assign_ix, 
-            &[
-                permission_account, 
-                system_program
-            ], 
+        create_pda_account(
+            payer, 
+            permission_account, 
+            system_program, 
+            Permission::LEN, 
             signers_seeds
-        )?;
-
-        Ok(())
+        )
     }
 }
 
@@ -285,8 +345,7 @@ pub fn process_initialize_permission(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     permission_authority: Pubkey,
-    is_super_admin: bool,
-    can_update_parameters: bool
+    roles: u64
 ) -> Result<(), ProgramError> {
     let accounts_info_iter = &mut accounts.iter();
 
@@ -312,7 +371,11 @@ pub fn process_initialize_permission(
         program_id
     )?;
 
-    permission.validate_super_admin_permission()?;
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    if roles & !roles::ALL != 0 {
+        return Err(ProgramError::InvalidArgument)
+    }
 
     Permission::create_permission_account(
         program_id,
@@ -327,12 +390,20 @@ pub fn process_initialize_permission(
         is_initialized: true,
         swap: *swap_info.key,
         authority: permission_authority,
-        is_super_admin,
-        can_update_parameters
+        roles
     };
 
     Permission::pack(new_permission, &mut new_permission_info.data.borrow_mut())?;
 
+    Event::InitializePermission(PermissionEvent {
+        swap: *swap_info.key,
+        permission: *new_permission_info.key,
+        authority: permission_authority,
+        roles,
+        signer: *signer_info.key,
+    })
+    .emit();
+
     Ok(())
 }
 
@@ -342,8 +413,7 @@ pub fn process_initialize_permission(
 pub fn process_update_permission(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    is_super_admin: bool,
-    can_update_parameters: bool
+    roles: u64
 ) -> Result<(), ProgramError> {
     let accounts_info_iter = &mut accounts.iter();
 
@@ -371,25 +441,377 @@ pub fn process_update_permission(
         program_id
     )?;
 
-    permission.validate_super_admin_permission()?;
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    if roles & !roles::ALL != 0 {
+        return Err(ProgramError::InvalidArgument)
+    }
     
     let mut update_permission_data = update_permission_info.data.borrow_mut();
 
-    let update_permission = Permission::unpack(&update_permission_data)?;
+    let update_permission = Permission::unpack_account_data(&update_permission_data)?;
 
     if update_permission.swap != *swap_info.key {
         return Err(SwapError::InvalidUpdatePermission.into())
     }
 
+    update_permission.check_address(update_permission_info)?;
+
     let updated_values = Permission {
         is_initialized: update_permission.is_initialized,
         swap: update_permission.swap,
         authority: update_permission.authority,
-        is_super_admin,
-        can_update_parameters
+        roles
+    };
+
+    updated_values.pack_account_data(&mut update_permission_data)?;
+
+    Event::UpdatePermission(PermissionEvent {
+        swap: *swap_info.key,
+        permission: *update_permission_info.key,
+        authority: update_permission.authority,
+        roles,
+        signer: *signer_info.key,
+    })
+    .emit();
+
+    Ok(())
+}
+
+/// Processes nominating a new super admin.
+/// Nominating the default pubkey cancels a pending nomination.
+pub fn process_propose_super_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info, 
+        swap_info, 
+        signer_info, 
+        program_id
+    )?;
+
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    if new_authority == *signer_info.key {
+        return Err(SwapError::InvalidPendingSuperAdmin.into())
+    }
+
+    let mut swap_data = swap_info.data.borrow_mut();
+    let mut swap = SwapVersion::unpack_version(&swap_data)?;
+
+    let pool_config = swap.pool_config_mut()?;
+    pool_config.pending_super_admin = new_authority;
+    pool_config.super_admin_proposer = if new_authority == Pubkey::default() {
+        Pubkey::default()
+    } else {
+        *signer_info.key
     };
 
-    Permission::pack(updated_values, &mut update_permission_data)?;
+    SwapVersion::pack(swap, &mut swap_data)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Processes accepting a super admin nomination.
+/// The nominated authority's permission becomes super admin,
+/// creating it if needed, and the proposer's permission is revoked.
+pub fn process_accept_super_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let proposer_permission_info = next_account_info(accounts_info_iter)?;
+    let new_permission_info = next_account_info(accounts_info_iter)?;
+    let new_authority_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !new_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let mut swap = SwapVersion::unpack_version(&swap_info.data.borrow())?;
+    let pool_config = swap.pool_config_mut()?;
+
+    if pool_config.pending_super_admin == Pubkey::default()
+        || pool_config.pending_super_admin != *new_authority_info.key
+    {
+        return Err(SwapError::InvalidPendingSuperAdmin.into())
+    }
+
+    // the proposer has to still be super admin
+    let proposer_permission = Permission::unpack_authority_permission(
+        proposer_permission_info, 
+        swap_info, 
+        &pool_config.super_admin_proposer, 
+        program_id
+    )?;
+
+    proposer_permission.validate_role(roles::SUPER_ADMIN)?;
+
+    let is_new_permission_initialized = new_permission_info.owner == program_id
+        && Permission::is_initialized_account(&new_permission_info.data.borrow());
+
+    let new_permission = if is_new_permission_initialized {
+        let existing_permission = Permission::unpack_authority_permission(
+            new_permission_info, 
+            swap_info, 
+            new_authority_info.key, 
+            program_id
+        )?;
+
+        Permission {
+            roles: existing_permission.roles | roles::SUPER_ADMIN,
+            ..existing_permission
+        }
+    } else {
+        Permission::create_permission_account(
+            program_id,
+            payer_info.clone(), 
+            new_permission_info.clone(), 
+            system_program_info.clone(), 
+            swap_info.key, 
+            new_authority_info.key
+        )?;
+
+        Permission {
+            is_initialized: true,
+            swap: *swap_info.key,
+            authority: *new_authority_info.key,
+            roles: roles::ALL,
+        }
+    };
+
+    new_permission.pack_account_data(&mut new_permission_info.data.borrow_mut())?;
+
+    let revoked_permission = Permission {
+        is_initialized: proposer_permission.is_initialized,
+        swap: proposer_permission.swap,
+        authority: proposer_permission.authority,
+        ..Permission::default()
+    };
+
+    revoked_permission.pack_account_data(&mut proposer_permission_info.data.borrow_mut())?;
+
+    pool_config.pending_super_admin = Pubkey::default();
+    pool_config.super_admin_proposer = Pubkey::default();
+
+    SwapVersion::pack(swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Processes closing a permission account, by a super admin or by its own authority.
+/// Its lamports are sent to the recipient and the account is handed back to the
+/// system program, so the same permission can be created again later.
+/// Super admin permissions can't be closed, so that a pool always keeps one:
+/// they are given up through the super admin handover.
+pub fn process_close_permission(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let close_permission_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let recipient_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    if close_permission_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info, 
+        swap_info, 
+        signer_info, 
+        program_id
+    )?;
+
+    let close_permission = Permission::unpack_account_data(&close_permission_info.data.borrow())?;
+
+    if close_permission.swap != *swap_info.key {
+        return Err(SwapError::InvalidUpdatePermission.into())
+    }
+
+    close_permission.check_address(close_permission_info)?;
+
+    if close_permission.has_role(roles::SUPER_ADMIN) {
+        return Err(SwapError::SuperAdminHandoverRequired.into())
+    }
+
+    if close_permission.authority != *signer_info.key {
+        permission.validate_role(roles::SUPER_ADMIN)?;
+    }
+
+    close_account(close_permission_info, recipient_info)
+}
+
+/// Processes migrating a legacy permission account to the current layout,
+/// keeping its roles. Anyone can pay for the migration.
+pub fn process_migrate_permission(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+
+    if permission_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    if *system_program_info.key != SYSTEM_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+
+    if !Permission::is_legacy(&permission_info.data.borrow()) {
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+
+    let permission = Permission::unpack_account_data(&permission_info.data.borrow())?;
+
+    let rent = Rent::get()?;
+    let required_lamports = rent
+        .minimum_balance(Permission::LEN)
+        .saturating_sub(permission_info.lamports());
+
+    if required_lamports > 0 {
+        invoke(
+            &transfer(
+                payer_info.key, 
+                permission_info.key, 
+                required_lamports
+            ), 
+            &[
+                payer_info.clone(), 
+                permission_info.clone(), 
+                system_program_info.clone()
+            ]
+        )?;
+    }
+
+    permission_info.realloc(Permission::LEN, true)?;
+    Permission::pack(permission, &mut permission_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_SWAP: Pubkey = Pubkey::new_from_array([1u8; 32]);
+    const TEST_AUTHORITY: Pubkey = Pubkey::new_from_array([2u8; 32]);
+
+    fn legacy_data(flags: &[u8]) -> Vec<u8> {
+        let mut data = vec![1];
+        data.extend_from_slice(&TEST_SWAP.to_bytes());
+        data.extend_from_slice(&TEST_AUTHORITY.to_bytes());
+        data.extend_from_slice(flags);
+        data
+    }
+
+    #[test]
+    fn permission_pack() {
+        let permission = Permission {
+            is_initialized: true,
+            swap: TEST_SWAP,
+            authority: TEST_AUTHORITY,
+            roles: roles::PAUSE | roles::MANAGE_LIQUIDITY,
+        };
+
+        let mut packed = [0u8; Permission::LEN];
+        Permission::pack(permission.clone(), &mut packed).unwrap();
+        assert_eq!(packed[65], Permission::VERSION);
+        assert_eq!(Permission::unpack_account_data(&packed).unwrap(), permission);
+
+        packed[65] = Permission::VERSION + 1;
+        assert_eq!(
+            Permission::unpack_account_data(&packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn permission_unpack_legacy() {
+        let permission = Permission::unpack_account_data(&legacy_data(&[1, 1])).unwrap();
+        assert_eq!(permission.swap, TEST_SWAP);
+        assert_eq!(permission.authority, TEST_AUTHORITY);
+        assert_eq!(permission.roles, roles::SUPER_ADMIN | roles::UPDATE_RATES);
+
+        let permission = Permission::unpack_account_data(&legacy_data(&[0, 1])).unwrap();
+        assert_eq!(permission.roles, roles::UPDATE_RATES);
+
+        // only the 67 bytes layout was ever deployed
+        assert!(!Permission::is_legacy(&legacy_data(&[0, 1, 0])));
+        assert!(!Permission::is_legacy(&legacy_data(&[0, 1, 0, 1])));
+
+        let mut uninitialized = legacy_data(&[0, 1]);
+        uninitialized[0] = 0;
+        assert_eq!(
+            Permission::unpack_account_data(&uninitialized),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn permission_pack_legacy() {
+        let mut data = legacy_data(&[0, 0]);
+        let permission = Permission {
+            is_initialized: true,
+            swap: TEST_SWAP,
+            authority: TEST_AUTHORITY,
+            roles: roles::SUPER_ADMIN,
+        };
+        permission.clone().pack_account_data(&mut data).unwrap();
+        assert_eq!(data, legacy_data(&[1, 0]));
+
+        // roles without a legacy boolean need a migration
+        let permission = Permission {
+            roles: roles::PAUSE,
+            ..permission
+        };
+        assert_eq!(
+            permission.pack_account_data(&mut data),
+            Err(ProgramError::AccountDataTooSmall)
+        );
+    }
+}
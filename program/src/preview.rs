@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Read-only previews of swaps and single token type deposits and
+//! withdrawals, computed with the curve and fees of the pool at the current
+//! timestamp and returned through the return data. Nothing is written and no
+//! tokens move, so aggregators and CPI callers can quote the pool without
+//! reimplementing the curve.
+//!
+//! Pool controls applying to everyone (pause, staleness, swap directions,
+//! swap rate limits and the value cap) fail the preview like the previewed
+//! instruction would. The allowlist is not checked.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    program::set_return_data,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    curve::{
+        base::SingleTokenTypeResult,
+        calculator::TradeDirection,
+    },
+    error::SwapError,
+    processor::{to_u64, Processor},
+    rate_provider::unpack_swap_with_rates,
+    state::SwapState,
+};
+use std::{slice::Iter, sync::Arc};
+
+/// Amounts of a previewed swap
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapPreview {
+    /// Amount of source tokens transferred from the user, fees included
+    pub amount_in: u64,
+    /// Amount of destination tokens transferred to the user
+    pub amount_out: u64,
+}
+
+impl IsInitialized for SwapPreview {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for SwapPreview {}
+impl Pack for SwapPreview {
+    const LEN: usize = 16;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 16];
+        let (amount_in, amount_out) = mut_array_refs![output, 8, 8];
+        *amount_in = self.amount_in.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<SwapPreview, ProgramError> {
+        let input = array_ref![input, 0, 16];
+        let (amount_in, amount_out) = array_refs![input, 8, 8];
+        Ok(Self {
+            amount_in: u64::from_le_bytes(*amount_in),
+            amount_out: u64::from_le_bytes(*amount_out),
+        })
+    }
+}
+
+/// Unpacks the pool, with the rates of its rate provider if it has one, and
+/// checks the controls shared by all previews
+fn unpack_swap(
+    program_id: &Pubkey,
+    swap_info: &AccountInfo,
+    accounts_info_iter: &mut Iter<AccountInfo>,
+) -> Result<Arc<dyn SwapState>, ProgramError> {
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let token_swap = unpack_swap_with_rates(program_id, swap_info, accounts_info_iter)?;
+    if token_swap.is_paused() {
+        return Err(SwapError::PoolPaused.into());
+    }
+    token_swap.swap_curve().calculator.validate_staleness(
+        token_swap.get_current_timestamp_opt()?,
+        token_swap.max_staleness(),
+    )?;
+    Ok(token_swap)
+}
+
+/// Unpacks the reserves of the pool and finds the direction converting the
+/// token of `mint_info` into the other one
+fn unpack_reserves(
+    token_swap: &dyn SwapState,
+    swap_token_a_info: &AccountInfo,
+    swap_token_b_info: &AccountInfo,
+    mint_info: &AccountInfo,
+) -> Result<(u128, u128, TradeDirection), ProgramError> {
+    if *swap_token_a_info.key != *token_swap.token_a_account()
+        || *swap_token_b_info.key != *token_swap.token_b_account()
+    {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    let swap_token_a = Processor::unpack_token_account(
+        swap_token_a_info,
+        Some(token_swap.token_a_mint())
+    )?;
+    let swap_token_b = Processor::unpack_token_account(
+        swap_token_b_info,
+        Some(token_swap.token_b_mint())
+    )?;
+
+    let trade_direction = if *mint_info.key == swap_token_a.mint {
+        TradeDirection::AtoB
+    } else if *mint_info.key == swap_token_b.mint {
+        TradeDirection::BtoA
+    } else {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    };
+
+    Ok((
+        u128::from(swap_token_a.amount),
+        u128::from(swap_token_b.amount),
+        trade_direction,
+    ))
+}
+
+/// Unpacks the pool mint and returns its supply
+fn unpack_pool_supply(
+    token_swap: &dyn SwapState,
+    pool_mint_info: &AccountInfo,
+) -> Result<u128, ProgramError> {
+    if *pool_mint_info.key != *token_swap.pool_mint() {
+        return Err(SwapError::IncorrectPoolMint.into());
+    }
+    Ok(u128::from(Processor::unpack_mint(pool_mint_info)?.supply))
+}
+
+/// Processes previewing a swap
+pub fn process_preview_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    exact_out: bool,
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let swap_source_info = next_account_info(accounts_info_iter)?;
+    let swap_destination_info = next_account_info(accounts_info_iter)?;
+
+    let token_swap = unpack_swap(program_id, swap_info, accounts_info_iter)?;
+
+    let trade_direction = if *swap_source_info.key == *token_swap.token_a_account()
+        && *swap_destination_info.key == *token_swap.token_b_account()
+    {
+        TradeDirection::AtoB
+    } else if *swap_source_info.key == *token_swap.token_b_account()
+        && *swap_destination_info.key == *token_swap.token_a_account()
+    {
+        TradeDirection::BtoA
+    } else {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    };
+    if !token_swap.is_direction_enabled(trade_direction) {
+        return Err(SwapError::SwapDirectionDisabled.into());
+    }
+
+    let (source_mint, destination_mint) = match trade_direction {
+        TradeDirection::AtoB => (token_swap.token_a_mint(), token_swap.token_b_mint()),
+        TradeDirection::BtoA => (token_swap.token_b_mint(), token_swap.token_a_mint()),
+    };
+    let swap_source = Processor::unpack_token_account(swap_source_info, Some(source_mint))?;
+    let swap_destination = Processor::unpack_token_account(
+        swap_destination_info,
+        Some(destination_mint)
+    )?;
+
+    let swap_curve = token_swap.swap_curve();
+    let timestamp = token_swap.get_current_timestamp_opt()?;
+    let result = if exact_out {
+        swap_curve.swap_exact_out(
+            u128::from(amount),
+            u128::from(swap_source.amount),
+            u128::from(swap_destination.amount),
+            trade_direction,
+            token_swap.fees(),
+            timestamp
+        )
+    } else {
+        swap_curve.swap(
+            u128::from(amount),
+            u128::from(swap_source.amount),
+            u128::from(swap_destination.amount),
+            trade_direction,
+            token_swap.fees(),
+            timestamp
+        )
+    };
+    let result = result.ok_or(SwapError::ZeroTradingTokens)?;
+
+    let preview = SwapPreview {
+        amount_in: to_u64(result.source_amount_swapped)?,
+        amount_out: to_u64(result.destination_amount_swapped)?,
+    };
+
+    let swap_rate_limit = token_swap.swap_rate_limit(trade_direction);
+    if swap_rate_limit.is_bounded()
+        && preview.amount_in > swap_rate_limit.current_amount(Clock::get()?.unix_timestamp as u64)
+    {
+        return Err(SwapError::SwapRateLimitExceeded.into());
+    }
+
+    let mut return_data = [0u8; SwapPreview::LEN];
+    preview.pack_into_slice(&mut return_data);
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/// Processes previewing a single token type deposit
+pub fn process_preview_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    source_token_amount: u64,
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let swap_token_a_info = next_account_info(accounts_info_iter)?;
+    let swap_token_b_info = next_account_info(accounts_info_iter)?;
+    let pool_mint_info = next_account_info(accounts_info_iter)?;
+    let source_mint_info = next_account_info(accounts_info_iter)?;
+
+    let token_swap = unpack_swap(program_id, swap_info, accounts_info_iter)?;
+    if !token_swap.swap_curve().calculator.allows_deposits() {
+        return Err(SwapError::UnsupportedCurveOperation.into());
+    }
+
+    let (swap_token_a_amount, swap_token_b_amount, trade_direction) = unpack_reserves(
+        token_swap.as_ref(),
+        swap_token_a_info,
+        swap_token_b_info,
+        source_mint_info,
+    )?;
+    // depositing a single token type converts it into the other one
+    if !token_swap.is_direction_enabled(trade_direction) {
+        return Err(SwapError::SwapDirectionDisabled.into());
+    }
+    let pool_mint_supply = unpack_pool_supply(token_swap.as_ref(), pool_mint_info)?;
+
+    let SingleTokenTypeResult { amount: pool_token_amount, .. } = token_swap
+        .swap_curve()
+        .deposit_single_token_type(
+            u128::from(source_token_amount),
+            swap_token_a_amount,
+            swap_token_b_amount,
+            pool_mint_supply,
+            trade_direction,
+            token_swap.fees(),
+            token_swap.get_current_timestamp_opt()?
+        )
+        .ok_or(SwapError::ZeroTradingTokens)?;
+    if pool_token_amount == 0 {
+        return Err(SwapError::ZeroTradingTokens.into());
+    }
+    let pool_token_amount = to_u64(pool_token_amount)?;
+
+    let (new_swap_token_a_amount, new_swap_token_b_amount) = match trade_direction {
+        TradeDirection::AtoB => (
+            swap_token_a_amount.checked_add(u128::from(source_token_amount)),
+            Some(swap_token_b_amount),
+        ),
+        TradeDirection::BtoA => (
+            Some(swap_token_a_amount),
+            swap_token_b_amount.checked_add(u128::from(source_token_amount)),
+        ),
+    };
+    Processor::check_max_tvl(
+        token_swap.as_ref(),
+        new_swap_token_a_amount.ok_or(SwapError::CalculationFailure)?,
+        new_swap_token_b_amount.ok_or(SwapError::CalculationFailure)?,
+    )?;
+
+    set_return_data(&pool_token_amount.to_le_bytes());
+
+    Ok(())
+}
+
+/// Processes previewing a single token type withdrawal
+pub fn process_preview_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    destination_token_amount: u64,
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let swap_token_a_info = next_account_info(accounts_info_iter)?;
+    let swap_token_b_info = next_account_info(accounts_info_iter)?;
+    let pool_mint_info = next_account_info(accounts_info_iter)?;
+    let pool_fee_account_info = next_account_info(accounts_info_iter)?;
+    let destination_mint_info = next_account_info(accounts_info_iter)?;
+
+    let token_swap = unpack_swap(program_id, swap_info, accounts_info_iter)?;
+
+    let (swap_token_a_amount, swap_token_b_amount, trade_direction) = unpack_reserves(
+        token_swap.as_ref(),
+        swap_token_a_info,
+        swap_token_b_info,
+        destination_mint_info,
+    )?;
+    // withdrawing a single token type converts the other one into it
+    if !token_swap.is_direction_enabled(trade_direction.opposite()) {
+        return Err(SwapError::SwapDirectionDisabled.into());
+    }
+    let pool_mint_supply = unpack_pool_supply(token_swap.as_ref(), pool_mint_info)?;
+    if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
+        return Err(SwapError::IncorrectFeeAccount.into());
+    }
+
+    let SingleTokenTypeResult { amount: burn_pool_token_amount, .. } = token_swap
+        .swap_curve()
+        .withdraw_single_token_type_exact_out(
+            u128::from(destination_token_amount),
+            swap_token_a_amount,
+            swap_token_b_amount,
+            pool_mint_supply,
+            trade_direction,
+            token_swap.fees(),
+            token_swap.get_current_timestamp_opt()?
+        )
+        .ok_or(SwapError::ZeroTradingTokens)?;
+
+    // the withdraw fee is only assessed while the fee account is valid
+    let withdraw_fee = if token_swap.check_pool_fee_info(pool_fee_account_info).is_ok() {
+        token_swap
+            .fees()
+            .owner_withdraw_fee(burn_pool_token_amount)
+            .ok_or(SwapError::FeeCalculationFailure)?
+    } else {
+        0
+    };
+    let pool_token_amount = burn_pool_token_amount
+        .checked_add(withdraw_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    if pool_token_amount == 0 {
+        return Err(SwapError::ZeroTradingTokens.into());
+    }
+    let pool_token_amount = to_u64(pool_token_amount)?;
+
+    set_return_data(&pool_token_amount.to_le_bytes());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_preview_pack() {
+        let preview = SwapPreview {
+            amount_in: 1_000_300,
+            amount_out: 1_000_000,
+        };
+
+        let mut packed = [0u8; SwapPreview::LEN];
+        preview.pack_into_slice(&mut packed);
+
+        let mut expect = vec![];
+        expect.extend_from_slice(&preview.amount_in.to_le_bytes());
+        expect.extend_from_slice(&preview.amount_out.to_le_bytes());
+        assert_eq!(packed.to_vec(), expect);
+        assert_eq!(SwapPreview::unpack_from_slice(&packed).unwrap(), preview);
+    }
+}
@@ -4,18 +4,20 @@
 
 use {
     crate::{
-        constraints::{validate_mint_extensions, SwapConstraints, SWAP_CONSTRAINTS}, curve::{
-            base::{CurveType, SingleTokenTypeResult, SwapCurve},
-            calculator::{RoundDirection, TradeDirection},
+        admin_processor::{process_close_pool, process_set_allowlist_mode, process_set_fee_account, process_set_max_staleness, process_set_max_tvl, process_set_paused, process_set_rate_update_limits, process_set_swap_directions, process_set_swap_rate_limits, process_set_timelock_delay, process_update_fees}, allowlist::{process_add_to_allowlist, process_remove_from_allowlist, AllowlistEntry}, constraints::{validate_mint_extensions, SwapConstraints, SWAP_CONSTRAINTS}, curve::{
+            base::{CurveType, SingleTokenTypeResult, SwapCurve, SwapResult},
+            calculator::{CurveCalculator, RoundDirection, TradeDirection},
             fees::Fees,
-        }, error::SwapError, instruction::{
-            DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize, InitializePermission, SetRates, Swap, SwapInstruction, UpdatePermission, WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut
-        }, permission::{process_initialize_permission, process_update_permission, Permission}, redemption_rate_processor::process_curve_update, state::{SwapState, SwapV1, SwapVersion}
+            redemption_rate::RAY,
+        }, error::SwapError, event::{DepositEvent, Event, SwapEvent, WithdrawEvent}, instruction::{
+            AddToAllowlist, DepositAllTokenTypes, GetConversionRate, DepositSingleTokenTypeExactAmountIn, Initialize, InitializePermission, InitializePsm3, InitializeRateProvider, ProposeSuperAdmin, BridgeSetRates, SetBridgeReceiver, Psm3Deposit, Psm3Swap, Psm3Withdraw, QueueChange, SetMaxSsr, SetMaxStaleness, SetMaxTvl, SetPaused, SetRateMultisig, SetRateUpdateLimits, SetAllowlistMode, SetRates, SetSwapDirections, SetSwapRateLimits, SetTimelockDelay, Swap, SwapExactAmountOut, SwapInstruction, PreviewDeposit, PreviewSwap, PreviewWithdraw, UpdateFees, UpdatePermission, WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut
+        }, multisig::process_set_rate_multisig, permission::{process_accept_super_admin, process_close_permission, process_initialize_permission, process_migrate_permission, process_propose_super_admin, process_update_permission, roles, Permission}, preview::{process_preview_deposit, process_preview_swap, process_preview_withdraw}, psm3::{process_initialize_psm3, process_psm3_deposit, process_psm3_swap, process_psm3_withdraw}, rate_provider::{process_initialize_rate_provider, unpack_swap_with_rates, RateProvider}, relay::process_relay_signed_rates, bridge::{process_bridge_set_rates, process_set_bridge_receiver}, redemption_rate_processor::{process_curve_update, process_get_conversion_rate, process_max_ssr_update}, state::{PoolConfig, SwapState, SwapV2, SwapVersion}, timelock::{process_cancel_change, process_execute_change, process_queue_change}
     },
     num_traits::FromPrimitive,
     solana_program::{
-        account_info::{next_account_info, AccountInfo}, clock::Clock, decode_error::DecodeError, entrypoint::ProgramResult, instruction::Instruction, msg, program::invoke_signed, program_error::{PrintProgramError, ProgramError}, program_option::COption, program_pack::Pack, pubkey::Pubkey, sysvar::Sysvar
+        account_info::{next_account_info, AccountInfo}, clock::Clock, decode_error::DecodeError, entrypoint::ProgramResult, instruction::Instruction, msg, program::{invoke, invoke_signed}, program_error::{PrintProgramError, ProgramError}, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, system_program, sysvar::Sysvar
     },
+    spl_math::uint::U256,
     spl_token_2022::{
         check_spl_token_program_account,
         error::TokenError,
@@ -120,6 +122,33 @@ impl Processor {
         )
     }
 
+    /// Issue a spl_token `CloseAccount` instruction.
+    pub fn token_close_account<'a>(
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        account: AccountInfo<'a>,
+        destination: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        bump_seed: u8,
+    ) -> Result<(), ProgramError> {
+        let swap_bytes = swap.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = spl_token_2022::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?;
+
+        invoke_signed_wrapper::<TokenError>(
+            &ix,
+            &[account, destination, authority, token_program],
+            signers,
+        )
+    }
+
     /// Issue a spl_token `MintTo` instruction.
     pub fn token_mint_to<'a>(
         swap: &Pubkey,
@@ -234,6 +263,39 @@ impl Processor {
         Ok(())
     }
 
+    /// Checks that the pool reserves after a deposit stay within the
+    /// value cap of the pool. The reserves are valued in token A, token B
+    /// at the conversion rate of the curve, or at twice the normalized
+    /// value for curves without one
+    pub(crate) fn check_max_tvl(
+        token_swap: &dyn SwapState,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> ProgramResult {
+        let max_tvl = token_swap.max_tvl();
+        if max_tvl == 0 {
+            return Ok(());
+        }
+        let timestamp = token_swap.get_current_timestamp_opt()?;
+        let calculator = &token_swap.swap_curve().calculator;
+        let value = match calculator.conversion_rate(timestamp) {
+            Some(rate) => U256::from(swap_token_b_amount)
+                .checked_mul(U256::from(rate))
+                .and_then(|value| value.checked_div(U256::from(RAY)))
+                .and_then(|value| value.checked_add(U256::from(swap_token_a_amount)))
+                .and_then(|value| u128::try_from(value).ok()),
+            None => calculator
+                .normalized_value(swap_token_a_amount, swap_token_b_amount, timestamp)
+                .and_then(|value| value.to_imprecise())
+                .and_then(|value| value.checked_mul(2)),
+        }
+        .ok_or(SwapError::CalculationFailure)?;
+        if value > max_tvl {
+            return Err(SwapError::MaxTvlExceeded.into());
+        }
+        Ok(())
+    }
+
     /// Processes an [Initialize](enum.Instruction.html).
     pub fn process_initialize(
         program_id: &Pubkey,
@@ -386,7 +448,9 @@ impl Processor {
         fees.validate()?;
 
         let timestamp_opt = match swap_curve.curve_type {
-            CurveType::RedemptionRateCurve => Some(Clock::get()?.unix_timestamp as u128),
+            CurveType::RedemptionRateCurve | CurveType::RateProviderCurve => {
+                Some(Clock::get()?.unix_timestamp as u128)
+            }
             _ => None
         };
 
@@ -404,7 +468,9 @@ impl Processor {
             to_u64(initial_amount)?,
         )?;
 
-        if swap_curve.curve_type == CurveType::RedemptionRateCurve {
+        if swap_curve.curve_type == CurveType::RedemptionRateCurve
+            || swap_curve.curve_type == CurveType::RateProviderCurve
+        {
             // RedemptionRateCurve requires both mints to have same decimals
             if token_a_mint_state.base.decimals != token_b_mint_state.base.decimals {
                 return Err(SwapError::MismatchedMintDecimals.into())
@@ -433,15 +499,19 @@ impl Processor {
                 is_initialized: true,
                 swap: *swap_info.key,
                 authority: *super_admin_info.key,
-                is_super_admin: true,
-                can_update_parameters: true,
+                roles: roles::ALL,
             };
 
             // pack permission in permission_info
             Permission::pack(permission, &mut permission_info.data.borrow_mut())?;
         }
 
-        let obj = SwapVersion::SwapV1(SwapV1 {
+        if let Some(rate_provider) = swap_curve.calculator.rate_provider() {
+            RateProvider::next_rates(account_info_iter, rate_provider, program_id)?
+                .validate(timestamp_opt)?;
+        }
+
+        let obj = SwapVersion::SwapV2(SwapV2 {
             is_initialized: true,
             bump_seed,
             token_program_id,
@@ -453,6 +523,7 @@ impl Processor {
             pool_fee_account: *fee_account_info.key,
             fees,
             swap_curve,
+            pool_config: PoolConfig::default(),
         });
         
         SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
@@ -465,8 +536,76 @@ impl Processor {
         program_id: &Pubkey,
         amount_in: u64,
         minimum_amount_out: u64,
+        referral_code: Option<u64>,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
+        Self::swap_tokens(
+            program_id,
+            referral_code,
+            accounts,
+            |token_swap, swap_source_amount, swap_destination_amount, trade_direction| {
+                let result = token_swap
+                    .swap_curve()
+                    .swap(
+                        u128::from(amount_in),
+                        swap_source_amount,
+                        swap_destination_amount,
+                        trade_direction,
+                        token_swap.fees(),
+                        token_swap.get_current_timestamp_opt()?
+                    )
+                    .ok_or(SwapError::ZeroTradingTokens)?;
+                if result.destination_amount_swapped < u128::from(minimum_amount_out) {
+                    return Err(SwapError::ExceededSlippage.into());
+                }
+                Ok(result)
+            },
+        )
+    }
+
+    /// Processes a [SwapExactAmountOut](enum.Instruction.html).
+    pub fn process_swap_exact_amount_out(
+        program_id: &Pubkey,
+        amount_out: u64,
+        maximum_amount_in: u64,
+        referral_code: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        Self::swap_tokens(
+            program_id,
+            referral_code,
+            accounts,
+            |token_swap, swap_source_amount, swap_destination_amount, trade_direction| {
+                let result = token_swap
+                    .swap_curve()
+                    .swap_exact_out(
+                        u128::from(amount_out),
+                        swap_source_amount,
+                        swap_destination_amount,
+                        trade_direction,
+                        token_swap.fees(),
+                        token_swap.get_current_timestamp_opt()?
+                    )
+                    .ok_or(SwapError::ZeroTradingTokens)?;
+                if result.source_amount_swapped > u128::from(maximum_amount_in) {
+                    return Err(SwapError::ExceededSlippage.into());
+                }
+                Ok(result)
+            },
+        )
+    }
+
+    /// Swaps the tokens of a pool for the amounts computed by `calculate`,
+    /// given the pool, its source and destination reserves and the direction
+    fn swap_tokens<F>(
+        program_id: &Pubkey,
+        referral_code: Option<u64>,
+        accounts: &[AccountInfo],
+        calculate: F,
+    ) -> ProgramResult
+    where
+        F: FnOnce(&dyn SwapState, u128, u128, TradeDirection) -> Result<SwapResult, ProgramError>,
+    {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
@@ -486,7 +625,23 @@ impl Processor {
         if swap_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = unpack_swap_with_rates(program_id, swap_info, account_info_iter)?;
+        if token_swap.is_paused() {
+            return Err(SwapError::PoolPaused.into());
+        }
+        let allowlist_info = AllowlistEntry::next_allowlist_entry(account_info_iter, program_id);
+        if token_swap.is_swaps_allowlisted() {
+            AllowlistEntry::check_allowlisted(
+                allowlist_info,
+                swap_info,
+                user_transfer_authority_info,
+                program_id,
+            )?;
+        }
+        token_swap.swap_curve().calculator.validate_staleness(
+            token_swap.get_current_timestamp_opt()?,
+            token_swap.max_staleness(),
+        )?;
 
         if *authority_info.key
             != Self::authority_id(program_id, swap_info.key, token_swap.bump_seed())?
@@ -538,17 +693,15 @@ impl Processor {
         } else {
             TradeDirection::BtoA
         };
-        let result = token_swap
-            .swap_curve()
-            .swap(
-                u128::from(amount_in),
-                u128::from(source_account.amount),
-                u128::from(dest_account.amount),
-                trade_direction,
-                token_swap.fees(),
-                token_swap.get_current_timestamp_opt()?
-            )
-            .ok_or(SwapError::ZeroTradingTokens)?;
+        if !token_swap.is_direction_enabled(trade_direction) {
+            return Err(SwapError::SwapDirectionDisabled.into());
+        }
+        let result = calculate(
+            token_swap.as_ref(),
+            u128::from(source_account.amount),
+            u128::from(dest_account.amount),
+            trade_direction,
+        )?;
 
         // Re-calculate the source amount swapped based on what the curve says
         let (source_transfer_amount, source_mint_decimals) = {
@@ -570,12 +723,18 @@ impl Processor {
                 destination_token_mint_info.owner,
             )?;
             let amount_out = to_u64(result.destination_amount_swapped)?;
-            if amount_out < minimum_amount_out {
-                return Err(SwapError::ExceededSlippage.into());
-            }
             (amount_out, destination_mint.base.decimals)
         };
 
+        if token_swap.swap_rate_limit(trade_direction).is_bounded() {
+            let mut swap_data = swap_info.data.borrow_mut();
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            swap.pool_config_mut()?
+                .swap_rate_limit_mut(trade_direction)
+                .consume(source_transfer_amount, Clock::get()?.unix_timestamp as u64)?;
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+
         let (mut swap_token_a_amount, mut swap_token_b_amount) = match trade_direction {
             TradeDirection::AtoB => (
                 result.new_swap_source_amount,
@@ -687,6 +846,28 @@ impl Processor {
             destination_mint_decimals,
         )?;
 
+        if let Some(referral_code) = referral_code {
+            msg!(
+                "Referral: code {}, amount_in {}, amount_out {}",
+                referral_code,
+                source_transfer_amount,
+                destination_transfer_amount
+            );
+        }
+
+        Event::Swap(SwapEvent {
+            swap: *swap_info.key,
+            user: *user_transfer_authority_info.key,
+            trade_direction,
+            amount_in: source_transfer_amount,
+            amount_out: destination_transfer_amount,
+            trade_fee: to_u64(result.trade_fee)?,
+            owner_fee: to_u64(result.owner_fee)?,
+            conversion_rate: conversion_rate(token_swap.as_ref())?,
+            referral_code,
+        })
+        .emit();
+
         Ok(())
     }
 
@@ -696,6 +877,7 @@ impl Processor {
         pool_token_amount: u64,
         maximum_token_a_amount: u64,
         maximum_token_b_amount: u64,
+        referral_code: Option<u64>,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
@@ -714,7 +896,19 @@ impl Processor {
         let token_b_program_info = next_account_info(account_info_iter)?;
         let pool_token_program_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = unpack_swap_with_rates(program_id, swap_info, account_info_iter)?;
+        if token_swap.is_paused() {
+            return Err(SwapError::PoolPaused.into());
+        }
+        let allowlist_info = AllowlistEntry::next_allowlist_entry(account_info_iter, program_id);
+        if token_swap.is_liquidity_allowlisted() {
+            AllowlistEntry::check_allowlisted(
+                allowlist_info,
+                swap_info,
+                user_transfer_authority_info,
+                program_id,
+            )?;
+        }
         let calculator = &token_swap.swap_curve().calculator;
         if !calculator.allows_deposits() {
             return Err(SwapError::UnsupportedCurveOperation.into());
@@ -776,6 +970,16 @@ impl Processor {
                 return Err(SwapError::ZeroTradingTokens.into());
             }
         }
+
+        Self::check_max_tvl(
+            token_swap.as_ref(),
+            u128::from(token_a.amount)
+                .checked_add(results.token_a_amount)
+                .ok_or(SwapError::CalculationFailure)?,
+            u128::from(token_b.amount)
+                .checked_add(results.token_b_amount)
+                .ok_or(SwapError::CalculationFailure)?,
+        )?;
         
         let pool_token_amount = to_u64(pool_token_amount)?;
 
@@ -811,6 +1015,28 @@ impl Processor {
             pool_token_amount,
         )?;
 
+        if let Some(referral_code) = referral_code {
+            msg!(
+                "Referral: code {}, token_a_amount {}, token_b_amount {}, pool_token_amount {}",
+                referral_code,
+                token_a_amount,
+                token_b_amount,
+                pool_token_amount
+            );
+        }
+
+        Event::Deposit(DepositEvent {
+            swap: *swap_info.key,
+            user: *user_transfer_authority_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            owner_fee: 0,
+            conversion_rate: conversion_rate(token_swap.as_ref())?,
+            referral_code,
+        })
+        .emit();
+
         Ok(())
     }
 
@@ -839,7 +1065,16 @@ impl Processor {
         let token_a_program_info = next_account_info(account_info_iter)?;
         let token_b_program_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = unpack_swap_with_rates(program_id, swap_info, account_info_iter)?;
+        let allowlist_info = AllowlistEntry::next_allowlist_entry(account_info_iter, program_id);
+        if token_swap.is_liquidity_allowlisted() {
+            AllowlistEntry::check_allowlisted(
+                allowlist_info,
+                swap_info,
+                user_transfer_authority_info,
+                program_id,
+            )?;
+        }
         Self::check_accounts(
             token_swap.as_ref(),
             program_id,
@@ -966,6 +1201,18 @@ impl Processor {
                 Self::unpack_mint(token_b_mint_info)?.decimals,
             )?;
         }
+
+        Event::Withdraw(WithdrawEvent {
+            swap: *swap_info.key,
+            user: *user_transfer_authority_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount: to_u64(pool_token_amount + withdraw_fee)?,
+            withdraw_fee: to_u64(withdraw_fee)?,
+            conversion_rate: conversion_rate(token_swap.as_ref())?,
+        })
+        .emit();
+
         Ok(())
     }
 
@@ -974,6 +1221,7 @@ impl Processor {
         program_id: &Pubkey,
         source_token_amount: u64,
         minimum_pool_token_amount: u64,
+        referral_code: Option<u64>,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
@@ -989,10 +1237,26 @@ impl Processor {
         let source_token_program_info = next_account_info(account_info_iter)?;
         let pool_token_program_info = next_account_info(account_info_iter)?;
 
+        let token_swap = unpack_swap_with_rates(program_id, swap_info, account_info_iter)?;
+        if token_swap.is_paused() {
+            return Err(SwapError::PoolPaused.into());
+        }
+        let allowlist_info = AllowlistEntry::next_allowlist_entry(account_info_iter, program_id);
+        if token_swap.is_liquidity_allowlisted() {
+            AllowlistEntry::check_allowlisted(
+                allowlist_info,
+                swap_info,
+                user_transfer_authority_info,
+                program_id,
+            )?;
+        }
+
         let pool_fee_account_info   = account_info_iter.next();
         let host_fee_account_info   = account_info_iter.next(); 
-
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        token_swap.swap_curve().calculator.validate_staleness(
+            token_swap.get_current_timestamp_opt()?,
+            token_swap.max_staleness(),
+        )?;
         let calculator = &token_swap.swap_curve().calculator;
         if !calculator.allows_deposits() {
             return Err(SwapError::UnsupportedCurveOperation.into());
@@ -1017,6 +1281,9 @@ impl Processor {
         } else {
             return Err(SwapError::IncorrectSwapAccount.into());
         };
+        if !token_swap.is_direction_enabled(trade_direction) {
+            return Err(SwapError::SwapDirectionDisabled.into());
+        }
 
         let (source_a_info, source_b_info) = match trade_direction {
             TradeDirection::AtoB => (Some(source_info), None),
@@ -1070,6 +1337,22 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        let (new_swap_token_a_amount, new_swap_token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (
+                swap_token_a_amount.checked_add(u128::from(source_token_amount)),
+                Some(swap_token_b_amount),
+            ),
+            TradeDirection::BtoA => (
+                Some(swap_token_a_amount),
+                swap_token_b_amount.checked_add(u128::from(source_token_amount)),
+            ),
+        };
+        Self::check_max_tvl(
+            token_swap.as_ref(),
+            new_swap_token_a_amount.ok_or(SwapError::CalculationFailure)?,
+            new_swap_token_b_amount.ok_or(SwapError::CalculationFailure)?,
+        )?;
+
         if owner_fee > 0 {
             if trade_direction == TradeDirection::AtoB {
                 swap_token_a_amount = swap_token_a_amount
@@ -1193,6 +1476,31 @@ impl Processor {
             pool_token_amount,
         )?;
 
+        if let Some(referral_code) = referral_code {
+            msg!(
+                "Referral: code {}, source_token_amount {}, pool_token_amount {}",
+                referral_code,
+                source_token_amount,
+                pool_token_amount
+            );
+        }
+
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (source_token_amount, 0),
+            TradeDirection::BtoA => (0, source_token_amount),
+        };
+        Event::Deposit(DepositEvent {
+            swap: *swap_info.key,
+            user: *user_transfer_authority_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            owner_fee: to_u64(owner_fee)?,
+            conversion_rate: conversion_rate(token_swap.as_ref())?,
+            referral_code,
+        })
+        .emit();
+
         Ok(())
     }
 
@@ -1218,9 +1526,25 @@ impl Processor {
         let pool_token_program_info = next_account_info(account_info_iter)?;
         let destination_token_program_info = next_account_info(account_info_iter)?;
 
-        let host_fee_account_info     = account_info_iter.next();
+        let token_swap = unpack_swap_with_rates(program_id, swap_info, account_info_iter)?;
+        if token_swap.is_paused() {
+            return Err(SwapError::PoolPaused.into());
+        }
+        let allowlist_info = AllowlistEntry::next_allowlist_entry(account_info_iter, program_id);
+        if token_swap.is_liquidity_allowlisted() {
+            AllowlistEntry::check_allowlisted(
+                allowlist_info,
+                swap_info,
+                user_transfer_authority_info,
+                program_id,
+            )?;
+        }
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let host_fee_account_info     = account_info_iter.next();
+        token_swap.swap_curve().calculator.validate_staleness(
+            token_swap.get_current_timestamp_opt()?,
+            token_swap.max_staleness(),
+        )?;
         let destination_account = Self::unpack_token_account(
             destination_info, 
             Some(destination_token_mint_info.key)
@@ -1241,6 +1565,10 @@ impl Processor {
         } else {
             return Err(SwapError::IncorrectSwapAccount.into());
         };
+        // withdrawing a single token type converts the other one into it
+        if !token_swap.is_direction_enabled(trade_direction.opposite()) {
+            return Err(SwapError::SwapDirectionDisabled.into());
+        }
 
         let (destination_a_info, destination_b_info) = match trade_direction {
             TradeDirection::AtoB => (Some(destination_info), None),
@@ -1436,6 +1764,63 @@ impl Processor {
             }
         }
 
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (destination_token_amount, 0),
+            TradeDirection::BtoA => (0, destination_token_amount),
+        };
+        Event::Withdraw(WithdrawEvent {
+            swap: *swap_info.key,
+            user: *user_transfer_authority_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount: to_u64(pool_token_amount)?,
+            withdraw_fee: to_u64(withdraw_fee)?,
+            conversion_rate: conversion_rate(token_swap.as_ref())?,
+        })
+        .emit();
+
+        Ok(())
+    }
+
+    /// Processes a [MigrateSwap](enum.Instruction.html).
+    pub fn process_migrate_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *system_program_info.key != system_program::ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let swap = match SwapVersion::unpack_version(&swap_info.data.borrow())? {
+            SwapVersion::SwapV1(swap) => SwapV2::from(swap),
+            SwapVersion::SwapV2(_) => return Err(ProgramError::AccountAlreadyInitialized),
+        };
+
+        let required_lamports = Rent::get()?
+            .minimum_balance(SwapVersion::LATEST_LEN)
+            .saturating_sub(swap_info.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, swap_info.key, required_lamports),
+                &[
+                    payer_info.clone(),
+                    swap_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+
+        swap_info.realloc(SwapVersion::LATEST_LEN, true)?;
+        SwapVersion::pack(SwapVersion::SwapV2(swap), &mut swap_info.data.borrow_mut())?;
+
         Ok(())
     }
 
@@ -1460,14 +1845,22 @@ impl Processor {
             SwapInstruction::Swap(Swap {
                 amount_in,
                 minimum_amount_out,
+                referral_code,
             }) => {
                 msg!("Instruction: Swap");
-                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
+                Self::process_swap(
+                    program_id,
+                    amount_in,
+                    minimum_amount_out,
+                    referral_code,
+                    accounts,
+                )
             }
             SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
                 pool_token_amount,
                 maximum_token_a_amount,
                 maximum_token_b_amount,
+                referral_code,
             }) => {
                 msg!("Instruction: DepositAllTokenTypes");
                 Self::process_deposit_all_token_types(
@@ -1475,6 +1868,7 @@ impl Processor {
                     pool_token_amount,
                     maximum_token_a_amount,
                     maximum_token_b_amount,
+                    referral_code,
                     accounts,
                 )
             }
@@ -1496,6 +1890,7 @@ impl Processor {
                 DepositSingleTokenTypeExactAmountIn {
                     source_token_amount,
                     minimum_pool_token_amount,
+                    referral_code,
                 },
             ) => {
                 msg!("Instruction: DepositSingleTokenTypeExactAmountIn");
@@ -1503,6 +1898,7 @@ impl Processor {
                     program_id,
                     source_token_amount,
                     minimum_pool_token_amount,
+                    referral_code,
                     accounts,
                 )
             }
@@ -1539,8 +1935,7 @@ impl Processor {
             SwapInstruction::InitializePermission(
                 InitializePermission {
                     permission_authority,
-                    is_super_admin,
-                    can_update_parameters
+                    roles
                 }
             ) => {
                 msg!("Instruction: InitializePermission");
@@ -1550,33 +1945,297 @@ impl Processor {
                 process_initialize_permission(
                     program_id, accounts, 
                     permission_authority_pubkey, 
-                    is_super_admin, 
-                    can_update_parameters
+                    roles
                 )
 
             }
             SwapInstruction::UpdatePermission(
-                UpdatePermission {
-                    is_super_admin,
-                    can_update_parameters
-                }
+                UpdatePermission { roles }
             ) => {
                 msg!("Instruction: UpdatePermission");
                 process_update_permission(
                     program_id, 
                     accounts, 
-                    is_super_admin, 
-                    can_update_parameters
+                    roles
+                )
+            }
+            SwapInstruction::SetPaused(SetPaused { is_paused }) => {
+                msg!("Instruction: SetPaused");
+                process_set_paused(
+                    program_id, 
+                    accounts, 
+                    is_paused
+                )
+            }
+            SwapInstruction::MigrateSwap => {
+                msg!("Instruction: MigrateSwap");
+                Self::process_migrate_swap(program_id, accounts)
+            }
+            SwapInstruction::SetMaxStaleness(SetMaxStaleness { max_staleness }) => {
+                msg!("Instruction: SetMaxStaleness");
+                process_set_max_staleness(
+                    program_id, 
+                    accounts, 
+                    max_staleness
+                )
+            }
+            SwapInstruction::SetMaxSsr(SetMaxSsr { max_ssr }) => {
+                msg!("Instruction: SetMaxSsr");
+                process_max_ssr_update(
+                    program_id, 
+                    accounts, 
+                    max_ssr
+                )
+            }
+            SwapInstruction::UpdateFees(UpdateFees { fees }) => {
+                msg!("Instruction: UpdateFees");
+                process_update_fees(
+                    program_id, 
+                    accounts, 
+                    fees, 
+                    swap_constraints
+                )
+            }
+            SwapInstruction::ProposeSuperAdmin(ProposeSuperAdmin { new_authority }) => {
+                msg!("Instruction: ProposeSuperAdmin");
+                process_propose_super_admin(
+                    program_id, 
+                    accounts, 
+                    Pubkey::new_from_array(new_authority)
+                )
+            }
+            SwapInstruction::AcceptSuperAdmin => {
+                msg!("Instruction: AcceptSuperAdmin");
+                process_accept_super_admin(program_id, accounts)
+            }
+            SwapInstruction::ClosePermission => {
+                msg!("Instruction: ClosePermission");
+                process_close_permission(program_id, accounts)
+            }
+            SwapInstruction::MigratePermission => {
+                msg!("Instruction: MigratePermission");
+                process_migrate_permission(program_id, accounts)
+            }
+            SwapInstruction::SetTimelockDelay(SetTimelockDelay { timelock_delay }) => {
+                msg!("Instruction: SetTimelockDelay");
+                process_set_timelock_delay(
+                    program_id, 
+                    accounts, 
+                    timelock_delay
+                )
+            }
+            SwapInstruction::QueueChange(QueueChange { change }) => {
+                msg!("Instruction: QueueChange");
+                process_queue_change(
+                    program_id, 
+                    accounts, 
+                    change, 
+                    swap_constraints
+                )
+            }
+            SwapInstruction::ExecuteChange => {
+                msg!("Instruction: ExecuteChange");
+                process_execute_change(
+                    program_id, 
+                    accounts, 
+                    swap_constraints
+                )
+            }
+            SwapInstruction::CancelChange => {
+                msg!("Instruction: CancelChange");
+                process_cancel_change(program_id, accounts)
+            }
+            SwapInstruction::SetRateUpdateLimits(SetRateUpdateLimits {
+                max_ssr_delta,
+                min_update_interval,
+            }) => {
+                msg!("Instruction: SetRateUpdateLimits");
+                process_set_rate_update_limits(
+                    program_id, 
+                    accounts, 
+                    max_ssr_delta, 
+                    min_update_interval
+                )
+            }
+            SwapInstruction::SetRateMultisig(SetRateMultisig { threshold }) => {
+                msg!("Instruction: SetRateMultisig");
+                process_set_rate_multisig(
+                    program_id, 
+                    accounts, 
+                    threshold
+                )
+            }
+            SwapInstruction::SetFeeAccount => {
+                msg!("Instruction: SetFeeAccount");
+                process_set_fee_account(
+                    program_id, 
+                    accounts, 
+                    swap_constraints
+                )
+            }
+            SwapInstruction::ClosePool => {
+                msg!("Instruction: ClosePool");
+                process_close_pool(program_id, accounts)
+            }
+            SwapInstruction::SetSwapDirections(SetSwapDirections {
+                a_to_b_enabled,
+                b_to_a_enabled,
+            }) => {
+                msg!("Instruction: SetSwapDirections");
+                process_set_swap_directions(
+                    program_id, 
+                    accounts, 
+                    a_to_b_enabled, 
+                    b_to_a_enabled
+                )
+            }
+            SwapInstruction::SetAllowlistMode(SetAllowlistMode {
+                liquidity_allowlisted,
+                swaps_allowlisted,
+            }) => {
+                msg!("Instruction: SetAllowlistMode");
+                process_set_allowlist_mode(
+                    program_id, 
+                    accounts, 
+                    liquidity_allowlisted, 
+                    swaps_allowlisted
+                )
+            }
+            SwapInstruction::AddToAllowlist(AddToAllowlist { user }) => {
+                msg!("Instruction: AddToAllowlist");
+                process_add_to_allowlist(
+                    program_id, 
+                    accounts, 
+                    Pubkey::new_from_array(user)
+                )
+            }
+            SwapInstruction::RemoveFromAllowlist => {
+                msg!("Instruction: RemoveFromAllowlist");
+                process_remove_from_allowlist(program_id, accounts)
+            }
+            SwapInstruction::SetSwapRateLimits(SetSwapRateLimits {
+                a_to_b_max_amount,
+                a_to_b_slope,
+                b_to_a_max_amount,
+                b_to_a_slope,
+            }) => {
+                msg!("Instruction: SetSwapRateLimits");
+                process_set_swap_rate_limits(
+                    program_id, 
+                    accounts, 
+                    a_to_b_max_amount, 
+                    a_to_b_slope, 
+                    b_to_a_max_amount, 
+                    b_to_a_slope
+                )
+            }
+            SwapInstruction::SetMaxTvl(SetMaxTvl { max_tvl }) => {
+                msg!("Instruction: SetMaxTvl");
+                process_set_max_tvl(program_id, accounts, max_tvl)
+            }
+            SwapInstruction::SwapExactAmountOut(SwapExactAmountOut {
+                amount_out,
+                maximum_amount_in,
+                referral_code,
+            }) => {
+                msg!("Instruction: SwapExactAmountOut");
+                Self::process_swap_exact_amount_out(
+                    program_id,
+                    amount_out,
+                    maximum_amount_in,
+                    referral_code,
+                    accounts,
+                )
+            }
+            SwapInstruction::PreviewSwap(PreviewSwap { amount, exact_out }) => {
+                msg!("Instruction: PreviewSwap");
+                process_preview_swap(program_id, accounts, amount, exact_out)
+            }
+            SwapInstruction::PreviewDeposit(PreviewDeposit { source_token_amount }) => {
+                msg!("Instruction: PreviewDeposit");
+                process_preview_deposit(program_id, accounts, source_token_amount)
+            }
+            SwapInstruction::PreviewWithdraw(PreviewWithdraw { destination_token_amount }) => {
+                msg!("Instruction: PreviewWithdraw");
+                process_preview_withdraw(program_id, accounts, destination_token_amount)
+            }
+            SwapInstruction::GetConversionRate(GetConversionRate { timestamp }) => {
+                msg!("Instruction: GetConversionRate");
+                process_get_conversion_rate(program_id, accounts, timestamp)
+            }
+            SwapInstruction::InitializePsm3(InitializePsm3 { rates }) => {
+                msg!("Instruction: InitializePsm3");
+                process_initialize_psm3(program_id, accounts, rates)
+            }
+            SwapInstruction::Psm3Swap(Psm3Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: Psm3Swap");
+                process_psm3_swap(program_id, accounts, amount_in, minimum_amount_out)
+            }
+            SwapInstruction::Psm3Deposit(Psm3Deposit {
+                source_token_amount,
+                minimum_pool_token_amount,
+            }) => {
+                msg!("Instruction: Psm3Deposit");
+                process_psm3_deposit(
+                    program_id, 
+                    accounts, 
+                    source_token_amount, 
+                    minimum_pool_token_amount
+                )
+            }
+            SwapInstruction::Psm3Withdraw(Psm3Withdraw {
+                destination_token_amount,
+                maximum_pool_token_amount,
+            }) => {
+                msg!("Instruction: Psm3Withdraw");
+                process_psm3_withdraw(
+                    program_id, 
+                    accounts, 
+                    destination_token_amount, 
+                    maximum_pool_token_amount
+                )
+            }
+            SwapInstruction::InitializeRateProvider(InitializeRateProvider { rates }) => {
+                msg!("Instruction: InitializeRateProvider");
+                process_initialize_rate_provider(program_id, accounts, rates)
+            }
+            SwapInstruction::RelaySignedRates(rates) => {
+                msg!("Instruction: RelaySignedRates");
+                process_relay_signed_rates(program_id, accounts, rates)
+            }
+            SwapInstruction::SetBridgeReceiver(SetBridgeReceiver { bridge_receiver }) => {
+                msg!("Instruction: SetBridgeReceiver");
+                process_set_bridge_receiver(
+                    program_id,
+                    accounts,
+                    Pubkey::new_from_array(bridge_receiver)
                 )
             }
+            SwapInstruction::BridgeSetRates(BridgeSetRates { ssr, rho, chi }) => {
+                msg!("Instruction: BridgeSetRates");
+                process_bridge_set_rates(program_id, accounts, ssr, rho, chi)
+            }
         }
     }
 }
 
-fn to_u64(val: u128) -> Result<u64, SwapError> {
+pub(crate) fn to_u64(val: u128) -> Result<u64, SwapError> {
     val.try_into().map_err(|_| SwapError::ConversionFailure)
 }
 
+/// Conversion rate the curve of the pool prices with right now, zero if it
+/// has none
+pub(crate) fn conversion_rate(token_swap: &dyn SwapState) -> Result<u128, ProgramError> {
+    Ok(token_swap
+        .swap_curve()
+        .calculator
+        .conversion_rate(token_swap.get_current_timestamp_opt()?)
+        .unwrap_or(0))
+}
+
 fn invoke_signed_wrapper<T>(
     instruction: &Instruction,
     account_infos: &[AccountInfo],
@@ -1847,16 +2506,72 @@ mod tests {
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
-                    &mut SolanaAccount::default(),
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    &mut self.token_a_mint_account,
-                    &mut self.token_b_mint_account,
-                    &mut self.pool_fee_account,
-                    &mut self.pool_token_account,
+                    &mut SolanaAccount::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut self.pool_token_account,
+                    &mut SolanaAccount::default(),
+                ],
+            )
+        }
+
+        pub fn set_paused(
+            &mut self,
+            authority_key: &Pubkey,
+            permission_account: &mut SolanaAccount,
+            is_paused: bool,
+        ) -> ProgramResult {
+            do_process_instruction(
+                Instruction {
+                    program_id: SWAP_PROGRAM_ID,
+                    accounts: vec![
+                        AccountMeta::new(self.swap_key, false),
+                        AccountMeta::new_readonly(
+                            get_permission_key(&self.swap_key, authority_key), 
+                            false
+                        ),
+                        AccountMeta::new_readonly(*authority_key, true),
+                    ],
+                    data: SwapInstruction::SetPaused(SetPaused { is_paused }).pack(),
+                },
+                vec![
+                    &mut self.swap_account,
+                    permission_account,
+                    &mut SolanaAccount::default(),
+                ],
+            )
+        }
+
+        pub fn update_fees(
+            &mut self,
+            authority_key: &Pubkey,
+            permission_account: &mut SolanaAccount,
+            fees: Fees,
+            swap_constraints: &Option<SwapConstraints>,
+        ) -> ProgramResult {
+            do_process_instruction_with_fee_constraints(
+                Instruction {
+                    program_id: SWAP_PROGRAM_ID,
+                    accounts: vec![
+                        AccountMeta::new(self.swap_key, false),
+                        AccountMeta::new_readonly(
+                            get_permission_key(&self.swap_key, authority_key), 
+                            false
+                        ),
+                        AccountMeta::new_readonly(*authority_key, true),
+                    ],
+                    data: SwapInstruction::UpdateFees(UpdateFees { fees }).pack(),
+                },
+                vec![
+                    &mut self.swap_account,
+                    permission_account,
                     &mut SolanaAccount::default(),
                 ],
+                swap_constraints,
             )
         }
 
@@ -2020,9 +2735,12 @@ mod tests {
                     &source_mint_key,
                     &destination_mint_key,
                     None,
+                    None,
+                    None,
                     Swap {
                         amount_in,
                         minimum_amount_out,
+                        referral_code: None,
                     },
                 )
                 .unwrap(),
@@ -2121,10 +2839,13 @@ mod tests {
                     depositor_pool_key,
                     &self.token_a_mint_key,
                     &self.token_b_mint_key,
+                    None,
+                    None,
                     DepositAllTokenTypes {
                         pool_token_amount,
                         maximum_token_a_amount,
                         maximum_token_b_amount,
+                        referral_code: None,
                     },
                 )
                 .unwrap(),
@@ -2203,6 +2924,8 @@ mod tests {
                     token_b_key,
                     &self.token_a_mint_key,
                     &self.token_b_mint_key,
+                    None,
+                    None,
                     WithdrawAllTokenTypes {
                         pool_token_amount,
                         minimum_token_a_amount,
@@ -2284,9 +3007,12 @@ mod tests {
                     &self.pool_mint_key,
                     deposit_pool_key,
                     &source_mint_key,
+                    None,
+                    None,
                     DepositSingleTokenTypeExactAmountIn {
                         source_token_amount,
                         minimum_pool_token_amount,
+                        referral_code: None,
                     },
                 )
                 .unwrap(),
@@ -2363,6 +3089,8 @@ mod tests {
                     &self.token_b_key,
                     destination_key,
                     &destination_mint_key,
+                    None,
+                    None,
                     WithdrawSingleTokenTypeExactAmountOut {
                         destination_token_amount,
                         maximum_pool_token_amount,
@@ -2470,6 +3198,37 @@ mod tests {
         do_process_instruction_with_fee_constraints(instruction, accounts, &SWAP_CONSTRAINTS)
     }
 
+    fn get_permission_key(swap_key: &Pubkey, authority_key: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                Permission::PERMISSION_SEED,
+                &swap_key.to_bytes(),
+                &authority_key.to_bytes(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    fn create_permission_account(
+        swap_key: &Pubkey,
+        authority_key: &Pubkey,
+        roles: u64,
+    ) -> SolanaAccount {
+        let mut permission_account = SolanaAccount::new(0, Permission::LEN, &SWAP_PROGRAM_ID);
+        Permission::pack(
+            Permission {
+                is_initialized: true,
+                swap: *swap_key,
+                authority: *authority_key,
+                roles,
+            },
+            &mut permission_account.data,
+        )
+        .unwrap();
+        permission_account
+    }
+
     fn mint_token(
         program_id: &Pubkey,
         mint_key: &Pubkey,
@@ -3995,10 +4754,13 @@ mod tests {
                         &pool_key,
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
+                        None,
+                        None,
                         DepositAllTokenTypes {
                             pool_token_amount: pool_amount.try_into().unwrap(),
                             maximum_token_a_amount: deposit_a,
                             maximum_token_b_amount: deposit_b,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -4052,10 +4814,13 @@ mod tests {
                         &pool_key,
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
+                        None,
+                        None,
                         DepositAllTokenTypes {
                             pool_token_amount: pool_amount.try_into().unwrap(),
                             maximum_token_a_amount: deposit_a,
                             maximum_token_b_amount: deposit_b,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -4699,6 +5464,8 @@ mod tests {
                         &token_b_key,
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
+                        None,
+                        None,
                         WithdrawAllTokenTypes {
                             pool_token_amount: withdraw_amount.try_into().unwrap(),
                             minimum_token_a_amount,
@@ -4764,6 +5531,8 @@ mod tests {
                         &token_b_key,
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
+                        None,
+                        None,
                         WithdrawAllTokenTypes {
                             pool_token_amount: withdraw_amount.try_into().unwrap(),
                             minimum_token_a_amount,
@@ -5425,9 +6194,12 @@ mod tests {
                         &accounts.pool_mint_key,
                         &pool_key,
                         &accounts.token_a_mint_key,
+                        None,
+                        None,
                         DepositSingleTokenTypeExactAmountIn {
                             source_token_amount: deposit_a,
                             minimum_pool_token_amount: pool_amount,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -5475,9 +6247,12 @@ mod tests {
                         &accounts.pool_mint_key,
                         &pool_key,
                         &accounts.token_a_mint_key,
+                        None,
+                        None,
                         DepositSingleTokenTypeExactAmountIn {
                             source_token_amount: deposit_a,
                             minimum_pool_token_amount: pool_amount,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -5775,9 +6550,12 @@ mod tests {
                 &accounts.pool_mint_key,
                 &pool_key,
                 &accounts.token_a_mint_key,
+                None,
+                None,
                 DepositSingleTokenTypeExactAmountIn {
                     source_token_amount: deposit_a,
                     minimum_pool_token_amount: pool_amount,
+                    referral_code: None,
                 },
             )
             .unwrap();
@@ -5864,9 +6642,12 @@ mod tests {
                 &accounts.pool_mint_key,
                 &pool_key,
                 &accounts.token_a_mint_key,
+                None,
+                None,
                 DepositSingleTokenTypeExactAmountIn {
                     source_token_amount: deposit_a,
                     minimum_pool_token_amount: pool_amount,
+                    referral_code: None,
                 },
             )
             .unwrap();
@@ -6290,6 +7071,8 @@ mod tests {
                         &accounts.token_b_key,
                         &token_a_key,
                         &accounts.token_a_mint_key,
+                        None,
+                        None,
                         WithdrawSingleTokenTypeExactAmountOut {
                             destination_token_amount: destination_a_amount,
                             maximum_pool_token_amount,
@@ -6348,6 +7131,8 @@ mod tests {
                         &accounts.token_b_key,
                         &token_a_key,
                         &accounts.token_a_mint_key,
+                        None,
+                        None,
                         WithdrawSingleTokenTypeExactAmountOut {
                             destination_token_amount: destination_a_amount,
                             maximum_pool_token_amount,
@@ -7344,10 +8129,13 @@ mod tests {
                 &accounts.pool_fee_key,
                 &accounts.token_a_mint_key,
                 &accounts.token_b_mint_key,
+                None,
+                None,
                 Some(&pool_key),
                 Swap {
                     amount_in,
                     minimum_amount_out,
+                    referral_code: None,
                 },
             )
             .unwrap(),
@@ -7574,9 +8362,12 @@ mod tests {
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
                         None,
+                        None,
+                        None,
                         Swap {
                             amount_in: initial_a,
                             minimum_amount_out: minimum_token_b_amount,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -7657,9 +8448,12 @@ mod tests {
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
                         None,
+                        None,
+                        None,
                         Swap {
                             amount_in: initial_a,
                             minimum_amount_out: minimum_token_b_amount,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -7838,9 +8632,12 @@ mod tests {
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
                         None,
+                        None,
+                        None,
                         Swap {
                             amount_in: initial_a,
                             minimum_amount_out: minimum_token_b_amount,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -8004,9 +8801,12 @@ mod tests {
                     &accounts.token_a_mint_key,
                     &accounts.token_b_mint_key,
                     None,
+                    None,
+                    None,
                     Swap {
                         amount_in: initial_a,
                         minimum_amount_out: minimum_token_b_amount,
+                        referral_code: None,
                     },
                 )
                 .unwrap(),
@@ -8085,10 +8885,13 @@ mod tests {
                         &accounts.pool_fee_key,
                         &accounts.token_a_mint_key,
                         &accounts.token_b_mint_key,
+                        None,
+                        None,
                         Some(&bad_token_a_key),
                         Swap {
                             amount_in: initial_a,
                             minimum_amount_out: 0,
+                            referral_code: None,
                         },
                     )
                     .unwrap(),
@@ -8772,6 +9575,8 @@ mod tests {
                 &token_b_key,
                 &accounts.token_a_mint_key,
                 &accounts.token_b_mint_key,
+                None,
+                None,
                 WithdrawAllTokenTypes {
                     pool_token_amount,
                     minimum_token_a_amount,
@@ -8923,6 +9728,8 @@ mod tests {
                 &accounts.token_b_key,
                 &token_a_key,
                 &accounts.token_a_mint_key,
+                None,
+                None,
                 WithdrawSingleTokenTypeExactAmountOut {
                     destination_token_amount: destination_a_amount,
                     maximum_pool_token_amount,
@@ -9086,10 +9893,13 @@ mod tests {
                 &accounts.pool_fee_key,
                 &accounts.token_a_mint_key,
                 &accounts.token_b_mint_key,
+                None,
+                None,
                 Some(&pool_key),
                 Swap {
                     amount_in: token_a_amount / 2,
                     minimum_amount_out: 0,
+                    referral_code: None,
                 },
             )
             .unwrap(),
@@ -9157,4 +9967,291 @@ mod tests {
             &token_b_program_id,
         );
     }
+
+    #[test_case(spl_token::id(), spl_token::id(), spl_token::id(); "all-token")]
+    #[test_case(spl_token_2022::id(), spl_token_2022::id(), spl_token_2022::id(); "all-token-2022")]
+    #[test_case(spl_token::id(), spl_token_2022::id(), spl_token_2022::id(); "mixed-pool-token")]
+    #[test_case(spl_token_2022::id(), spl_token_2022::id(), spl_token::id(); "mixed-pool-token-2022")]
+    fn test_paused_pool(
+        pool_token_program_id: Pubkey,
+        token_a_program_id: Pubkey,
+        token_b_program_id: Pubkey,
+    ) {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 0,
+            owner_trade_fee_denominator: 0,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let user_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let pauser_key = Pubkey::new_unique();
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            swap_curve,
+            1_000_000,
+            1_000_000,
+            &pool_token_program_id,
+            &token_a_program_id,
+            &token_b_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let initial_a = 10_000;
+        let initial_b = 10_000;
+        let initial_pool = 1_000_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &user_key,
+            &depositor_key,
+            initial_a,
+            initial_b,
+            initial_pool,
+        );
+
+        // permission without the pause capability
+        {
+            let mut permission_account =
+                create_permission_account(&accounts.swap_key, &pauser_key, roles::UPDATE_RATES);
+            assert_eq!(
+                Err(SwapError::InvalidUpdatePermission.into()),
+                accounts.set_paused(&pauser_key, &mut permission_account, true)
+            );
+        }
+
+        let mut permission_account =
+            create_permission_account(&accounts.swap_key, &pauser_key, roles::PAUSE);
+        accounts
+            .set_paused(&pauser_key, &mut permission_account, true)
+            .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_state.is_paused());
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        assert_eq!(
+            Err(SwapError::PoolPaused.into()),
+            accounts.swap(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                initial_a / 10,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::PoolPaused.into()),
+            accounts.deposit_all_token_types(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                initial_pool / 10,
+                initial_a,
+                initial_b,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::PoolPaused.into()),
+            accounts.deposit_single_token_type_exact_amount_in(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                initial_a / 10,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::PoolPaused.into()),
+            accounts.withdraw_single_token_type_exact_amount_out(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                initial_a / 10,
+                initial_pool,
+            )
+        );
+
+        // withdrawing both tokens is still allowed while paused
+        accounts
+            .withdraw_all_token_types(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                initial_pool / 2,
+                0,
+                0,
+            )
+            .unwrap();
+
+        accounts
+            .set_paused(&pauser_key, &mut permission_account, false)
+            .unwrap();
+        accounts
+            .swap(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                initial_a / 10,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_update_fees() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 0,
+            owner_trade_fee_denominator: 0,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let user_key = Pubkey::new_unique();
+        let fee_admin_key = Pubkey::new_unique();
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve,
+            1_000_000,
+            1_000_000,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
+        );
+        accounts.initialize_swap().unwrap();
+
+        let new_fees = Fees {
+            trade_fee_numerator: 5,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+
+        // permission without the update fees capability
+        {
+            let mut permission_account =
+                create_permission_account(&accounts.swap_key, &fee_admin_key, roles::PAUSE);
+            assert_eq!(
+                Err(SwapError::InvalidUpdatePermission.into()),
+                accounts.update_fees(
+                    &fee_admin_key,
+                    &mut permission_account,
+                    new_fees.clone(),
+                    &None
+                )
+            );
+        }
+
+        let mut permission_account =
+            create_permission_account(&accounts.swap_key, &fee_admin_key, roles::UPDATE_FEES);
+
+        // invalid fees
+        {
+            let mut bad_fees = new_fees.clone();
+            bad_fees.trade_fee_numerator = bad_fees.trade_fee_denominator + 1;
+            assert_eq!(
+                Err(SwapError::InvalidFee.into()),
+                accounts.update_fees(
+                    &fee_admin_key,
+                    &mut permission_account,
+                    bad_fees,
+                    &None
+                )
+            );
+        }
+
+        // fees below the constraints
+        {
+            let owner_key = user_key.to_string();
+            let constraints = Some(SwapConstraints {
+                owner_key: Some(owner_key.as_ref()),
+                valid_curve_types: &[CurveType::ConstantProduct],
+                fees: &fees,
+            });
+            assert_eq!(
+                Err(SwapError::InvalidFee.into()),
+                accounts.update_fees(
+                    &fee_admin_key,
+                    &mut permission_account,
+                    new_fees.clone(),
+                    &constraints
+                )
+            );
+        }
+
+        accounts
+            .update_fees(
+                &fee_admin_key,
+                &mut permission_account,
+                new_fees.clone(),
+                &None
+            )
+            .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(*swap_state.fees(), new_fees);
+        assert_eq!(*swap_state.token_a_account(), accounts.token_a_key);
+
+        // super admins can always update fees
+        let super_admin_key = Pubkey::new_unique();
+        let mut permission_account =
+            create_permission_account(&accounts.swap_key, &super_admin_key, roles::SUPER_ADMIN);
+        accounts
+            .update_fees(
+                &super_admin_key,
+                &mut permission_account,
+                fees.clone(),
+                &None
+            )
+            .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(*swap_state.fees(), fees);
+    }
 }
@@ -0,0 +1,973 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Three-asset pool inspired by Spark PSM3, holding USDC, USDS and sUSDS
+//! behind a single pool token. USDC and USDS swap 1:1, sUSDS is priced with
+//! the rate math of the `RedemptionRateCurve`, and pool tokens are valued in
+//! USD terms.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_math::{checked_ceil_div::CheckedCeilDiv, uint::U256};
+
+use crate::{
+    constraints::validate_mint_extensions,
+    curve::{
+        calculator::{CurveCalculator, RoundDirection},
+        redemption_rate::{RateUpdateLimits, RedemptionRateCurve, RAY},
+    },
+    error::SwapError,
+    event::{Event, Psm3LiquidityEvent, Psm3SwapEvent},
+    permission::{roles, Permission},
+    processor::{to_u64, Processor},
+};
+
+/// Number of assets held by a three-asset pool
+pub const PSM3_ASSETS: usize = 3;
+
+/// Assets of a three-asset pool, in the order they are stored
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Psm3Asset {
+    /// Stable asset swapped 1:1 with USDS
+    Usdc,
+    /// Stable asset swapped 1:1 with USDC
+    Usds,
+    /// Savings asset worth `conversion_rate / RAY` USDS
+    Susds,
+}
+
+impl Psm3Asset {
+    /// All assets, in the order they are stored
+    pub const ALL: [Psm3Asset; PSM3_ASSETS] = [Self::Usdc, Self::Usds, Self::Susds];
+
+    /// Value in USD of `amount` of the asset
+    pub fn value(
+        self,
+        amount: u128,
+        conversion_rate: U256,
+        round_direction: RoundDirection,
+    ) -> Option<u128> {
+        match self {
+            Self::Usdc | Self::Usds => Some(amount),
+            Self::Susds => {
+                let value = U256::from(amount).checked_mul(conversion_rate)?;
+                let value = match round_direction {
+                    RoundDirection::Floor => value.checked_div(U256::from(RAY))?,
+                    RoundDirection::Ceiling => value.checked_ceil_div(U256::from(RAY))?.0,
+                };
+                value.try_into().ok()
+            }
+        }
+    }
+
+    /// Amount of the asset worth `value` USD
+    pub fn amount(
+        self,
+        value: u128,
+        conversion_rate: U256,
+        round_direction: RoundDirection,
+    ) -> Option<u128> {
+        match self {
+            Self::Usdc | Self::Usds => Some(value),
+            Self::Susds => {
+                let amount = U256::from(value).checked_mul(U256::from(RAY))?;
+                let amount = match round_direction {
+                    RoundDirection::Floor => amount.checked_div(conversion_rate)?,
+                    RoundDirection::Ceiling => amount.checked_ceil_div(conversion_rate)?.0,
+                };
+                amount.try_into().ok()
+            }
+        }
+    }
+}
+
+/// Value in USD of the reserves of a pool, rounded down
+pub fn total_value(reserves: [u128; PSM3_ASSETS], conversion_rate: U256) -> Option<u128> {
+    Psm3Asset::ALL
+        .iter()
+        .zip(reserves)
+        .try_fold(0u128, |total, (asset, amount)| {
+            total.checked_add(asset.value(amount, conversion_rate, RoundDirection::Floor)?)
+        })
+}
+
+/// Amount of `destination` tokens received for `amount_in` of `source`
+pub fn swap_amount_out(
+    source: Psm3Asset,
+    destination: Psm3Asset,
+    amount_in: u128,
+    conversion_rate: U256,
+) -> Option<u128> {
+    let value = source.value(amount_in, conversion_rate, RoundDirection::Floor)?;
+    destination.amount(value, conversion_rate, RoundDirection::Floor)
+}
+
+/// Amount of pool tokens worth `value` USD, rounded in `round_direction`
+pub fn value_to_pool_tokens(
+    value: u128,
+    reserves: [u128; PSM3_ASSETS],
+    pool_supply: u128,
+    conversion_rate: U256,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    let total_value = U256::from(total_value(reserves, conversion_rate)?);
+    let pool_tokens = U256::from(pool_supply).checked_mul(U256::from(value))?;
+    let pool_tokens = match round_direction {
+        RoundDirection::Floor => pool_tokens.checked_div(total_value)?,
+        RoundDirection::Ceiling => pool_tokens.checked_ceil_div(total_value)?.0,
+    };
+    pool_tokens.try_into().ok()
+}
+
+/// Three-asset pool state
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Psm3Pool {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Bump seed of the pool authority, derived like the authority of a swap
+    pub bump_seed: u8,
+    /// Program ID of the pool token
+    pub token_program_id: Pubkey,
+    /// Pool tokens are issued when any asset is deposited, valued in USD
+    pub pool_mint: Pubkey,
+    /// Reserve token accounts, indexed by `Psm3Asset`
+    pub reserves: [Pubkey; PSM3_ASSETS],
+    /// Mints of the assets, indexed by `Psm3Asset`
+    pub mints: [Pubkey; PSM3_ASSETS],
+    /// Rates pricing sUSDS in USDS
+    pub rates: RedemptionRateCurve,
+    /// Swaps and deposits are rejected while paused, withdrawals stay open
+    pub is_paused: bool,
+    /// Maximum age in seconds of the rates for swaps and deposits, zero for
+    /// no bound
+    pub max_staleness: u64,
+    /// Maximum change of ssr in a single rate update, zero if unbounded
+    pub max_ssr_delta: u128,
+    /// Minimum number of seconds between two rate updates, zero if unbounded
+    pub min_rates_update_interval: u64,
+    /// Timestamp of the last rate update, zero before the first one
+    pub last_rates_update: u64,
+    /// Delay in seconds before a queued change can be executed, zero
+    /// if changes apply directly
+    pub timelock_delay: u64,
+}
+
+impl Sealed for Psm3Pool {}
+impl IsInitialized for Psm3Pool {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Psm3Pool {
+    const LEN: usize = 450;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, Psm3Pool::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            pool_mint,
+            reserves,
+            mints,
+            rates,
+            is_paused,
+            max_staleness,
+            max_ssr_delta,
+            min_rates_update_interval,
+            last_rates_update,
+            timelock_delay,
+            _reserved,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 96, 96, 64, 1, 8, 16, 8, 8, 8, 79];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        for (dst, key) in reserves.chunks_exact_mut(32).zip(&self.reserves) {
+            dst.copy_from_slice(key.as_ref());
+        }
+        for (dst, key) in mints.chunks_exact_mut(32).zip(&self.mints) {
+            dst.copy_from_slice(key.as_ref());
+        }
+        Pack::pack_into_slice(&self.rates, rates);
+        is_paused[0] = self.is_paused as u8;
+        *max_staleness = self.max_staleness.to_le_bytes();
+        *max_ssr_delta = self.max_ssr_delta.to_le_bytes();
+        *min_rates_update_interval = self.min_rates_update_interval.to_le_bytes();
+        *last_rates_update = self.last_rates_update.to_le_bytes();
+        *timelock_delay = self.timelock_delay.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Psm3Pool, ProgramError> {
+        let input = array_ref![input, 0, Psm3Pool::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            pool_mint,
+            reserves,
+            mints,
+            rates,
+            is_paused,
+            max_staleness,
+            max_ssr_delta,
+            min_rates_update_interval,
+            last_rates_update,
+            timelock_delay,
+            _reserved,
+        ) = array_refs![input, 1, 1, 32, 32, 96, 96, 64, 1, 8, 16, 8, 8, 8, 79];
+
+        let unpack_keys = |input: &[u8; 96]| -> Result<[Pubkey; PSM3_ASSETS], ProgramError> {
+            let mut keys = [Pubkey::default(); PSM3_ASSETS];
+            for (key, bytes) in keys.iter_mut().zip(input.chunks_exact(32)) {
+                *key = Pubkey::try_from(bytes).map_err(|_| ProgramError::InvalidAccountData)?;
+            }
+            Ok(keys)
+        };
+
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            reserves: unpack_keys(reserves)?,
+            mints: unpack_keys(mints)?,
+            rates: RedemptionRateCurve::unpack_from_slice(rates)?,
+            is_paused: match is_paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            max_staleness: u64::from_le_bytes(*max_staleness),
+            max_ssr_delta: u128::from_le_bytes(*max_ssr_delta),
+            min_rates_update_interval: u64::from_le_bytes(*min_rates_update_interval),
+            last_rates_update: u64::from_le_bytes(*last_rates_update),
+            timelock_delay: u64::from_le_bytes(*timelock_delay),
+        })
+    }
+}
+
+impl Psm3Pool {
+    /// Returns true if `account_info` has the size of a three-asset pool
+    pub fn is_psm3_pool(account_info: &AccountInfo) -> bool {
+        account_info.data_len() == Self::LEN
+    }
+
+    /// Bounds on rate updates of the pool
+    pub fn rate_update_limits(&self) -> RateUpdateLimits {
+        RateUpdateLimits {
+            max_ssr_delta: self.max_ssr_delta,
+            min_update_interval: self.min_rates_update_interval,
+            last_update: self.last_rates_update,
+        }
+    }
+
+    /// Unpacks an initialized pool owned by the program
+    pub fn unpack_pool(
+        pool_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+        if !Self::is_psm3_pool(pool_info) {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        Self::unpack(&pool_info.data.borrow())
+    }
+
+    /// Checks the pool authority
+    fn check_authority(
+        &self,
+        authority_info: &AccountInfo,
+        pool_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        if *authority_info.key
+            != Processor::authority_id(program_id, pool_info.key, self.bump_seed)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into())
+        }
+        Ok(())
+    }
+
+    /// Asset held in the reserve `reserve`
+    fn reserve_asset(&self, reserve: &Pubkey) -> Result<Psm3Asset, ProgramError> {
+        Psm3Asset::ALL
+            .into_iter()
+            .find(|asset| self.reserves[*asset as usize] == *reserve)
+            .ok_or(SwapError::IncorrectSwapAccount.into())
+    }
+
+    /// Asset of the mint `mint`
+    fn mint_asset(&self, mint: &Pubkey) -> Result<Psm3Asset, ProgramError> {
+        Psm3Asset::ALL
+            .into_iter()
+            .find(|asset| self.mints[*asset as usize] == *mint)
+            .ok_or(SwapError::IncorrectSwapAccount.into())
+    }
+
+    /// Checks the reserve accounts and returns their balances
+    fn unpack_reserves(
+        &self,
+        reserve_infos: [&AccountInfo; PSM3_ASSETS],
+    ) -> Result<[u128; PSM3_ASSETS], ProgramError> {
+        let mut reserves = [0u128; PSM3_ASSETS];
+        for asset in Psm3Asset::ALL {
+            let reserve_info = reserve_infos[asset as usize];
+            if *reserve_info.key != self.reserves[asset as usize] {
+                return Err(SwapError::IncorrectSwapAccount.into())
+            }
+            let reserve = Processor::unpack_token_account(
+                reserve_info,
+                Some(&self.mints[asset as usize])
+            )?;
+            reserves[asset as usize] = u128::from(reserve.amount);
+        }
+        Ok(reserves)
+    }
+
+    /// Checks the pool mint and its token program, returning its supply
+    fn unpack_pool_supply(
+        &self,
+        pool_mint_info: &AccountInfo,
+        pool_token_program_info: &AccountInfo,
+    ) -> Result<u128, ProgramError> {
+        if *pool_mint_info.key != self.pool_mint {
+            return Err(SwapError::IncorrectPoolMint.into())
+        }
+        if *pool_token_program_info.key != self.token_program_id {
+            return Err(SwapError::IncorrectTokenProgramId.into())
+        }
+        Ok(u128::from(Processor::unpack_mint(pool_mint_info)?.supply))
+    }
+
+    /// Unpacks a pool, with its authority checked
+    fn unpack_checked_pool(
+        pool_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let pool = Self::unpack_pool(pool_info, program_id)?;
+        pool.check_authority(authority_info, pool_info, program_id)?;
+        Ok(pool)
+    }
+
+    /// Unpacks a pool accepting swaps and deposits, with its authority checked
+    fn unpack_active_pool(
+        pool_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let pool = Self::unpack_checked_pool(pool_info, authority_info, program_id)?;
+        if pool.is_paused {
+            return Err(SwapError::PoolPaused.into())
+        }
+        Ok(pool)
+    }
+
+    /// Conversion rate of sUSDS at the current timestamp, failing with
+    /// `StaleRate` if the rates are older than `max_staleness`
+    fn conversion_rate(&self) -> Result<U256, ProgramError> {
+        let timestamp = Clock::get()?.unix_timestamp as u128;
+        self.rates.validate_staleness(Some(timestamp), self.max_staleness)?;
+        self.conversion_rate_at(timestamp)
+    }
+
+    /// Conversion rate of sUSDS at the current timestamp, extrapolated from
+    /// the last known rates however old they are
+    fn last_known_conversion_rate(&self) -> Result<U256, ProgramError> {
+        self.conversion_rate_at(Clock::get()?.unix_timestamp as u128)
+    }
+
+    fn conversion_rate_at(&self, timestamp: u128) -> Result<U256, ProgramError> {
+        Ok(self
+            .rates
+            .get_conversion_rate(timestamp)
+            .ok_or(SwapError::CalculationFailure)?)
+    }
+}
+
+/// Conversion rate as logged in events
+fn to_event_rate(conversion_rate: U256) -> Result<u128, ProgramError> {
+    conversion_rate
+        .try_into()
+        .map_err(|_| SwapError::ConversionFailure.into())
+}
+
+/// Decimals of the mint `mint_info`
+fn mint_decimals(mint_info: &AccountInfo) -> Result<u8, ProgramError> {
+    Ok(Processor::unpack_mint(mint_info)?.decimals)
+}
+
+/// Processes an [InitializePsm3](crate::instruction::SwapInstruction::InitializePsm3)
+pub fn process_initialize_psm3(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rates: RedemptionRateCurve,
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(accounts_info_iter)?;
+    let authority_info = next_account_info(accounts_info_iter)?;
+    let reserve_infos = [
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+    ];
+    let mint_infos = [
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+    ];
+    let pool_mint_info = next_account_info(accounts_info_iter)?;
+    let destination_info = next_account_info(accounts_info_iter)?;
+    let pool_token_program_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let super_admin_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+
+    if pool_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner)
+    }
+    if !Psm3Pool::is_psm3_pool(pool_info) {
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if Psm3Pool::unpack_unchecked(&pool_info.data.borrow())?.is_initialized {
+        return Err(SwapError::AlreadyInUse.into())
+    }
+
+    let (authority, bump_seed) =
+        Pubkey::find_program_address(&[&pool_info.key.to_bytes()], program_id);
+    if *authority_info.key != authority {
+        return Err(SwapError::InvalidProgramAddress.into())
+    }
+
+    let mut reserves = [0u128; PSM3_ASSETS];
+    let mut decimals = [0u8; PSM3_ASSETS];
+    for asset in Psm3Asset::ALL {
+        let mint_info = mint_infos[asset as usize];
+        let reserve = Processor::unpack_token_account(
+            reserve_infos[asset as usize],
+            Some(mint_info.key)
+        )?;
+
+        if reserve.owner != authority {
+            return Err(SwapError::InvalidOwner.into())
+        }
+        if reserve.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into())
+        }
+        if reserve.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into())
+        }
+
+        let mint_data = mint_info.data.borrow();
+        let mint = Processor::unpack_mint_with_extensions(&mint_data, mint_info.owner)?;
+        validate_mint_extensions(&mint, false)?;
+
+        reserves[asset as usize] = u128::from(reserve.amount);
+        decimals[asset as usize] = mint.base.decimals;
+    }
+
+    if mint_infos[0].key == mint_infos[1].key
+        || mint_infos[0].key == mint_infos[2].key
+        || mint_infos[1].key == mint_infos[2].key
+    {
+        return Err(SwapError::RepeatedMint.into())
+    }
+    // assets are valued 1:1 in base units
+    if decimals.iter().any(|d| *d != decimals[0]) {
+        return Err(SwapError::MismatchedMintDecimals.into())
+    }
+
+    let pool_mint = {
+        let pool_mint_data = pool_mint_info.data.borrow();
+        let pool_mint = Processor::unpack_mint_with_extensions(
+            &pool_mint_data,
+            pool_mint_info.owner,
+        )?;
+        validate_mint_extensions(&pool_mint, true)?;
+        pool_mint.base
+    };
+    if pool_mint.mint_authority != COption::Some(authority) {
+        return Err(SwapError::InvalidOwner.into())
+    }
+    if pool_mint.freeze_authority.is_some() {
+        return Err(SwapError::InvalidFreezeAuthority.into())
+    }
+    if pool_mint.supply != 0 {
+        return Err(SwapError::InvalidSupply.into())
+    }
+    if mint_infos.iter().any(|mint_info| mint_info.key == pool_mint_info.key) {
+        return Err(SwapError::IncorrectPoolMint.into())
+    }
+
+    // initial pool tokens are locked, like the initial supply of a swap
+    let (destination_owner, _) = Pubkey::find_program_address(
+        &[
+            b"init_destination",
+            &pool_info.key.to_bytes()
+        ],
+        program_id
+    );
+    let destination = Processor::unpack_token_account(
+        destination_info,
+        Some(pool_mint_info.key)
+    )?;
+    if destination.owner != destination_owner {
+        return Err(SwapError::InvalidOutputOwner.into())
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp as u128;
+    rates.validate(Some(timestamp))?;
+    let conversion_rate = rates
+        .get_conversion_rate(timestamp)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    // pool tokens start at one per USD of reserves
+    let initial_amount = total_value(reserves, conversion_rate)
+        .ok_or(SwapError::CalculationFailure)?;
+    if initial_amount == 0 {
+        return Err(SwapError::EmptySupply.into())
+    }
+
+    Processor::token_mint_to(
+        pool_info.key,
+        pool_token_program_info.clone(),
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        bump_seed,
+        to_u64(initial_amount)?,
+    )?;
+
+    Permission::create_permission_account(
+        program_id,
+        payer_info.clone(),
+        permission_info.clone(),
+        system_program_info.clone(),
+        pool_info.key,
+        super_admin_info.key
+    )?;
+    Permission::pack(
+        Permission {
+            is_initialized: true,
+            swap: *pool_info.key,
+            authority: *super_admin_info.key,
+            roles: roles::ALL,
+        },
+        &mut permission_info.data.borrow_mut()
+    )?;
+
+    let pool = Psm3Pool {
+        is_initialized: true,
+        bump_seed,
+        token_program_id: *pool_token_program_info.key,
+        pool_mint: *pool_mint_info.key,
+        reserves: reserve_infos.map(|info| *info.key),
+        mints: mint_infos.map(|info| *info.key),
+        rates,
+        ..Psm3Pool::default()
+    };
+    Psm3Pool::pack(pool, &mut pool_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Processes a [Psm3Swap](crate::instruction::SwapInstruction::Psm3Swap)
+pub fn process_psm3_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(accounts_info_iter)?;
+    let authority_info = next_account_info(accounts_info_iter)?;
+    let user_transfer_authority_info = next_account_info(accounts_info_iter)?;
+    let source_info = next_account_info(accounts_info_iter)?;
+    let pool_source_info = next_account_info(accounts_info_iter)?;
+    let pool_destination_info = next_account_info(accounts_info_iter)?;
+    let destination_info = next_account_info(accounts_info_iter)?;
+    let source_mint_info = next_account_info(accounts_info_iter)?;
+    let destination_mint_info = next_account_info(accounts_info_iter)?;
+    let source_token_program_info = next_account_info(accounts_info_iter)?;
+    let destination_token_program_info = next_account_info(accounts_info_iter)?;
+
+    let pool = Psm3Pool::unpack_active_pool(pool_info, authority_info, program_id)?;
+
+    let source = pool.reserve_asset(pool_source_info.key)?;
+    let destination = pool.reserve_asset(pool_destination_info.key)?;
+    if source == destination {
+        return Err(SwapError::InvalidInput.into())
+    }
+    if pool_source_info.key == source_info.key
+        || pool_destination_info.key == destination_info.key
+    {
+        return Err(SwapError::InvalidInput.into())
+    }
+    if *source_mint_info.key != pool.mints[source as usize]
+        || *destination_mint_info.key != pool.mints[destination as usize]
+    {
+        return Err(SwapError::InvalidAccountMint.into())
+    }
+
+    let conversion_rate = pool.conversion_rate()?;
+    let amount_out = swap_amount_out(
+        source,
+        destination,
+        u128::from(amount_in),
+        conversion_rate,
+    )
+    .ok_or(SwapError::CalculationFailure)?;
+    let amount_out = to_u64(amount_out)?;
+
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into())
+    }
+    if amount_out == 0 {
+        return Err(SwapError::ZeroTradingTokens.into())
+    }
+
+    Processor::token_transfer(
+        pool_info.key,
+        source_token_program_info.clone(),
+        source_info.clone(),
+        source_mint_info.clone(),
+        pool_source_info.clone(),
+        user_transfer_authority_info.clone(),
+        pool.bump_seed,
+        amount_in,
+        mint_decimals(source_mint_info)?,
+    )?;
+    Processor::token_transfer(
+        pool_info.key,
+        destination_token_program_info.clone(),
+        pool_destination_info.clone(),
+        destination_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        pool.bump_seed,
+        amount_out,
+        mint_decimals(destination_mint_info)?,
+    )?;
+
+    Event::Psm3Swap(Psm3SwapEvent {
+        pool: *pool_info.key,
+        user: *user_transfer_authority_info.key,
+        source,
+        destination,
+        amount_in,
+        amount_out,
+        conversion_rate: to_event_rate(conversion_rate)?,
+    })
+    .emit();
+
+    Ok(())
+}
+
+/// Processes a [Psm3Deposit](crate::instruction::SwapInstruction::Psm3Deposit)
+pub fn process_psm3_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    source_token_amount: u64,
+    minimum_pool_token_amount: u64,
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(accounts_info_iter)?;
+    let authority_info = next_account_info(accounts_info_iter)?;
+    let user_transfer_authority_info = next_account_info(accounts_info_iter)?;
+    let source_info = next_account_info(accounts_info_iter)?;
+    let reserve_infos = [
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+    ];
+    let pool_mint_info = next_account_info(accounts_info_iter)?;
+    let destination_info = next_account_info(accounts_info_iter)?;
+    let source_mint_info = next_account_info(accounts_info_iter)?;
+    let source_token_program_info = next_account_info(accounts_info_iter)?;
+    let pool_token_program_info = next_account_info(accounts_info_iter)?;
+
+    let pool = Psm3Pool::unpack_active_pool(pool_info, authority_info, program_id)?;
+
+    let reserves = pool.unpack_reserves(reserve_infos)?;
+    let pool_supply = pool.unpack_pool_supply(pool_mint_info, pool_token_program_info)?;
+    let asset = pool.mint_asset(source_mint_info.key)?;
+    let conversion_rate = pool.conversion_rate()?;
+
+    let value = asset
+        .value(u128::from(source_token_amount), conversion_rate, RoundDirection::Floor)
+        .ok_or(SwapError::CalculationFailure)?;
+    let pool_token_amount = value_to_pool_tokens(
+        value,
+        reserves,
+        pool_supply,
+        conversion_rate,
+        RoundDirection::Floor,
+    )
+    .ok_or(SwapError::CalculationFailure)?;
+    let pool_token_amount = to_u64(pool_token_amount)?;
+
+    if pool_token_amount < minimum_pool_token_amount {
+        return Err(SwapError::ExceededSlippage.into())
+    }
+    if pool_token_amount == 0 {
+        return Err(SwapError::ZeroTradingTokens.into())
+    }
+
+    Processor::token_transfer(
+        pool_info.key,
+        source_token_program_info.clone(),
+        source_info.clone(),
+        source_mint_info.clone(),
+        reserve_infos[asset as usize].clone(),
+        user_transfer_authority_info.clone(),
+        pool.bump_seed,
+        source_token_amount,
+        mint_decimals(source_mint_info)?,
+    )?;
+    Processor::token_mint_to(
+        pool_info.key,
+        pool_token_program_info.clone(),
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        pool.bump_seed,
+        pool_token_amount,
+    )?;
+
+    Event::Psm3Deposit(Psm3LiquidityEvent {
+        pool: *pool_info.key,
+        user: *user_transfer_authority_info.key,
+        asset,
+        asset_amount: source_token_amount,
+        pool_token_amount,
+        conversion_rate: to_event_rate(conversion_rate)?,
+    })
+    .emit();
+
+    Ok(())
+}
+
+/// Processes a [Psm3Withdraw](crate::instruction::SwapInstruction::Psm3Withdraw)
+pub fn process_psm3_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    destination_token_amount: u64,
+    maximum_pool_token_amount: u64,
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(accounts_info_iter)?;
+    let authority_info = next_account_info(accounts_info_iter)?;
+    let user_transfer_authority_info = next_account_info(accounts_info_iter)?;
+    let pool_mint_info = next_account_info(accounts_info_iter)?;
+    let source_info = next_account_info(accounts_info_iter)?;
+    let reserve_infos = [
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+        next_account_info(accounts_info_iter)?,
+    ];
+    let destination_info = next_account_info(accounts_info_iter)?;
+    let destination_mint_info = next_account_info(accounts_info_iter)?;
+    let pool_token_program_info = next_account_info(accounts_info_iter)?;
+    let destination_token_program_info = next_account_info(accounts_info_iter)?;
+
+    // withdrawals stay open while the pool is paused or its rates are stale,
+    // so liquidity providers can always exit
+    let pool = Psm3Pool::unpack_checked_pool(pool_info, authority_info, program_id)?;
+
+    let reserves = pool.unpack_reserves(reserve_infos)?;
+    let pool_supply = pool.unpack_pool_supply(pool_mint_info, pool_token_program_info)?;
+    let asset = pool.mint_asset(destination_mint_info.key)?;
+    let conversion_rate = pool.last_known_conversion_rate()?;
+
+    let value = asset
+        .value(u128::from(destination_token_amount), conversion_rate, RoundDirection::Ceiling)
+        .ok_or(SwapError::CalculationFailure)?;
+    let pool_token_amount = value_to_pool_tokens(
+        value,
+        reserves,
+        pool_supply,
+        conversion_rate,
+        RoundDirection::Ceiling,
+    )
+    .ok_or(SwapError::CalculationFailure)?;
+    let pool_token_amount = to_u64(pool_token_amount)?;
+
+    if pool_token_amount > maximum_pool_token_amount {
+        return Err(SwapError::ExceededSlippage.into())
+    }
+    if pool_token_amount == 0 {
+        return Err(SwapError::ZeroTradingTokens.into())
+    }
+
+    Processor::token_burn(
+        pool_info.key,
+        pool_token_program_info.clone(),
+        source_info.clone(),
+        pool_mint_info.clone(),
+        user_transfer_authority_info.clone(),
+        pool.bump_seed,
+        pool_token_amount,
+    )?;
+    Processor::token_transfer(
+        pool_info.key,
+        destination_token_program_info.clone(),
+        reserve_infos[asset as usize].clone(),
+        destination_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        pool.bump_seed,
+        destination_token_amount,
+        mint_decimals(destination_mint_info)?,
+    )?;
+
+    Event::Psm3Withdraw(Psm3LiquidityEvent {
+        pool: *pool_info.key,
+        user: *user_transfer_authority_info.key,
+        asset,
+        asset_amount: destination_token_amount,
+        pool_token_amount,
+        conversion_rate: to_event_rate(conversion_rate)?,
+    })
+    .emit();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, proptest::prelude::*};
+
+    // 5% more USDS per sUSDS
+    const RATE: u128 = 1_050_000_000_000_000_000_000_000_000;
+
+    #[test]
+    fn psm3_pool_pack() {
+        let pool = Psm3Pool {
+            is_initialized: true,
+            bump_seed: 254,
+            token_program_id: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            reserves: [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()],
+            mints: [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()],
+            rates: RedemptionRateCurve {
+                max_ssr: 0,
+                ssr: RAY,
+                rho: 1_700_000_000,
+                chi: RATE,
+            },
+            is_paused: true,
+            max_staleness: 3_600,
+            max_ssr_delta: 1_000,
+            min_rates_update_interval: 60,
+            last_rates_update: 1_700_000_000,
+            timelock_delay: 86_400,
+        };
+
+        let mut packed = [0u8; Psm3Pool::LEN];
+        Psm3Pool::pack(pool.clone(), &mut packed).unwrap();
+        assert_eq!(Psm3Pool::unpack(&packed).unwrap(), pool);
+
+        assert_eq!(packed[0], 1);
+        assert_eq!(packed[1], 254);
+        assert_eq!(&packed[66..98], pool.reserves[0].as_ref());
+        assert_eq!(&packed[226..258], pool.mints[2].as_ref());
+        assert_eq!(packed[322], 1);
+        assert_eq!(&packed[323..331], &3_600u64.to_le_bytes());
+        assert_eq!(&packed[331..347], &1_000u128.to_le_bytes());
+        assert_eq!(&packed[347..355], &60u64.to_le_bytes());
+        assert_eq!(&packed[355..363], &1_700_000_000u64.to_le_bytes());
+        assert_eq!(&packed[363..371], &86_400u64.to_le_bytes());
+        assert!(packed[371..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn psm3_swap_amounts() {
+        let rate = U256::from(RATE);
+
+        assert_eq!(swap_amount_out(Psm3Asset::Usdc, Psm3Asset::Usds, 1_000, rate), Some(1_000));
+        assert_eq!(swap_amount_out(Psm3Asset::Usds, Psm3Asset::Usdc, 1_000, rate), Some(1_000));
+        assert_eq!(swap_amount_out(Psm3Asset::Susds, Psm3Asset::Usdc, 1_000, rate), Some(1_050));
+        assert_eq!(swap_amount_out(Psm3Asset::Usds, Psm3Asset::Susds, 1_050, rate), Some(1_000));
+        // 1_000 / 1.05 = 952.38
+        assert_eq!(swap_amount_out(Psm3Asset::Usdc, Psm3Asset::Susds, 1_000, rate), Some(952));
+    }
+
+    #[test]
+    fn psm3_pool_tokens() {
+        let rate = U256::from(RATE);
+        let reserves = [1_000_000, 2_000_000, 1_000_000];
+        assert_eq!(total_value(reserves, rate), Some(4_050_000));
+
+        // the pool is worth 4_050_000 USD for 2_025_000 pool tokens
+        assert_eq!(
+            value_to_pool_tokens(1_001, reserves, 2_025_000, rate, RoundDirection::Floor),
+            Some(500)
+        );
+        assert_eq!(
+            value_to_pool_tokens(1_001, reserves, 2_025_000, rate, RoundDirection::Ceiling),
+            Some(501)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn psm3_round_trip_never_gains(
+            amount in 1..u64::MAX,
+            rate in RAY..(2 * RAY),
+            source in 0..PSM3_ASSETS,
+            destination in 0..PSM3_ASSETS,
+        ) {
+            let rate = U256::from(rate);
+            let source = Psm3Asset::ALL[source];
+            let destination = Psm3Asset::ALL[destination];
+
+            let amount_out = swap_amount_out(source, destination, u128::from(amount), rate).unwrap();
+            let amount_back = swap_amount_out(destination, source, amount_out, rate).unwrap();
+            prop_assert!(amount_back <= u128::from(amount));
+        }
+
+        #[test]
+        fn psm3_deposit_then_withdraw_never_gains(
+            amount in 1..u64::MAX >> 16,
+            reserves in [1..u64::MAX >> 16, 1..u64::MAX >> 16, 1..u64::MAX >> 16],
+            pool_supply in 1..u64::MAX >> 16,
+            rate in RAY..(2 * RAY),
+            asset in 0..PSM3_ASSETS,
+        ) {
+            let rate = U256::from(rate);
+            let asset = Psm3Asset::ALL[asset];
+            let mut reserves = reserves.map(u128::from);
+            let pool_supply = u128::from(pool_supply);
+
+            let value = asset.value(u128::from(amount), rate, RoundDirection::Floor).unwrap();
+            let minted = value_to_pool_tokens(value, reserves, pool_supply, rate, RoundDirection::Floor).unwrap();
+            reserves[asset as usize] += u128::from(amount);
+
+            let value = asset.value(u128::from(amount), rate, RoundDirection::Ceiling).unwrap();
+            let burned = value_to_pool_tokens(value, reserves, pool_supply + minted, rate, RoundDirection::Ceiling).unwrap();
+            prop_assert!(burned >= minted);
+        }
+    }
+}
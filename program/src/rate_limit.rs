@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Rolling-window limit on the volume swapped in one direction of a pool,
+//! capping how much value can leave the pool if a bad rate gets through.
+//! Follows the max amount / slope model of Spark's ALM controller rate limits.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+};
+
+use crate::error::SwapError;
+
+/// Volume limit of a swap direction, refilling linearly up to `max_amount`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapRateLimit {
+    /// Maximum amount of source tokens that can be swapped at once,
+    /// zero if unbounded
+    pub max_amount: u64,
+    /// Amount of source tokens the limit refills by every second
+    pub slope: u64,
+    /// Amount available at `last_updated`
+    pub last_amount: u64,
+    /// Unix timestamp of the last swap or configuration of the limit
+    pub last_updated: u64,
+}
+
+impl SwapRateLimit {
+    /// Creates a full limit, set at `timestamp`
+    pub fn new(max_amount: u64, slope: u64, timestamp: u64) -> Self {
+        Self {
+            max_amount,
+            slope,
+            last_amount: max_amount,
+            last_updated: timestamp,
+        }
+    }
+
+    /// Is the volume of the direction limited
+    pub fn is_bounded(&self) -> bool {
+        self.max_amount != 0
+    }
+
+    /// Amount that can be swapped at `timestamp`
+    pub fn current_amount(&self, timestamp: u64) -> u64 {
+        if !self.is_bounded() {
+            return u64::MAX
+        }
+
+        let elapsed = timestamp.saturating_sub(self.last_updated);
+        self.slope
+            .saturating_mul(elapsed)
+            .saturating_add(self.last_amount)
+            .min(self.max_amount)
+    }
+
+    /// Consumes `amount` of the limit at `timestamp`
+    pub fn consume(&mut self, amount: u64, timestamp: u64) -> Result<(), ProgramError> {
+        if !self.is_bounded() {
+            return Ok(())
+        }
+
+        let current_amount = self.current_amount(timestamp);
+        if amount > current_amount {
+            return Err(SwapError::SwapRateLimitExceeded.into())
+        }
+
+        self.last_amount = current_amount - amount;
+        self.last_updated = timestamp;
+
+        Ok(())
+    }
+}
+
+impl IsInitialized for SwapRateLimit {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for SwapRateLimit {}
+impl Pack for SwapRateLimit {
+    const LEN: usize = 32;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 32];
+        let (
+            max_amount,
+            slope,
+            last_amount,
+            last_updated
+        ) = mut_array_refs![output, 8, 8, 8, 8];
+        *max_amount = self.max_amount.to_le_bytes();
+        *slope = self.slope.to_le_bytes();
+        *last_amount = self.last_amount.to_le_bytes();
+        *last_updated = self.last_updated.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<SwapRateLimit, ProgramError> {
+        let input = array_ref![input, 0, 32];
+        let (
+            max_amount,
+            slope,
+            last_amount,
+            last_updated
+        ) = array_refs![input, 8, 8, 8, 8];
+        Ok(Self {
+            max_amount: u64::from_le_bytes(*max_amount),
+            slope: u64::from_le_bytes(*slope),
+            last_amount: u64::from_le_bytes(*last_amount),
+            last_updated: u64::from_le_bytes(*last_updated),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unbounded_limit() {
+        let mut limit = SwapRateLimit::default();
+        assert!(!limit.is_bounded());
+        assert_eq!(limit.current_amount(1_700_000_000), u64::MAX);
+
+        limit.consume(u64::MAX, 1_700_000_000).unwrap();
+        assert_eq!(limit, SwapRateLimit::default());
+    }
+
+    #[test]
+    fn consume_and_refill() {
+        let timestamp = 1_700_000_000;
+        let mut limit = SwapRateLimit::new(1_000, 10, timestamp);
+        assert_eq!(limit.current_amount(timestamp), 1_000);
+
+        limit.consume(600, timestamp).unwrap();
+        assert_eq!(limit.current_amount(timestamp), 400);
+
+        let err = limit.consume(401, timestamp).unwrap_err();
+        assert_eq!(err, SwapError::SwapRateLimitExceeded.into());
+
+        // refills by slope every second
+        assert_eq!(limit.current_amount(timestamp + 30), 700);
+        limit.consume(700, timestamp + 30).unwrap();
+        assert_eq!(limit.last_amount, 0);
+        assert_eq!(limit.last_updated, timestamp + 30);
+
+        // up to max_amount
+        assert_eq!(limit.current_amount(timestamp + 1_000), 1_000);
+        assert_eq!(limit.current_amount(u64::MAX), 1_000);
+
+        // no refill without slope
+        let mut limit = SwapRateLimit::new(1_000, 0, timestamp);
+        limit.consume(1_000, timestamp).unwrap();
+        assert_eq!(limit.current_amount(timestamp + 86_400), 0);
+    }
+
+    #[test]
+    fn swap_rate_limit_pack() {
+        let limit = SwapRateLimit {
+            max_amount: 1_000_000,
+            slope: 10,
+            last_amount: 500_000,
+            last_updated: 1_700_000_000,
+        };
+
+        let mut packed = [0u8; SwapRateLimit::LEN];
+        limit.pack_into_slice(&mut packed);
+
+        let mut expect = vec![];
+        expect.extend_from_slice(&limit.max_amount.to_le_bytes());
+        expect.extend_from_slice(&limit.slope.to_le_bytes());
+        expect.extend_from_slice(&limit.last_amount.to_le_bytes());
+        expect.extend_from_slice(&limit.last_updated.to_le_bytes());
+        assert_eq!(packed.to_vec(), expect);
+        assert_eq!(SwapRateLimit::unpack_from_slice(&packed).unwrap(), limit);
+    }
+}
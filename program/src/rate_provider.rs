@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Standalone account holding redemption rates shared by many pools.
+//! Pools with a `RateProviderCurve` read the rates of their rate provider at
+//! instruction time, so that a single `SetRates` reprices all of them.
+//! The rate provider has its own permission accounts, derived from its
+//! address like the permissions of a swap.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use std::{slice::Iter, sync::Arc};
+
+use crate::{
+    curve::{
+        base::{CurveType, SwapCurve},
+        calculator::CurveCalculator,
+        redemption_rate::{RateUpdateLimits, RedemptionRateCurve},
+    },
+    error::SwapError,
+    permission::{roles, Permission},
+    state::{SwapState, SwapVersion},
+};
+
+/// Rates shared by the pools referencing the account in their
+/// `RateProviderCurve`
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RateProvider {
+    /// Boolean set to true after a RateProvider is initialized
+    pub is_initialized: bool,
+    /// Rates, updated with `SetRates` and `SetMaxSsr`
+    pub rates: RedemptionRateCurve,
+    /// Program allowed to update the rates through CPI with `BridgeSetRates`,
+    /// default pubkey if none
+    pub bridge_receiver: Pubkey,
+    /// Maximum change of ssr in a single rate update, zero if unbounded
+    pub max_ssr_delta: u128,
+    /// Minimum number of seconds between two rate updates, zero if unbounded
+    pub min_rates_update_interval: u64,
+    /// Timestamp of the last rate update, zero before the first one
+    pub last_rates_update: u64,
+    /// Delay in seconds before a queued change can be executed, zero
+    /// if changes apply directly
+    pub timelock_delay: u64,
+}
+
+impl IsInitialized for RateProvider {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RateProvider {}
+
+impl Pack for RateProvider {
+    const LEN: usize = 169;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RateProvider::LEN];
+        let (
+            is_initialized,
+            rates,
+            bridge_receiver,
+            max_ssr_delta,
+            min_rates_update_interval,
+            last_rates_update,
+            timelock_delay,
+            _reserved,
+        ) = mut_array_refs![output, 1, 64, 32, 16, 8, 8, 8, 32];
+        is_initialized[0] = self.is_initialized as u8;
+        Pack::pack_into_slice(&self.rates, rates);
+        bridge_receiver.copy_from_slice(self.bridge_receiver.as_ref());
+        *max_ssr_delta = self.max_ssr_delta.to_le_bytes();
+        *min_rates_update_interval = self.min_rates_update_interval.to_le_bytes();
+        *last_rates_update = self.last_rates_update.to_le_bytes();
+        *timelock_delay = self.timelock_delay.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<RateProvider, ProgramError> {
+        let input = array_ref![input, 0, RateProvider::LEN];
+        let (
+            is_initialized,
+            rates,
+            bridge_receiver,
+            max_ssr_delta,
+            min_rates_update_interval,
+            last_rates_update,
+            timelock_delay,
+            _reserved,
+        ) = array_refs![input, 1, 64, 32, 16, 8, 8, 8, 32];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            rates: RedemptionRateCurve::unpack_from_slice(rates)?,
+            bridge_receiver: Pubkey::new_from_array(*bridge_receiver),
+            max_ssr_delta: u128::from_le_bytes(*max_ssr_delta),
+            min_rates_update_interval: u64::from_le_bytes(*min_rates_update_interval),
+            last_rates_update: u64::from_le_bytes(*last_rates_update),
+            timelock_delay: u64::from_le_bytes(*timelock_delay),
+        })
+    }
+}
+
+impl RateProvider {
+    /// Returns true if `account_info` has the size of a rate provider
+    pub fn is_rate_provider(account_info: &AccountInfo) -> bool {
+        account_info.data_len() == Self::LEN
+    }
+
+    /// Bounds on rate updates of the rate provider
+    pub fn rate_update_limits(&self) -> RateUpdateLimits {
+        RateUpdateLimits {
+            max_ssr_delta: self.max_ssr_delta,
+            min_update_interval: self.min_rates_update_interval,
+            last_update: self.last_rates_update,
+        }
+    }
+
+    /// Unpacks an initialized rate provider owned by the program
+    pub fn unpack_rate_provider(
+        rate_provider_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        if rate_provider_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+        if !Self::is_rate_provider(rate_provider_info) {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        Self::unpack(&rate_provider_info.data.borrow())
+    }
+
+    /// Returns the rates of the next account of `account_info_iter`, which
+    /// must be the rate provider `rate_provider`
+    pub fn next_rates(
+        account_info_iter: &mut Iter<AccountInfo>,
+        rate_provider: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<RedemptionRateCurve, ProgramError> {
+        let rate_provider_info = next_account_info(account_info_iter)?;
+        if rate_provider_info.key != rate_provider {
+            return Err(SwapError::IncorrectRateProvider.into())
+        }
+        Ok(Self::unpack_rate_provider(rate_provider_info, program_id)?.rates)
+    }
+}
+
+/// Unpacks a swap to price with its curve. The `RateProviderCurve` of a pool
+/// is replaced with the rates of its rate provider, which is the next account
+/// of `account_info_iter`.
+pub fn unpack_swap_with_rates(
+    program_id: &Pubkey,
+    swap_info: &AccountInfo,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> Result<Arc<dyn SwapState>, ProgramError> {
+    let mut swap = SwapVersion::unpack_version(&swap_info.data.borrow())?;
+
+    if let Some(rate_provider) = swap.swap_curve().calculator.rate_provider() {
+        let rates = RateProvider::next_rates(account_info_iter, rate_provider, program_id)?;
+        swap.set_swap_curve(SwapCurve {
+            curve_type: CurveType::RedemptionRateCurve,
+            calculator: Arc::new(rates),
+        });
+    }
+
+    Ok(Arc::new(swap))
+}
+
+/// Processes an [InitializeRateProvider](crate::instruction::SwapInstruction::InitializeRateProvider)
+pub fn process_initialize_rate_provider(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rates: RedemptionRateCurve,
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let rate_provider_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let super_admin_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+
+    if rate_provider_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner)
+    }
+    if !RateProvider::is_rate_provider(rate_provider_info) {
+        return Err(ProgramError::InvalidAccountData)
+    }
+    if RateProvider::unpack_unchecked(&rate_provider_info.data.borrow())?.is_initialized {
+        return Err(SwapError::AlreadyInUse.into())
+    }
+
+    rates.validate(Some(Clock::get()?.unix_timestamp as u128))?;
+
+    Permission::create_permission_account(
+        program_id,
+        payer_info.clone(),
+        permission_info.clone(),
+        system_program_info.clone(),
+        rate_provider_info.key,
+        super_admin_info.key
+    )?;
+    Permission::pack(
+        Permission {
+            is_initialized: true,
+            swap: *rate_provider_info.key,
+            authority: *super_admin_info.key,
+            roles: roles::ALL,
+        },
+        &mut permission_info.data.borrow_mut()
+    )?;
+
+    RateProvider::pack(
+        RateProvider {
+            is_initialized: true,
+            rates,
+            ..RateProvider::default()
+        },
+        &mut rate_provider_info.data.borrow_mut()
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::redemption_rate::RAY;
+
+    #[test]
+    fn rate_provider_pack() {
+        let rate_provider = RateProvider {
+            is_initialized: true,
+            rates: RedemptionRateCurve {
+                max_ssr: 2 * RAY,
+                ssr: RAY + 1,
+                rho: 1_700_000_000,
+                chi: RAY * 5 / 4,
+            },
+            bridge_receiver: Pubkey::new_unique(),
+            max_ssr_delta: RAY / 1_000,
+            min_rates_update_interval: 3_600,
+            last_rates_update: 1_700_000_100,
+            timelock_delay: 86_400,
+        };
+
+        let mut packed = [0u8; RateProvider::LEN];
+        RateProvider::pack(rate_provider.clone(), &mut packed).unwrap();
+        assert_eq!(packed[0], 1);
+        assert_eq!(packed[1..17], rate_provider.rates.max_ssr.to_le_bytes());
+        assert_eq!(packed[49..65], rate_provider.rates.chi.to_le_bytes());
+        assert_eq!(packed[65..97], rate_provider.bridge_receiver.to_bytes());
+        assert_eq!(packed[97..113], rate_provider.max_ssr_delta.to_le_bytes());
+        assert_eq!(packed[113..121], rate_provider.min_rates_update_interval.to_le_bytes());
+        assert_eq!(packed[121..129], rate_provider.last_rates_update.to_le_bytes());
+        assert_eq!(packed[129..137], rate_provider.timelock_delay.to_le_bytes());
+        assert!(packed[137..].iter().all(|byte| *byte == 0));
+        assert_eq!(RateProvider::unpack(&packed).unwrap(), rate_provider);
+
+        packed[0] = 2;
+        assert_eq!(
+            RateProvider::unpack_unchecked(&packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
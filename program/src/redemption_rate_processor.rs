@@ -9,6 +9,7 @@ use solana_program::{
     pubkey::Pubkey,
     program_error::ProgramError,
     account_info::{AccountInfo, next_account_info},
+    program::set_return_data,
     program_pack::Pack,
     clock::Clock,
     sysvar::Sysvar
@@ -19,10 +20,19 @@ use crate::{
         base::{CurveType, SwapCurve}, 
         calculator::CurveCalculator, 
         redemption_rate::RedemptionRateCurve
-    }, permission::Permission, state::{SwapState, SwapV1, SwapVersion}
+    }, 
+    error::SwapError, 
+    event::{Event, SetRatesEvent}, 
+    multisig::RateMultisig, 
+    permission::{roles, Permission}, 
+    psm3::Psm3Pool, 
+    rate_provider::RateProvider, 
+    state::{SwapState, SwapVersion}
 };
 
-/// Processes update
+/// Processes update of a swap, a three-asset pool or a rate provider,
+/// signed by a permission with the UPDATE_RATES role
+/// or by enough signers of the swap's rate multisig
 pub fn process_curve_update(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -32,6 +42,115 @@ pub fn process_curve_update(
 ) -> Result<(), ProgramError> {
     let accounts_info_iter = &mut accounts.iter();
 
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let authority = if RateMultisig::is_rate_multisig(permission_info) {
+        RateMultisig::validate_signers(
+            permission_info, 
+            swap_info, 
+            accounts_info_iter.as_slice(), 
+            program_id
+        )?;
+        *permission_info.key
+    } else {
+        let signer_info = next_account_info(accounts_info_iter)?;
+
+        if !signer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature)
+        }
+
+        let permission = Permission::unpack_permission(
+            permission_info, 
+            swap_info, 
+            signer_info, 
+            program_id
+        )?;
+
+        permission.validate_role(roles::UPDATE_RATES)?;
+        *signer_info.key
+    };
+
+    update_rates(swap_info, authority, ssr, rho, chi)
+}
+
+/// Updates the rates of a swap, a three-asset pool or a rate provider
+/// on behalf of `authority`, whose permission was already validated
+pub fn update_rates(
+    swap_info: &AccountInfo,
+    authority: Pubkey,
+    ssr: u128,
+    rho: u128,
+    chi: u128
+) -> Result<(), ProgramError> {
+    if Psm3Pool::is_psm3_pool(swap_info) {
+        let mut pool = Psm3Pool::unpack(&swap_info.data.borrow())?;
+        let current_timestamp = Clock::get()?.unix_timestamp as u128;
+
+        pool.rates = pool.rates.set_rates(
+            ssr,
+            rho,
+            chi,
+            current_timestamp,
+            &pool.rate_update_limits()
+        )?;
+        pool.last_rates_update = current_timestamp as u64;
+
+        Psm3Pool::pack(pool, &mut swap_info.data.borrow_mut())?;
+    } else if RateProvider::is_rate_provider(swap_info) {
+        let mut rate_provider = RateProvider::unpack(&swap_info.data.borrow())?;
+        let current_timestamp = Clock::get()?.unix_timestamp as u128;
+
+        rate_provider.rates = rate_provider.rates.set_rates(
+            ssr,
+            rho,
+            chi,
+            current_timestamp,
+            &rate_provider.rate_update_limits()
+        )?;
+        rate_provider.last_rates_update = current_timestamp as u64;
+
+        RateProvider::pack(rate_provider, &mut swap_info.data.borrow_mut())?;
+    } else {
+        let mut swap_data = swap_info.data.borrow_mut();
+        let swap = SwapVersion::unpack_version(&swap_data)?;
+        let curve = extract_curve(&swap_data)?;
+
+        let new_swap_state = create_new_swap_state(
+            ssr, 
+            rho, 
+            chi, 
+            curve, 
+            swap
+        )?;
+
+        SwapVersion::pack(new_swap_state, &mut swap_data)?;
+    }
+
+    Event::SetRates(SetRatesEvent {
+        swap: *swap_info.key,
+        authority,
+        ssr,
+        rho,
+        chi,
+    })
+    .emit();
+
+    Ok(())
+}
+
+/// Processes max_ssr update
+pub fn process_max_ssr_update(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_ssr: u128
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
     let swap_info = next_account_info(accounts_info_iter)?;
     let permission_info = next_account_info(accounts_info_iter)?;
     let signer_info = next_account_info(accounts_info_iter)?;
@@ -50,22 +169,111 @@ pub fn process_curve_update(
         signer_info, 
         program_id
     )?;
-    
-    permission.validate_update_params_permission()?;
+
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    if RateProvider::is_rate_provider(swap_info) {
+        let mut rate_provider = RateProvider::unpack(&swap_info.data.borrow())?;
+
+        if rate_provider.timelock_delay != 0 {
+            return Err(SwapError::TimelockRequired.into())
+        }
+
+        rate_provider.rates = rate_provider.rates.set_max_ssr(max_ssr)?;
+        return RateProvider::pack(rate_provider, &mut swap_info.data.borrow_mut())
+    }
+
+    if Psm3Pool::is_psm3_pool(swap_info) {
+        let mut pool = Psm3Pool::unpack(&swap_info.data.borrow())?;
+
+        if pool.timelock_delay != 0 {
+            return Err(SwapError::TimelockRequired.into())
+        }
+
+        pool.rates = pool.rates.set_max_ssr(max_ssr)?;
+        return Psm3Pool::pack(pool, &mut swap_info.data.borrow_mut())
+    }
 
     let mut swap_data = swap_info.data.borrow_mut();
-    let swap = SwapVersion::unpack(&swap_data)?;
-    let curve = extract_curve(&swap_data)?;
+    let swap = SwapVersion::unpack_version(&swap_data)?;
 
-    let new_swap_state = create_new_swap_state(
-        ssr, 
-        rho, 
-        chi, 
-        curve, 
+    if swap.timelock_delay() != 0 {
+        return Err(SwapError::TimelockRequired.into())
+    }
+
+    set_max_ssr(&mut swap_data, max_ssr)
+}
+
+/// Sets max_ssr of the RedemptionRateCurve stored in `swap_data`
+pub fn set_max_ssr(
+    swap_data: &mut [u8],
+    max_ssr: u128
+) -> Result<(), ProgramError> {
+    let swap = SwapVersion::unpack_version(swap_data)?;
+    let curve = extract_curve(swap_data)?;
+
+    let new_swap_state = replace_calculator(
+        curve.set_max_ssr(max_ssr)?,
         swap
-    )?;
+    );
 
-    SwapVersion::pack(new_swap_state, &mut swap_data)?;
+    SwapVersion::pack(new_swap_state, swap_data)?;
+
+    Ok(())
+}
+
+/// Processes returning the conversion rate of the curve at the current
+/// timestamp or at a later `timestamp`
+pub fn process_get_conversion_rate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    timestamp: Option<u64>
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    // rate providers have no staleness bound of their own
+    let (curve, max_staleness) = if RateProvider::is_rate_provider(swap_info) {
+        (RateProvider::unpack_rate_provider(swap_info, program_id)?.rates, 0)
+    } else {
+        let swap_data = swap_info.data.borrow();
+        let swap = SwapVersion::unpack(&swap_data)?;
+
+        let curve = match swap.swap_curve().calculator.rate_provider() {
+            Some(rate_provider) => {
+                RateProvider::next_rates(accounts_info_iter, rate_provider, program_id)?
+            },
+            None if swap.swap_curve().curve_type == CurveType::RedemptionRateCurve => {
+                extract_curve(&swap_data)?
+            },
+            None => return Err(SwapError::UnsupportedCurveOperation.into()),
+        };
+        (curve, swap.max_staleness())
+    };
+    let current_timestamp = Clock::get()?.unix_timestamp as u128;
+
+    curve.validate_staleness(Some(current_timestamp), max_staleness)?;
+
+    let timestamp = match timestamp {
+        Some(timestamp) if u128::from(timestamp) < current_timestamp => {
+            return Err(SwapError::InvalidInput.into())
+        },
+        Some(timestamp) => u128::from(timestamp),
+        None => current_timestamp,
+    };
+
+    let rate: u128 = curve
+        .get_conversion_rate(timestamp)
+        .ok_or(SwapError::CalculationFailure)?
+        .try_into()
+        .map_err(|_| SwapError::ConversionFailure)?;
+
+    set_return_data(&rate.to_le_bytes());
 
     Ok(())
 }
@@ -75,7 +283,7 @@ fn create_new_swap_state(
     rho: u128,
     chi: u128,
     curve: RedemptionRateCurve,
-    swap: Arc<dyn SwapState>,
+    mut swap: SwapVersion,
 ) -> Result<SwapVersion, ProgramError> {
 
     let current_timestamp = Clock::get()?.unix_timestamp as u128;
@@ -84,36 +292,38 @@ fn create_new_swap_state(
         ssr, 
         rho,
         chi, 
-        current_timestamp
+        current_timestamp,
+        &swap.rate_update_limits()
     )?;
 
-    let new_swap = SwapVersion::SwapV1(SwapV1 {
-        is_initialized: swap.is_initialized(),
-        bump_seed: swap.bump_seed(),
-        token_program_id: swap.token_program_id().clone(),
-        token_a: swap.token_a_account().clone(),
-        token_b: swap.token_b_account().clone(),
-        pool_mint: swap.pool_mint().clone(),
-        token_a_mint: swap.token_a_mint().clone(),
-        token_b_mint: swap.token_b_mint().clone(),
-        pool_fee_account: swap.pool_fee_account().clone(),
-        fees: swap.fees().clone(),
-        swap_curve: SwapCurve {
-            curve_type: CurveType::RedemptionRateCurve,
-            calculator: Arc::new(new_calculator) as Arc<dyn CurveCalculator + Send + Sync>,
-        },
-    });
+    // only swaps with pool controls keep track of rate updates
+    if let Ok(pool_config) = swap.pool_config_mut() {
+        pool_config.last_rates_update = current_timestamp as u64;
+    }
+
+    Ok(replace_calculator(new_calculator, swap))
 
-    Ok(new_swap)
+}
+
+fn replace_calculator(
+    calculator: RedemptionRateCurve,
+    mut swap: SwapVersion,
+) -> SwapVersion {
+    swap.set_swap_curve(SwapCurve {
+        curve_type: CurveType::RedemptionRateCurve,
+        calculator: Arc::new(calculator) as Arc<dyn CurveCalculator + Send + Sync>,
+    });
 
+    swap
 }
 
 
 fn extract_curve(
     input: &[u8]
 ) -> Result<RedemptionRateCurve, ProgramError> {
-    // equal to SwapVersion::LATEST_LEN - SwapCurve::LEN , SwapCurve::LEN
-    let input = array_ref![input, SwapVersion::LATEST_LEN - SwapCurve::LEN, SwapCurve::LEN];
+    // the curve is packed right after the fees in every version,
+    // which is the end of a SwapV1 account
+    let input = array_ref![input, SwapVersion::V1_LEN - SwapCurve::LEN, SwapCurve::LEN];
 
     let (curve_type, calculator) = array_refs![input, 1, RedemptionRateCurve::LEN];
 
@@ -139,7 +349,7 @@ mod tests {
 
     #[test]
     fn test_swap_v1_curve_offset() {
-        // requires that SwapCurve is packed last in SwapVersion
-        assert_eq!(SwapVersion::LATEST_LEN - SwapCurve::LEN, 291);
+        // requires that SwapCurve is packed at the same offset in all versions
+        assert_eq!(SwapVersion::V1_LEN - SwapCurve::LEN, 291);
     }
 }
\ No newline at end of file
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Permissionless relay of rates signed off-chain by a keeper.
+//!
+//! A keeper with the UPDATE_RATES role signs `(swap, ssr, rho, chi, nonce)`,
+//! and anyone can submit the update with `RelaySignedRates`, right after an
+//! Ed25519 program instruction verifying the signature. The nonces relayed for
+//! a keeper must increase, so that a signed update can't be replayed.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    ed25519_program,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::{
+    error::SwapError,
+    instruction::RelaySignedRates,
+    permission::{roles, Permission},
+    redemption_rate_processor::update_rates,
+    utils::create_pda_account,
+    ID as PROGRAM_ID,
+};
+
+/// Size of the message signed by keepers
+pub const SIGNED_RATES_MESSAGE_LEN: usize = 88;
+
+/// Start of the signature offsets in the data of an Ed25519 program
+/// instruction, after the number of signatures and a padding byte
+const ED25519_OFFSETS_START: usize = 2;
+
+/// Size of the offsets of a signature in an Ed25519 program instruction
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// Last nonce relayed for a keeper on a swap
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RelayNonce {
+    /// Boolean set to true after a RelayNonce is created
+    pub is_initialized: bool,
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// The keeper signing the relayed rates
+    pub authority: Pubkey,
+    /// Last relayed nonce, the next one must be greater
+    pub nonce: u64,
+}
+
+impl IsInitialized for RelayNonce {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RelayNonce {}
+
+impl Pack for RelayNonce {
+    const LEN: usize = 73;
+
+    fn unpack_from_slice(input: &[u8]) -> Result<RelayNonce, ProgramError> {
+        let input = array_ref![input, 0, RelayNonce::LEN];
+        let (is_initialized, swap, authority, nonce) = array_refs![input, 1, 32, 32, 8];
+
+        Ok(Self {
+            is_initialized: is_initialized[0] != 0,
+            swap: Pubkey::new_from_array(*swap),
+            authority: Pubkey::new_from_array(*authority),
+            nonce: u64::from_le_bytes(*nonce),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RelayNonce::LEN];
+        let (is_initialized, swap, authority, nonce) = mut_array_refs![output, 1, 32, 32, 8];
+
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        authority.copy_from_slice(self.authority.as_ref());
+        *nonce = self.nonce.to_le_bytes();
+    }
+}
+
+impl RelayNonce {
+    /// Seed for PDA
+    pub const RELAY_NONCE_SEED: &'static [u8] = b"relay_nonce";
+
+    /// Derives the relay nonce account of a keeper on a swap
+    pub fn derive_relay_nonce_pubkey_and_bump(
+        swap: &Pubkey,
+        authority: &Pubkey
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::RELAY_NONCE_SEED,
+                &swap.to_bytes(),
+                &authority.to_bytes()
+            ],
+            &PROGRAM_ID
+        )
+    }
+}
+
+/// Returns the message signed by a keeper to relay `rates` to `swap`:
+/// the swap address followed by ssr, rho, chi and nonce in little-endian
+pub fn signed_rates_message(
+    swap: &Pubkey,
+    rates: &RelaySignedRates
+) -> [u8; SIGNED_RATES_MESSAGE_LEN] {
+    let mut message = [0u8; SIGNED_RATES_MESSAGE_LEN];
+    let (swap_dst, ssr, rho, chi, nonce) = mut_array_refs![&mut message, 32, 16, 16, 16, 8];
+
+    swap_dst.copy_from_slice(swap.as_ref());
+    *ssr = rates.ssr.to_le_bytes();
+    *rho = rates.rho.to_le_bytes();
+    *chi = rates.chi.to_le_bytes();
+    *nonce = rates.nonce.to_le_bytes();
+
+    message
+}
+
+/// Returns the public key whose signature of `message` was verified by the
+/// Ed25519 program instruction right before the current instruction
+fn ed25519_signer(
+    instructions_sysvar_info: &AccountInfo,
+    message: &[u8]
+) -> Result<Pubkey, ProgramError> {
+    let current_index = load_current_index_checked(instructions_sysvar_info)?;
+    let ed25519_index = current_index
+        .checked_sub(1)
+        .ok_or(SwapError::InvalidSignedRates)?;
+    let instruction = load_instruction_at_checked(
+        ed25519_index as usize,
+        instructions_sysvar_info
+    )?;
+
+    if instruction.program_id != ed25519_program::ID {
+        return Err(SwapError::InvalidSignedRates.into())
+    }
+
+    let data = instruction.data.as_slice();
+    let offsets = data
+        .get(ED25519_OFFSETS_START..ED25519_OFFSETS_START + ED25519_OFFSETS_LEN)
+        .ok_or(SwapError::InvalidSignedRates)?;
+    if data[0] != 1 {
+        return Err(SwapError::InvalidSignedRates.into())
+    }
+
+    let offsets = array_ref![offsets, 0, ED25519_OFFSETS_LEN];
+    let (
+        _signature_offset,
+        signature_instruction_index,
+        public_key_offset,
+        public_key_instruction_index,
+        message_offset,
+        message_size,
+        message_instruction_index
+    ) = array_refs![offsets, 2, 2, 2, 2, 2, 2, 2];
+
+    // the verified data must be read from the Ed25519 instruction itself,
+    // other instructions of the transaction aren't checked here
+    let in_ed25519_instruction = [
+        signature_instruction_index,
+        public_key_instruction_index,
+        message_instruction_index
+    ]
+    .iter()
+    .all(|index| u16::from_le_bytes(**index) == u16::MAX);
+    if !in_ed25519_instruction {
+        return Err(SwapError::InvalidSignedRates.into())
+    }
+
+    let read = |offset: &[u8; 2], len: usize| {
+        let start = u16::from_le_bytes(*offset) as usize;
+        data.get(start..start.checked_add(len)?)
+    };
+
+    let public_key = read(public_key_offset, 32)
+        .ok_or(SwapError::InvalidSignedRates)?;
+    let signed_message = read(message_offset, u16::from_le_bytes(*message_size) as usize)
+        .ok_or(SwapError::InvalidSignedRates)?;
+
+    if signed_message != message {
+        return Err(SwapError::InvalidSignedRates.into())
+    }
+
+    Pubkey::try_from(public_key).map_err(|_| SwapError::InvalidSignedRates.into())
+}
+
+/// Processes a [RelaySignedRates](crate::instruction::SwapInstruction::RelaySignedRates)
+pub fn process_relay_signed_rates(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rates: RelaySignedRates
+) -> ProgramResult {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let relay_nonce_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+    let instructions_sysvar_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let authority = ed25519_signer(
+        instructions_sysvar_info,
+        &signed_rates_message(swap_info.key, &rates)
+    )?;
+
+    let permission = Permission::unpack_authority_permission(
+        permission_info,
+        swap_info,
+        &authority,
+        program_id
+    )?;
+
+    permission.validate_role(roles::UPDATE_RATES)?;
+
+    let (
+        relay_nonce_address,
+        relay_nonce_bump
+    ) = RelayNonce::derive_relay_nonce_pubkey_and_bump(swap_info.key, &authority);
+
+    if *relay_nonce_info.key != relay_nonce_address {
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    // the relay nonce account is created by the first relayed update
+    let mut relay_nonce = if relay_nonce_info.owner == program_id {
+        RelayNonce::unpack(&relay_nonce_info.data.borrow())?
+    } else {
+        create_pda_account(
+            payer_info.clone(),
+            relay_nonce_info.clone(),
+            system_program_info.clone(),
+            RelayNonce::LEN,
+            &[&[
+                RelayNonce::RELAY_NONCE_SEED,
+                swap_info.key.as_ref(),
+                authority.as_ref(),
+                &[relay_nonce_bump]
+            ]]
+        )?;
+
+        RelayNonce {
+            is_initialized: true,
+            swap: *swap_info.key,
+            authority,
+            nonce: 0,
+        }
+    };
+
+    if rates.nonce <= relay_nonce.nonce {
+        return Err(SwapError::NonceAlreadyUsed.into())
+    }
+    relay_nonce.nonce = rates.nonce;
+
+    RelayNonce::pack(relay_nonce, &mut relay_nonce_info.data.borrow_mut())?;
+
+    update_rates(swap_info, authority, rates.ssr, rates.rho, rates.chi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_rates_message_layout() {
+        let swap = Pubkey::new_unique();
+        let rates = RelaySignedRates {
+            ssr: 1_000_000_001_547_125_957_863_212_448,
+            rho: 1_700_000_000,
+            chi: 1_050_000_000_000_000_000_000_000_000,
+            nonce: 7,
+        };
+
+        let message = signed_rates_message(&swap, &rates);
+        assert_eq!(message[..32], swap.to_bytes());
+        assert_eq!(message[32..48], rates.ssr.to_le_bytes());
+        assert_eq!(message[48..64], rates.rho.to_le_bytes());
+        assert_eq!(message[64..80], rates.chi.to_le_bytes());
+        assert_eq!(message[80..], rates.nonce.to_le_bytes());
+    }
+
+    #[test]
+    fn relay_nonce_pack() {
+        let relay_nonce = RelayNonce {
+            is_initialized: true,
+            swap: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            nonce: u64::MAX - 1,
+        };
+
+        let mut packed = [0u8; RelayNonce::LEN];
+        RelayNonce::pack(relay_nonce.clone(), &mut packed).unwrap();
+        assert_eq!(RelayNonce::unpack(&packed).unwrap(), relay_nonce);
+        assert_eq!(packed[65..], relay_nonce.nonce.to_le_bytes());
+    }
+}
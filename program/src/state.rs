@@ -4,8 +4,9 @@
 
 use {
     crate::{
-        curve::{base::{CurveType, SwapCurve}, fees::Fees},
+        curve::{base::{CurveType, SwapCurve}, calculator::TradeDirection, fees::Fees, redemption_rate::RateUpdateLimits},
         error::SwapError,
+        rate_limit::SwapRateLimit,
     },
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     enum_dispatch::enum_dispatch,
@@ -57,13 +58,37 @@ pub trait SwapState {
     fn swap_curve(&self) -> &SwapCurve;
     /// Gets current_timestamp option, only needed for RedemptionRateCurve
     fn get_current_timestamp_opt(&self) -> Result<Option<u128>, ProgramError>;
+
+    /// Is the pool paused, blocking swaps and deposits
+    fn is_paused(&self) -> bool;
+    /// Are conversions in the given direction allowed
+    fn is_direction_enabled(&self, trade_direction: TradeDirection) -> bool;
+    /// Do deposits and withdrawals require an allowlisted user
+    fn is_liquidity_allowlisted(&self) -> bool;
+    /// Do swaps require an allowlisted user
+    fn is_swaps_allowlisted(&self) -> bool;
+    /// Maximum age in seconds of the curve's rate before pricing is rejected,
+    /// zero if disabled
+    fn max_staleness(&self) -> u64;
+    /// Minimum number of seconds between queueing a sensitive change
+    /// and executing it, zero if changes apply immediately
+    fn timelock_delay(&self) -> u64;
+    /// Bounds on rate updates of the curve, unbounded by default
+    fn rate_update_limits(&self) -> RateUpdateLimits;
+    /// Volume limit of swaps in the given direction, unbounded by default
+    fn swap_rate_limit(&self, trade_direction: TradeDirection) -> SwapRateLimit;
+    /// Maximum normalized value of the pool after a deposit, zero if unbounded
+    fn max_tvl(&self) -> u128;
 }
 
 /// All versions of SwapState
 #[enum_dispatch(SwapState)]
+#[allow(clippy::large_enum_variant)]
 pub enum SwapVersion {
-    /// Latest version, used for all new swaps
+    /// Original version, without pool controls
     SwapV1,
+    /// Latest version, used for all new swaps
+    SwapV2,
 }
 
 /// SwapVersion does not implement program_pack::Pack because there are size
@@ -71,7 +96,10 @@ pub enum SwapVersion {
 /// special implementations are provided here
 impl SwapVersion {
     /// Size of the latest version of the SwapState
-    pub const LATEST_LEN: usize = 1 + SwapV1::LEN; // add one for the version enum
+    pub const LATEST_LEN: usize = 1 + SwapV2::LEN; // add one for the version enum
+
+    /// Size of swap accounts created with `SwapV1`
+    pub const V1_LEN: usize = 1 + SwapV1::LEN;
 
     /// Pack a swap into a byte array, based on its version
     pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
@@ -81,6 +109,11 @@ impl SwapVersion {
                 SwapV1::pack(swap_info, &mut dst[1..])?;
                 Ok(())
             }
+            Self::SwapV2(swap_info) => {
+                dst[0] = 2;
+                SwapV2::pack(swap_info, &mut dst[1..])?;
+                Ok(())
+            }
         }
     }
 
@@ -92,10 +125,66 @@ impl SwapVersion {
             .ok_or(ProgramError::InvalidAccountData)?;
         match version {
             1 => Ok(Arc::new(SwapV1::unpack(rest)?)),
+            2 => Ok(Arc::new(SwapV2::unpack(rest)?)),
             _ => Err(ProgramError::UninitializedAccount),
         }
     }
 
+    /// Unpack the swap account based on its version, keeping the concrete
+    /// version so that it can be modified and packed back in place
+    pub fn unpack_version(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&version, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        match version {
+            1 => Ok(Self::SwapV1(SwapV1::unpack(rest)?)),
+            2 => Ok(Self::SwapV2(SwapV2::unpack(rest)?)),
+            _ => Err(ProgramError::UninitializedAccount),
+        }
+    }
+
+    /// Replaces the swap curve, keeping the version of the swap
+    pub fn set_swap_curve(&mut self, swap_curve: SwapCurve) {
+        match self {
+            Self::SwapV1(swap_info) => swap_info.swap_curve = swap_curve,
+            Self::SwapV2(swap_info) => swap_info.swap_curve = swap_curve,
+        }
+    }
+
+    /// Replaces the fees, keeping the version of the swap
+    pub fn set_fees(&mut self, fees: Fees) {
+        match self {
+            Self::SwapV1(swap_info) => swap_info.fees = fees,
+            Self::SwapV2(swap_info) => swap_info.fees = fees,
+        }
+    }
+
+    /// Replaces the pool fee account, keeping the version of the swap
+    pub fn set_pool_fee_account(&mut self, pool_fee_account: Pubkey) {
+        match self {
+            Self::SwapV1(swap_info) => swap_info.pool_fee_account = pool_fee_account,
+            Self::SwapV2(swap_info) => swap_info.pool_fee_account = pool_fee_account,
+        }
+    }
+
+    /// Access to the pool controls, which only exist from `SwapV2`.
+    /// Older swaps have to be migrated with `MigrateSwap` first.
+    pub fn pool_config(&self) -> Result<&PoolConfig, ProgramError> {
+        match self {
+            Self::SwapV1(_) => Err(SwapError::SwapMigrationRequired.into()),
+            Self::SwapV2(swap_info) => Ok(&swap_info.pool_config),
+        }
+    }
+
+    /// Mutable access to the pool controls, which only exist from `SwapV2`.
+    /// Older swaps have to be migrated with `MigrateSwap` first.
+    pub fn pool_config_mut(&mut self) -> Result<&mut PoolConfig, ProgramError> {
+        match self {
+            Self::SwapV1(_) => Err(SwapError::SwapMigrationRequired.into()),
+            Self::SwapV2(swap_info) => Ok(&mut swap_info.pool_config),
+        }
+    }
+
     /// Returns `true` if the second byte (initialized-flag) is non-zero.
     /// Offsets are identical for `SwapV1` and future versions.
     #[inline]
@@ -104,6 +193,41 @@ impl SwapVersion {
     }
 }
 
+/// Check if the pool fee info is a valid token program account capable of
+/// receiving tokens from the pool mint
+fn check_pool_fee_info(
+    pool_fee_info: &AccountInfo,
+    token_program_id: &Pubkey,
+    pool_mint: &Pubkey,
+) -> Result<(), ProgramError> {
+    let data = &pool_fee_info.data.borrow();
+    let token_account =
+        StateWithExtensions::<Account>::unpack(data).map_err(|err| match err {
+            ProgramError::InvalidAccountData | ProgramError::UninitializedAccount => {
+                SwapError::InvalidFeeAccount.into()
+            }
+            _ => err,
+        })?;
+    if pool_fee_info.owner != token_program_id
+        || token_account.base.state != AccountState::Initialized
+        || token_account.base.mint != *pool_mint
+    {
+        msg!("Pool fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
+        return Err(SwapError::InvalidFeeAccount.into());
+    }
+    Ok(())
+}
+
+/// Gets current_timestamp option, only needed for redemption rate curves
+fn current_timestamp_opt(curve_type: CurveType) -> Result<Option<u128>, ProgramError> {
+    Ok(match curve_type {
+        CurveType::RedemptionRateCurve | CurveType::RateProviderCurve => {
+            Some(Clock::get()?.unix_timestamp as u128)
+        }
+        _ => None
+    })
+}
+
 /// Program states.
 #[repr(C)]
 #[derive(Debug, Default, PartialEq)]
@@ -183,22 +307,7 @@ impl SwapState for SwapV1 {
     }
 
     fn check_pool_fee_info(&self, pool_fee_info: &AccountInfo) -> Result<(), ProgramError> {
-        let data = &pool_fee_info.data.borrow();
-        let token_account =
-            StateWithExtensions::<Account>::unpack(data).map_err(|err| match err {
-                ProgramError::InvalidAccountData | ProgramError::UninitializedAccount => {
-                    SwapError::InvalidFeeAccount.into()
-                }
-                _ => err,
-            })?;
-        if pool_fee_info.owner != &self.token_program_id
-            || token_account.base.state != AccountState::Initialized
-            || token_account.base.mint != self.pool_mint
-        {
-            msg!("Pool fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
-            return Err(SwapError::InvalidFeeAccount.into());
-        }
-        Ok(())
+        check_pool_fee_info(pool_fee_info, &self.token_program_id, &self.pool_mint)
     }
 
     fn fees(&self) -> &Fees {
@@ -210,10 +319,43 @@ impl SwapState for SwapV1 {
     }
 
     fn get_current_timestamp_opt(&self) -> Result<Option<u128>, ProgramError> {
-        Ok(match self.swap_curve.curve_type {
-            CurveType::RedemptionRateCurve => Some(Clock::get()?.unix_timestamp as u128),
-            _ => None
-        })
+        current_timestamp_opt(self.swap_curve.curve_type)
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn is_direction_enabled(&self, _trade_direction: TradeDirection) -> bool {
+        true
+    }
+
+    fn is_liquidity_allowlisted(&self) -> bool {
+        false
+    }
+
+    fn is_swaps_allowlisted(&self) -> bool {
+        false
+    }
+
+    fn max_staleness(&self) -> u64 {
+        0
+    }
+
+    fn timelock_delay(&self) -> u64 {
+        0
+    }
+
+    fn rate_update_limits(&self) -> RateUpdateLimits {
+        RateUpdateLimits::default()
+    }
+
+    fn swap_rate_limit(&self, _trade_direction: TradeDirection) -> SwapRateLimit {
+        SwapRateLimit::default()
+    }
+
+    fn max_tvl(&self) -> u128 {
+        0
     }
 }
 
@@ -292,6 +434,422 @@ impl Pack for SwapV1 {
     }
 }
 
+/// Swap state with pool-level controls, which are managed through
+/// permissioned instructions
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV2 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Bump seed used in program address.
+    pub bump_seed: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Pool-level controls
+    pub pool_config: PoolConfig,
+}
+
+impl From<SwapV1> for SwapV2 {
+    fn from(swap_info: SwapV1) -> Self {
+        Self {
+            is_initialized: swap_info.is_initialized,
+            bump_seed: swap_info.bump_seed,
+            token_program_id: swap_info.token_program_id,
+            token_a: swap_info.token_a,
+            token_b: swap_info.token_b,
+            pool_mint: swap_info.pool_mint,
+            token_a_mint: swap_info.token_a_mint,
+            token_b_mint: swap_info.token_b_mint,
+            pool_fee_account: swap_info.pool_fee_account,
+            fees: swap_info.fees,
+            swap_curve: swap_info.swap_curve,
+            pool_config: PoolConfig::default(),
+        }
+    }
+}
+
+impl SwapState for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn bump_seed(&self) -> u8 {
+        self.bump_seed
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn check_pool_fee_info(&self, pool_fee_info: &AccountInfo) -> Result<(), ProgramError> {
+        check_pool_fee_info(pool_fee_info, &self.token_program_id, &self.pool_mint)
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn get_current_timestamp_opt(&self) -> Result<Option<u128>, ProgramError> {
+        current_timestamp_opt(self.swap_curve.curve_type)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.pool_config.is_paused
+    }
+
+    fn is_direction_enabled(&self, trade_direction: TradeDirection) -> bool {
+        match trade_direction {
+            TradeDirection::AtoB => !self.pool_config.a_to_b_disabled,
+            TradeDirection::BtoA => !self.pool_config.b_to_a_disabled,
+        }
+    }
+
+    fn is_liquidity_allowlisted(&self) -> bool {
+        self.pool_config.liquidity_allowlisted
+    }
+
+    fn is_swaps_allowlisted(&self) -> bool {
+        self.pool_config.swaps_allowlisted
+    }
+
+    fn max_staleness(&self) -> u64 {
+        self.pool_config.max_staleness
+    }
+
+    fn timelock_delay(&self) -> u64 {
+        self.pool_config.timelock_delay
+    }
+
+    fn rate_update_limits(&self) -> RateUpdateLimits {
+        RateUpdateLimits {
+            max_ssr_delta: self.pool_config.max_ssr_delta,
+            min_update_interval: self.pool_config.min_rates_update_interval,
+            last_update: self.pool_config.last_rates_update,
+        }
+    }
+
+    fn swap_rate_limit(&self, trade_direction: TradeDirection) -> SwapRateLimit {
+        *self.pool_config.swap_rate_limit(trade_direction)
+    }
+
+    fn max_tvl(&self) -> u128 {
+        self.pool_config.max_tvl
+    }
+}
+
+impl Sealed for SwapV2 {}
+impl IsInitialized for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV2 {
+    const LEN: usize = 611;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 611];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            pool_config,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 65, 256];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        self.pool_config.pack_into_slice(&mut pool_config[..]);
+    }
+
+    /// Unpacks a byte buffer into a [SwapV2](struct.SwapV2.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 611];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            pool_config,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 65, 256];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            pool_config: PoolConfig::unpack_from_slice(pool_config)?,
+        })
+    }
+}
+
+/// Pool-level controls stored at the end of the swap state.
+/// Unused bytes are reserved so that new controls can be added without
+/// reallocating swap accounts again.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoolConfig {
+    /// Swaps and deposits are rejected while the pool is paused, only
+    /// withdrawals of both token types remain available
+    pub is_paused: bool,
+    /// Maximum number of seconds since the redemption rate was last updated
+    /// before rate-based swaps and single-sided deposits / withdrawals are
+    /// rejected, zero disables the check
+    pub max_staleness: u64,
+    /// Authority nominated by a super admin to take over the pool, it
+    /// becomes super admin once it accepts. Default pubkey if none
+    pub pending_super_admin: Pubkey,
+    /// Super admin that nominated `pending_super_admin`, its permission
+    /// is revoked on acceptance
+    pub super_admin_proposer: Pubkey,
+    /// Minimum number of seconds between queueing a sensitive change and
+    /// executing it, zero if changes can be applied immediately
+    pub timelock_delay: u64,
+    /// Maximum change of the ssr in a single rate update, zero if unbounded
+    pub max_ssr_delta: u128,
+    /// Minimum number of seconds between two rate updates, zero if unbounded
+    pub min_rates_update_interval: u64,
+    /// Unix timestamp of the last rate update
+    pub last_rates_update: u64,
+    /// Swaps from token A to token B are rejected, as well as the
+    /// single-sided deposits and withdrawals converting the same way
+    pub a_to_b_disabled: bool,
+    /// Swaps from token B to token A are rejected, as well as the
+    /// single-sided deposits and withdrawals converting the same way
+    pub b_to_a_disabled: bool,
+    /// Deposits and withdrawals require an allowlist entry of the user
+    pub liquidity_allowlisted: bool,
+    /// Swaps require an allowlist entry of the user
+    pub swaps_allowlisted: bool,
+    /// Volume limit of swaps from token A to token B
+    pub a_to_b_rate_limit: SwapRateLimit,
+    /// Volume limit of swaps from token B to token A
+    pub b_to_a_rate_limit: SwapRateLimit,
+    /// Maximum value of the pool reserves after a deposit, both reserves
+    /// valued in token A, zero if unbounded
+    pub max_tvl: u128,
+    /// Program allowed to update the rates through CPI with `BridgeSetRates`,
+    /// default pubkey if none
+    pub bridge_receiver: Pubkey,
+}
+
+impl PoolConfig {
+    /// Volume limit of swaps in the given direction
+    pub fn swap_rate_limit(&self, trade_direction: TradeDirection) -> &SwapRateLimit {
+        match trade_direction {
+            TradeDirection::AtoB => &self.a_to_b_rate_limit,
+            TradeDirection::BtoA => &self.b_to_a_rate_limit,
+        }
+    }
+
+    /// Mutable volume limit of swaps in the given direction
+    pub fn swap_rate_limit_mut(&mut self, trade_direction: TradeDirection) -> &mut SwapRateLimit {
+        match trade_direction {
+            TradeDirection::AtoB => &mut self.a_to_b_rate_limit,
+            TradeDirection::BtoA => &mut self.b_to_a_rate_limit,
+        }
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for PoolConfig {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for PoolConfig {}
+impl Pack for PoolConfig {
+    const LEN: usize = 256;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 256];
+        let (
+            is_paused, 
+            max_staleness, 
+            pending_super_admin, 
+            super_admin_proposer, 
+            timelock_delay, 
+            max_ssr_delta, 
+            min_rates_update_interval, 
+            last_rates_update, 
+            a_to_b_disabled, 
+            b_to_a_disabled, 
+            liquidity_allowlisted, 
+            swaps_allowlisted, 
+            a_to_b_rate_limit, 
+            b_to_a_rate_limit, 
+            max_tvl, 
+            bridge_receiver, 
+            _reserved
+        ) = mut_array_refs![output, 1, 8, 32, 32, 8, 16, 8, 8, 1, 1, 1, 1, 32, 32, 16, 32, 27];
+        is_paused[0] = self.is_paused as u8;
+        *max_staleness = self.max_staleness.to_le_bytes();
+        pending_super_admin.copy_from_slice(self.pending_super_admin.as_ref());
+        super_admin_proposer.copy_from_slice(self.super_admin_proposer.as_ref());
+        *timelock_delay = self.timelock_delay.to_le_bytes();
+        *max_ssr_delta = self.max_ssr_delta.to_le_bytes();
+        *min_rates_update_interval = self.min_rates_update_interval.to_le_bytes();
+        *last_rates_update = self.last_rates_update.to_le_bytes();
+        a_to_b_disabled[0] = self.a_to_b_disabled as u8;
+        b_to_a_disabled[0] = self.b_to_a_disabled as u8;
+        liquidity_allowlisted[0] = self.liquidity_allowlisted as u8;
+        swaps_allowlisted[0] = self.swaps_allowlisted as u8;
+        self.a_to_b_rate_limit.pack_into_slice(a_to_b_rate_limit);
+        self.b_to_a_rate_limit.pack_into_slice(b_to_a_rate_limit);
+        *max_tvl = self.max_tvl.to_le_bytes();
+        bridge_receiver.copy_from_slice(self.bridge_receiver.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<PoolConfig, ProgramError> {
+        let input = array_ref![input, 0, 256];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_paused, 
+            max_staleness, 
+            pending_super_admin, 
+            super_admin_proposer, 
+            timelock_delay, 
+            max_ssr_delta, 
+            min_rates_update_interval, 
+            last_rates_update, 
+            a_to_b_disabled, 
+            b_to_a_disabled, 
+            liquidity_allowlisted, 
+            swaps_allowlisted, 
+            a_to_b_rate_limit, 
+            b_to_a_rate_limit, 
+            max_tvl, 
+            bridge_receiver, 
+            _reserved
+        ) = array_refs![input, 1, 8, 32, 32, 8, 16, 8, 8, 1, 1, 1, 1, 32, 32, 16, 32, 27];
+        Ok(Self {
+            is_paused: match is_paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            max_staleness: u64::from_le_bytes(*max_staleness),
+            pending_super_admin: Pubkey::new_from_array(*pending_super_admin),
+            super_admin_proposer: Pubkey::new_from_array(*super_admin_proposer),
+            timelock_delay: u64::from_le_bytes(*timelock_delay),
+            max_ssr_delta: u128::from_le_bytes(*max_ssr_delta),
+            min_rates_update_interval: u64::from_le_bytes(*min_rates_update_interval),
+            last_rates_update: u64::from_le_bytes(*last_rates_update),
+            a_to_b_disabled: match a_to_b_disabled {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            b_to_a_disabled: match b_to_a_disabled {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            liquidity_allowlisted: match liquidity_allowlisted {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swaps_allowlisted: match swaps_allowlisted {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            a_to_b_rate_limit: SwapRateLimit::unpack_from_slice(a_to_b_rate_limit)?,
+            b_to_a_rate_limit: SwapRateLimit::unpack_from_slice(b_to_a_rate_limit)?,
+            max_tvl: u128::from_le_bytes(*max_tvl),
+            bridge_receiver: Pubkey::new_from_array(*bridge_receiver),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::curve::{constant_price::ConstantPriceCurve, offset::OffsetCurve, redemption_rate::RAY}, std::convert::TryInto};
@@ -349,7 +907,7 @@ mod tests {
             swap_curve: swap_curve.clone(),
         });
 
-        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        let mut packed = [0u8; SwapVersion::V1_LEN];
         SwapVersion::pack(swap_info, &mut packed).unwrap();
         let unpacked = SwapVersion::unpack(&packed).unwrap();
 
@@ -388,7 +946,7 @@ mod tests {
             swap_curve: swap_curve.clone(),
         });
 
-        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        let mut packed = [0u8; SwapVersion::V1_LEN];
         SwapVersion::pack(swap_info, &mut packed).unwrap();
         let unpacked = SwapVersion::unpack(&packed).unwrap();
 
@@ -524,4 +1082,176 @@ mod tests {
         let err = SwapV1::unpack(&packed).unwrap_err();
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
+
+    #[test]
+    fn swap_version_pack_v2() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapVersion::SwapV2(SwapV2 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            pool_config: PoolConfig {
+                is_paused: true,
+                max_staleness: 3600,
+                ..PoolConfig::default()
+            },
+        });
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(swap_info, &mut packed).unwrap();
+        assert_eq!(packed[0], 2);
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
+        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
+        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
+        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
+        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+        assert!(unpacked.is_paused());
+        assert_eq!(unpacked.max_staleness(), 3600);
+
+        // V1 and V2 share the same prefix, so the curve is at the same offset
+        let mut packed_v1 = [0u8; SwapVersion::V1_LEN];
+        let swap_v1 = SwapV1 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+        };
+        SwapVersion::pack(SwapVersion::SwapV1(swap_v1), &mut packed_v1).unwrap();
+        assert_eq!(packed_v1[1..], packed[1..SwapVersion::V1_LEN]);
+    }
+
+    #[test]
+    fn swap_version_unpack_version() {
+        let swap_curve = SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Arc::new(TEST_CURVE),
+        };
+        let swap_v1 = SwapV1 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+        };
+
+        let mut packed = [0u8; SwapVersion::V1_LEN];
+        SwapVersion::pack(SwapVersion::SwapV1(swap_v1), &mut packed).unwrap();
+        let mut swap = SwapVersion::unpack_version(&packed).unwrap();
+        assert!(!swap.is_paused());
+        assert_eq!(swap.max_staleness(), 0);
+        assert_eq!(
+            swap.pool_config_mut().unwrap_err(),
+            SwapError::SwapMigrationRequired.into()
+        );
+
+        let swap_v2 = match swap {
+            SwapVersion::SwapV1(swap_v1) => SwapV2::from(swap_v1),
+            SwapVersion::SwapV2(_) => panic!("expected SwapV1"),
+        };
+        assert_eq!(swap_v2.pool_config, PoolConfig::default());
+        assert_eq!(swap_v2.swap_curve, swap_curve);
+
+        let mut swap = SwapVersion::SwapV2(swap_v2);
+        swap.pool_config_mut().unwrap().is_paused = true;
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(swap, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert!(unpacked.is_paused());
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+    }
+
+    #[test]
+    fn pack_pool_config() {
+        let max_staleness = 86_400u64;
+        let pending_super_admin = Pubkey::new_from_array([8u8; 32]);
+        let super_admin_proposer = Pubkey::new_from_array([9u8; 32]);
+        let timelock_delay = 172_800u64;
+        let max_ssr_delta = 1_000_000_000u128;
+        let min_rates_update_interval = 3_600u64;
+        let last_rates_update = 1_700_000_000u64;
+        let a_to_b_rate_limit = SwapRateLimit::new(1_000_000, 10, last_rates_update);
+        let max_tvl = 5_000_000_000u128;
+        let bridge_receiver = Pubkey::new_from_array([10u8; 32]);
+        let pool_config = PoolConfig {
+            is_paused: true,
+            max_staleness,
+            pending_super_admin,
+            super_admin_proposer,
+            timelock_delay,
+            max_ssr_delta,
+            min_rates_update_interval,
+            last_rates_update,
+            a_to_b_disabled: false,
+            b_to_a_disabled: true,
+            liquidity_allowlisted: true,
+            swaps_allowlisted: false,
+            a_to_b_rate_limit,
+            b_to_a_rate_limit: SwapRateLimit::default(),
+            max_tvl,
+            bridge_receiver,
+        };
+
+        let mut packed = [0u8; PoolConfig::LEN];
+        Pack::pack_into_slice(&pool_config, &mut packed[..]);
+        let unpacked = PoolConfig::unpack_from_slice(&packed).unwrap();
+        assert_eq!(pool_config, unpacked);
+
+        let mut packed = vec![1u8];
+        packed.extend_from_slice(&max_staleness.to_le_bytes());
+        packed.extend_from_slice(pending_super_admin.as_ref());
+        packed.extend_from_slice(super_admin_proposer.as_ref());
+        packed.extend_from_slice(&timelock_delay.to_le_bytes());
+        packed.extend_from_slice(&max_ssr_delta.to_le_bytes());
+        packed.extend_from_slice(&min_rates_update_interval.to_le_bytes());
+        packed.extend_from_slice(&last_rates_update.to_le_bytes());
+        packed.extend_from_slice(&[0, 1, 1, 0]);
+        packed.extend_from_slice(&a_to_b_rate_limit.max_amount.to_le_bytes());
+        packed.extend_from_slice(&a_to_b_rate_limit.slope.to_le_bytes());
+        packed.extend_from_slice(&a_to_b_rate_limit.last_amount.to_le_bytes());
+        packed.extend_from_slice(&a_to_b_rate_limit.last_updated.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 32]);
+        packed.extend_from_slice(&max_tvl.to_le_bytes());
+        packed.extend_from_slice(bridge_receiver.as_ref());
+        packed.extend_from_slice(&[0u8; 27]);
+        let unpacked = PoolConfig::unpack_from_slice(&packed).unwrap();
+        assert_eq!(pool_config, unpacked);
+
+        packed[0] = 2;
+        let err = PoolConfig::unpack_from_slice(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
 }
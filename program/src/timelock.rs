@@ -0,0 +1,649 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Timelock for sensitive pool changes, which are queued in their own
+//! account and applied once the pool's timelock delay has elapsed.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    admin_processor::{validate_fee_account, validate_fees},
+    constraints::SwapConstraints,
+    curve::fees::Fees,
+    error::SwapError,
+    permission::{roles, Permission},
+    psm3::Psm3Pool,
+    rate_limit::SwapRateLimit,
+    rate_provider::RateProvider,
+    redemption_rate_processor::set_max_ssr,
+    state::{SwapState, SwapVersion},
+    utils::{close_account, create_pda_account},
+    ID as PROGRAM_ID,
+};
+
+/// A change that has to go through the timelock when the pool has a delay
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterChange {
+    /// New max_ssr of the RedemptionRateCurve, zero to remove the bound
+    MaxSsr(u128),
+    /// New pool fees
+    Fees(Fees),
+    /// New pool token account receiving the fees
+    FeeAccount(Pubkey),
+    /// New timelock delay of the pool
+    TimelockDelay(u64),
+    /// New bounds on rate updates of the pool
+    RateUpdateLimits {
+        /// maximum change of the ssr in a single update, zero if unbounded
+        max_ssr_delta: u128,
+        /// minimum seconds between two updates, zero if unbounded
+        min_update_interval: u64,
+    },
+    /// New swap volume limits of the pool
+    SwapRateLimits {
+        /// maximum amount of token A swapped to token B at once, zero if unbounded
+        a_to_b_max_amount: u64,
+        /// amount of token A the A to B limit refills by every second
+        a_to_b_slope: u64,
+        /// maximum amount of token B swapped to token A at once, zero if unbounded
+        b_to_a_max_amount: u64,
+        /// amount of token B the B to A limit refills by every second
+        b_to_a_slope: u64,
+    },
+    /// New bridge receiver program of the pool, default pubkey to remove it
+    BridgeReceiver(Pubkey),
+    /// New maximum staleness of the pool's rate, zero to disable the check
+    MaxStaleness(u64),
+}
+
+impl ParameterChange {
+    /// Size of a packed change, the kind followed by the largest value
+    pub const LEN: usize = 1 + Fees::LEN;
+
+    /// Kind of the change, each kind can only be queued once at a time
+    pub fn kind(&self) -> u8 {
+        match self {
+            Self::MaxSsr(_) => 0,
+            Self::Fees(_) => 1,
+            Self::FeeAccount(_) => 2,
+            Self::TimelockDelay(_) => 3,
+            Self::RateUpdateLimits { .. } => 4,
+            Self::SwapRateLimits { .. } => 5,
+            Self::BridgeReceiver(_) => 6,
+            Self::MaxStaleness(_) => 7,
+        }
+    }
+
+    /// Role required to queue the change, the same as to apply it directly
+    pub fn required_role(&self) -> u64 {
+        match self {
+            Self::MaxSsr(_) => roles::SUPER_ADMIN,
+            Self::Fees(_) => roles::UPDATE_FEES,
+            Self::FeeAccount(_) => roles::UPDATE_FEE_ACCOUNT,
+            Self::TimelockDelay(_) => roles::SUPER_ADMIN,
+            Self::RateUpdateLimits { .. } => roles::SUPER_ADMIN,
+            Self::SwapRateLimits { .. } => roles::SUPER_ADMIN,
+            Self::BridgeReceiver(_) => roles::SUPER_ADMIN,
+            Self::MaxStaleness(_) => roles::SUPER_ADMIN,
+        }
+    }
+
+    /// Returns true if the change can be queued on a rate provider, which
+    /// only holds rates and the controls of their updates
+    pub fn applies_to_rate_provider(&self) -> bool {
+        matches!(
+            self,
+            Self::MaxSsr(_)
+                | Self::TimelockDelay(_)
+                | Self::RateUpdateLimits { .. }
+                | Self::BridgeReceiver(_)
+        )
+    }
+
+    /// Returns true if the change can be queued on a three-asset pool, which
+    /// has no fees, fee account, swap volume limits or bridge receiver
+    pub fn applies_to_psm3_pool(&self) -> bool {
+        matches!(
+            self,
+            Self::MaxSsr(_)
+                | Self::TimelockDelay(_)
+                | Self::RateUpdateLimits { .. }
+                | Self::MaxStaleness(_)
+        )
+    }
+
+    /// Unpacks a change, `input` has to hold at least `ParameterChange::LEN` bytes
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let (kind, value) = input.split_at(1);
+
+        Ok(match kind[0] {
+            0 => Self::MaxSsr(u128::from_le_bytes(*array_ref![value, 0, 16])),
+            1 => Self::Fees(Fees::unpack_from_slice(value)?),
+            2 => Self::FeeAccount(Pubkey::new_from_array(*array_ref![value, 0, 32])),
+            3 => Self::TimelockDelay(u64::from_le_bytes(*array_ref![value, 0, 8])),
+            4 => Self::RateUpdateLimits {
+                max_ssr_delta: u128::from_le_bytes(*array_ref![value, 0, 16]),
+                min_update_interval: u64::from_le_bytes(*array_ref![value, 16, 8]),
+            },
+            5 => Self::SwapRateLimits {
+                a_to_b_max_amount: u64::from_le_bytes(*array_ref![value, 0, 8]),
+                a_to_b_slope: u64::from_le_bytes(*array_ref![value, 8, 8]),
+                b_to_a_max_amount: u64::from_le_bytes(*array_ref![value, 16, 8]),
+                b_to_a_slope: u64::from_le_bytes(*array_ref![value, 24, 8]),
+            },
+            6 => Self::BridgeReceiver(Pubkey::new_from_array(*array_ref![value, 0, 32])),
+            7 => Self::MaxStaleness(u64::from_le_bytes(*array_ref![value, 0, 8])),
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+
+    /// Packs a change into `ParameterChange::LEN` bytes
+    pub fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, ParameterChange::LEN];
+        let (kind, value) = mut_array_refs![output, 1, Fees::LEN];
+
+        kind[0] = self.kind();
+        value.fill(0);
+        match self {
+            Self::MaxSsr(max_ssr) => {
+                value[..16].copy_from_slice(&max_ssr.to_le_bytes());
+            }
+            Self::Fees(fees) => {
+                fees.pack_into_slice(value);
+            }
+            Self::FeeAccount(fee_account) => {
+                value[..32].copy_from_slice(fee_account.as_ref());
+            }
+            Self::TimelockDelay(timelock_delay) => {
+                value[..8].copy_from_slice(&timelock_delay.to_le_bytes());
+            }
+            Self::RateUpdateLimits { max_ssr_delta, min_update_interval } => {
+                value[..16].copy_from_slice(&max_ssr_delta.to_le_bytes());
+                value[16..24].copy_from_slice(&min_update_interval.to_le_bytes());
+            }
+            Self::SwapRateLimits {
+                a_to_b_max_amount,
+                a_to_b_slope,
+                b_to_a_max_amount,
+                b_to_a_slope,
+            } => {
+                value[..8].copy_from_slice(&a_to_b_max_amount.to_le_bytes());
+                value[8..16].copy_from_slice(&a_to_b_slope.to_le_bytes());
+                value[16..24].copy_from_slice(&b_to_a_max_amount.to_le_bytes());
+                value[24..32].copy_from_slice(&b_to_a_slope.to_le_bytes());
+            }
+            Self::BridgeReceiver(bridge_receiver) => {
+                value[..32].copy_from_slice(bridge_receiver.as_ref());
+            }
+            Self::MaxStaleness(max_staleness) => {
+                value[..8].copy_from_slice(&max_staleness.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Account holding a queued change of a pool until it is executed or cancelled
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedChange {
+    /// Boolean set to true after a QueuedChange is created
+    pub is_initialized: bool,
+    /// The Swap account address
+    pub swap: Pubkey,
+    /// Payer of the account, refunded when it is closed
+    pub payer: Pubkey,
+    /// Unix timestamp from which the change can be executed
+    pub eta: i64,
+    /// The queued change
+    pub change: ParameterChange,
+}
+
+impl IsInitialized for QueuedChange {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for QueuedChange {}
+
+impl Pack for QueuedChange {
+    const LEN: usize = 138;
+
+    fn unpack_from_slice(input: &[u8]) -> Result<QueuedChange, ProgramError> {
+        let input = array_ref![input, 0, QueuedChange::LEN];
+        let (
+            is_initialized,
+            swap,
+            payer,
+            eta,
+            change
+        ) = array_refs![input, 1, 32, 32, 8, ParameterChange::LEN];
+
+        Ok(Self {
+            is_initialized: is_initialized[0] != 0,
+            swap: Pubkey::new_from_array(*swap),
+            payer: Pubkey::new_from_array(*payer),
+            eta: i64::from_le_bytes(*eta),
+            change: ParameterChange::unpack(change)?,
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, QueuedChange::LEN];
+        let (
+            is_initialized,
+            swap,
+            payer,
+            eta,
+            change
+        ) = mut_array_refs![output, 1, 32, 32, 8, ParameterChange::LEN];
+
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        payer.copy_from_slice(self.payer.as_ref());
+        *eta = self.eta.to_le_bytes();
+        self.change.pack_into_slice(change);
+    }
+}
+
+impl QueuedChange {
+    /// Seed for PDA
+    pub const QUEUED_CHANGE_SEED: &'static [u8] = b"queued_change";
+
+    /// Derives the queued change account of a swap for a kind of change
+    pub fn derive_queued_change_pubkey_and_bump(
+        swap: &Pubkey,
+        kind: u8
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::QUEUED_CHANGE_SEED,
+                &swap.to_bytes(),
+                &[kind]
+            ],
+            &PROGRAM_ID
+        )
+    }
+
+    /// Unpacks a queued change and checks that it belongs to the swap
+    fn unpack_queued_change(
+        queued_change_info: &AccountInfo,
+        swap_info: &AccountInfo,
+        program_id: &Pubkey
+    ) -> Result<Self, ProgramError> {
+        if queued_change_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner)
+        }
+
+        let queued_change = Self::unpack(&queued_change_info.data.borrow())?;
+
+        if queued_change.swap != *swap_info.key {
+            return Err(ProgramError::InvalidArgument)
+        }
+
+        Ok(queued_change)
+    }
+}
+
+/// Processes queueing a change, executable once the timelock delay
+/// of the pool has elapsed.
+/// Signer must have the role needed to apply the change directly.
+pub fn process_queue_change(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    change: ParameterChange,
+    swap_constraints: &Option<SwapConstraints>
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let queued_change_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+    let system_program_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info,
+        swap_info,
+        signer_info,
+        program_id
+    )?;
+
+    permission.validate_role(change.required_role())?;
+
+    if let ParameterChange::Fees(fees) = &change {
+        validate_fees(fees, swap_constraints)?;
+    }
+
+    let timelock_delay = if RateProvider::is_rate_provider(swap_info) {
+        if !change.applies_to_rate_provider() {
+            return Err(SwapError::InvalidInput.into())
+        }
+
+        RateProvider::unpack_rate_provider(swap_info, program_id)?.timelock_delay
+    } else if Psm3Pool::is_psm3_pool(swap_info) {
+        if !change.applies_to_psm3_pool() {
+            return Err(SwapError::InvalidInput.into())
+        }
+
+        Psm3Pool::unpack_pool(swap_info, program_id)?.timelock_delay
+    } else {
+        SwapVersion::unpack_version(&swap_info.data.borrow())?.timelock_delay()
+    };
+    let timelock_delay: i64 = timelock_delay
+        .try_into()
+        .map_err(|_| SwapError::ConversionFailure)?;
+    let eta = Clock::get()?
+        .unix_timestamp
+        .checked_add(timelock_delay)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    let (
+        queued_change_address,
+        queued_change_bump
+    ) = QueuedChange::derive_queued_change_pubkey_and_bump(swap_info.key, change.kind());
+
+    if *queued_change_info.key != queued_change_address {
+        return Err(ProgramError::InvalidSeeds)
+    }
+
+    // A change of the same kind is already queued, it has to be
+    // executed or cancelled first
+    if queued_change_info.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized)
+    }
+
+    create_pda_account(
+        payer_info.clone(),
+        queued_change_info.clone(),
+        system_program_info.clone(),
+        QueuedChange::LEN,
+        &[&[
+            QueuedChange::QUEUED_CHANGE_SEED,
+            swap_info.key.as_ref(),
+            &[change.kind()],
+            &[queued_change_bump]
+        ]]
+    )?;
+
+    let queued_change = QueuedChange {
+        is_initialized: true,
+        swap: *swap_info.key,
+        payer: *payer_info.key,
+        eta,
+        change,
+    };
+
+    QueuedChange::pack(queued_change, &mut queued_change_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Processes executing a queued change once its eta has passed.
+/// Anyone can execute it, the account rent goes back to its payer.
+pub fn process_execute_change(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    swap_constraints: &Option<SwapConstraints>
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let queued_change_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    let queued_change = QueuedChange::unpack_queued_change(
+        queued_change_info,
+        swap_info,
+        program_id
+    )?;
+
+    if queued_change.payer != *payer_info.key {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    if Clock::get()?.unix_timestamp < queued_change.eta {
+        return Err(SwapError::TimelockNotElapsed.into())
+    }
+
+    if RateProvider::is_rate_provider(swap_info) {
+        let mut rate_provider = RateProvider::unpack_rate_provider(swap_info, program_id)?;
+
+        match queued_change.change {
+            ParameterChange::MaxSsr(max_ssr) => {
+                rate_provider.rates = rate_provider.rates.set_max_ssr(max_ssr)?;
+            }
+            ParameterChange::TimelockDelay(timelock_delay) => {
+                rate_provider.timelock_delay = timelock_delay;
+            }
+            ParameterChange::RateUpdateLimits { max_ssr_delta, min_update_interval } => {
+                rate_provider.max_ssr_delta = max_ssr_delta;
+                rate_provider.min_rates_update_interval = min_update_interval;
+            }
+            ParameterChange::BridgeReceiver(bridge_receiver) => {
+                rate_provider.bridge_receiver = bridge_receiver;
+            }
+            _ => return Err(SwapError::InvalidInput.into()),
+        }
+
+        RateProvider::pack(rate_provider, &mut swap_info.data.borrow_mut())?;
+
+        return close_account(queued_change_info, payer_info)
+    }
+
+    if Psm3Pool::is_psm3_pool(swap_info) {
+        let mut pool = Psm3Pool::unpack_pool(swap_info, program_id)?;
+
+        match queued_change.change {
+            ParameterChange::MaxSsr(max_ssr) => {
+                pool.rates = pool.rates.set_max_ssr(max_ssr)?;
+            }
+            ParameterChange::TimelockDelay(timelock_delay) => {
+                pool.timelock_delay = timelock_delay;
+            }
+            ParameterChange::RateUpdateLimits { max_ssr_delta, min_update_interval } => {
+                pool.max_ssr_delta = max_ssr_delta;
+                pool.min_rates_update_interval = min_update_interval;
+            }
+            ParameterChange::MaxStaleness(max_staleness) => {
+                pool.max_staleness = max_staleness;
+            }
+            _ => return Err(SwapError::InvalidInput.into()),
+        }
+
+        Psm3Pool::pack(pool, &mut swap_info.data.borrow_mut())?;
+
+        return close_account(queued_change_info, payer_info)
+    }
+
+    let mut swap_data = swap_info.data.borrow_mut();
+
+    match queued_change.change {
+        ParameterChange::MaxSsr(max_ssr) => {
+            set_max_ssr(&mut swap_data, max_ssr)?;
+        }
+        ParameterChange::Fees(fees) => {
+            validate_fees(&fees, swap_constraints)?;
+
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            swap.set_fees(fees);
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+        ParameterChange::FeeAccount(fee_account) => {
+            let fee_account_info = next_account_info(accounts_info_iter)?;
+
+            if *fee_account_info.key != fee_account {
+                return Err(SwapError::IncorrectFeeAccount.into())
+            }
+
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            validate_fee_account(
+                program_id,
+                swap_info,
+                &swap,
+                fee_account_info,
+                swap_constraints
+            )?;
+            swap.set_pool_fee_account(fee_account);
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+        ParameterChange::TimelockDelay(timelock_delay) => {
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            swap.pool_config_mut()?.timelock_delay = timelock_delay;
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+        ParameterChange::RateUpdateLimits { max_ssr_delta, min_update_interval } => {
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            let pool_config = swap.pool_config_mut()?;
+            pool_config.max_ssr_delta = max_ssr_delta;
+            pool_config.min_rates_update_interval = min_update_interval;
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+        ParameterChange::SwapRateLimits {
+            a_to_b_max_amount,
+            a_to_b_slope,
+            b_to_a_max_amount,
+            b_to_a_slope,
+        } => {
+            let timestamp = Clock::get()?.unix_timestamp as u64;
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            let pool_config = swap.pool_config_mut()?;
+            pool_config.a_to_b_rate_limit = SwapRateLimit::new(a_to_b_max_amount, a_to_b_slope, timestamp);
+            pool_config.b_to_a_rate_limit = SwapRateLimit::new(b_to_a_max_amount, b_to_a_slope, timestamp);
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+        ParameterChange::BridgeReceiver(bridge_receiver) => {
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            swap.pool_config_mut()?.bridge_receiver = bridge_receiver;
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+        ParameterChange::MaxStaleness(max_staleness) => {
+            let mut swap = SwapVersion::unpack_version(&swap_data)?;
+            swap.pool_config_mut()?.max_staleness = max_staleness;
+            SwapVersion::pack(swap, &mut swap_data)?;
+        }
+    }
+
+    close_account(queued_change_info, payer_info)
+}
+
+/// Processes cancelling a queued change, the account rent goes back to its payer.
+/// Signer must have a super_admin associated permission account.
+pub fn process_cancel_change(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let accounts_info_iter = &mut accounts.iter();
+
+    let swap_info = next_account_info(accounts_info_iter)?;
+    let permission_info = next_account_info(accounts_info_iter)?;
+    let queued_change_info = next_account_info(accounts_info_iter)?;
+    let signer_info = next_account_info(accounts_info_iter)?;
+    let payer_info = next_account_info(accounts_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner)
+    }
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+
+    let permission = Permission::unpack_permission(
+        permission_info,
+        swap_info,
+        signer_info,
+        program_id
+    )?;
+
+    permission.validate_role(roles::SUPER_ADMIN)?;
+
+    let queued_change = QueuedChange::unpack_queued_change(
+        queued_change_info,
+        swap_info,
+        program_id
+    )?;
+
+    if queued_change.payer != *payer_info.key {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    close_account(queued_change_info, payer_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queued_change_pack() {
+        let changes = [
+            ParameterChange::MaxSsr(u128::MAX),
+            ParameterChange::Fees(Fees {
+                trade_fee_numerator: 1,
+                trade_fee_denominator: 10_000,
+                ..Fees::default()
+            }),
+            ParameterChange::FeeAccount(Pubkey::new_from_array([3u8; 32])),
+            ParameterChange::TimelockDelay(86_400),
+            ParameterChange::RateUpdateLimits {
+                max_ssr_delta: 1_000_000_000,
+                min_update_interval: 3_600,
+            },
+            ParameterChange::SwapRateLimits {
+                a_to_b_max_amount: 1_000_000,
+                a_to_b_slope: 10,
+                b_to_a_max_amount: u64::MAX,
+                b_to_a_slope: 0,
+            },
+            ParameterChange::BridgeReceiver(Pubkey::new_from_array([4u8; 32])),
+            ParameterChange::MaxStaleness(3_600),
+        ];
+
+        for (kind, change) in changes.into_iter().enumerate() {
+            assert_eq!(change.kind(), kind as u8);
+
+            let queued_change = QueuedChange {
+                is_initialized: true,
+                swap: Pubkey::new_from_array([1u8; 32]),
+                payer: Pubkey::new_from_array([2u8; 32]),
+                eta: 1_700_000_000,
+                change,
+            };
+
+            let mut packed = [0u8; QueuedChange::LEN];
+            QueuedChange::pack(queued_change.clone(), &mut packed).unwrap();
+            assert_eq!(QueuedChange::unpack(&packed).unwrap(), queued_change);
+        }
+
+        let mut packed = [0u8; ParameterChange::LEN];
+        packed[0] = 8;
+        assert_eq!(
+            ParameterChange::unpack(&packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
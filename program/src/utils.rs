@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Helpers for program owned accounts.
+
+use solana_program::{
+    account_info::AccountInfo,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    rent::Rent,
+    system_instruction::{allocate, assign, create_account, transfer},
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::Sysvar,
+};
+
+use crate::ID as PROGRAM_ID;
+
+/// Creates a rent exempt PDA of `space` bytes owned by the program,
+/// `signers_seeds` being the seeds of `account`.
+/// Also works when lamports were already sent to the address.
+pub fn create_pda_account<'a>(
+    payer: AccountInfo<'a>,
+    account: AccountInfo<'a>,
+    system_program: AccountInfo<'a>,
+    space: usize,
+    signers_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    if *system_program.key != SYSTEM_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+
+    let rent = Rent::get()?;
+    let lamports = rent.minimum_balance(space);
+
+    let current_lamports = account.lamports();
+
+    // Account has no lamports, normal account creation
+    if current_lamports == 0 {
+        let ix = create_account(
+            payer.key,
+            account.key,
+            lamports,
+            space as u64,
+            &PROGRAM_ID
+        );
+
+        invoke_signed(
+            &ix,
+            &[
+                payer,
+                account,
+                system_program
+            ],
+            signers_seeds
+        )?;
+
+        return Ok(())
+    }
+
+    // Account has a balance, so we have to:
+    // 1. Transfer required lamports for rent exempt (if needed)
+    // 2. Allocate space for the account
+    // 3. Assign to current program
+
+    let required_lamports = lamports.max(1)
+        .saturating_sub(current_lamports);
+
+    if required_lamports > 0 {
+        let ix = transfer(
+            payer.key,
+            account.key,
+            required_lamports
+        );
+
+        invoke(
+            &ix,
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program.clone()
+            ]
+        )?;
+    }
+
+    let allocate_ix = allocate(
+        account.key,
+        space as u64
+    );
+
+    invoke_signed(
+        &allocate_ix,
+        &[
+            account.clone(),
+            system_program.clone()
+        ],
+        signers_seeds
+    )?;
+
+    let assign_ix = assign(
+        account.key,
+        &PROGRAM_ID
+    );
+
+    invoke_signed(
+        &assign_ix,
+        &[
+            account,
+            system_program
+        ],
+        signers_seeds
+    )?;
+
+    Ok(())
+}
+
+/// Closes a program owned account, sending its lamports to `recipient_info`.
+/// The account is handed back to the system program so that the same
+/// address can be created again.
+pub fn close_account(
+    account_info: &AccountInfo,
+    recipient_info: &AccountInfo,
+) -> Result<(), ProgramError> {
+    if recipient_info.key == account_info.key {
+        return Err(ProgramError::InvalidArgument)
+    }
+
+    let lamports = account_info.lamports();
+    **account_info.lamports.borrow_mut() = 0;
+    **recipient_info.lamports.borrow_mut() = recipient_info
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    account_info.data.borrow_mut().fill(0);
+    account_info.realloc(0, false)?;
+    account_info.assign(&SYSTEM_PROGRAM_ID);
+
+    Ok(())
+}
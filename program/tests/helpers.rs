@@ -11,12 +11,24 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use solana_program_test::{
+    processor, 
     ProgramTest, 
     ProgramTestContext
 };
-use solana_program::pubkey;
+use solana_program::{
+    account_info::AccountInfo, 
+    entrypoint::ProgramResult, 
+    program::invoke_signed, 
+    program_error::ProgramError, 
+    pubkey
+};
 use solana_psm::{
-    curve::redemption_rate::RedemptionRateCurve, permission::Permission, state::SwapVersion
+    bridge::{derive_bridge_authority_pubkey_and_bump, BRIDGE_AUTHORITY_SEED}, 
+    curve::redemption_rate::RedemptionRateCurve, 
+    instruction::{bridge_set_rates, SwapInstruction}, 
+    permission::Permission, 
+    rate_provider::RateProvider, 
+    state::SwapVersion
 };
 use spl_token::{
     state::{Mint, Account as TokenAccount},
@@ -26,7 +38,7 @@ use spl_token::{
 
 pub const PROGRAM_ID: Pubkey = pubkey!("5B9vCSSga3qXgHca5Liy3WAQqC2HaB3sBsyjfkH47uYv");
 
-pub async fn program_test_context() -> ProgramTestContext {
+fn program_test() -> ProgramTest {
     let mut program_test = ProgramTest::default();
 
     program_test.add_program(
@@ -35,9 +47,59 @@ pub async fn program_test_context() -> ProgramTestContext {
         None
     );
 
+    program_test
+}
+
+pub async fn program_test_context() -> ProgramTestContext {
+    program_test().start_with_context().await
+}
+
+/// Context with the mock bridge receiver deployed at every given program id
+pub async fn program_test_context_with_bridge_receivers(
+    bridge_receivers: &[Pubkey]
+) -> ProgramTestContext {
+    let mut program_test = program_test();
+
+    for bridge_receiver in bridge_receivers {
+        program_test.add_program(
+            "mock_bridge_receiver", 
+            *bridge_receiver, 
+            processor!(mock_bridge_receiver)
+        );
+    }
+
     program_test.start_with_context().await
 }
 
+/// Mock bridge receiver, forwarding its `BridgeSetRates` instruction data
+/// through CPI signed by its bridge authority.
+/// Accounts are the swap, the bridge authority, the instructions sysvar
+/// and the PSM program.
+fn mock_bridge_receiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8]
+) -> ProgramResult {
+    let rates = match SwapInstruction::unpack(input)? {
+        SwapInstruction::BridgeSetRates(rates) => rates,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let (bridge_authority, bump) = derive_bridge_authority_pubkey_and_bump(program_id);
+    let ix = bridge_set_rates(
+        &PROGRAM_ID, 
+        accounts[0].key, 
+        &bridge_authority, 
+        rates
+    )?;
+
+    invoke_signed(
+        &ix, 
+        accounts, 
+        &[&[BRIDGE_AUTHORITY_SEED, &[bump]]]
+    )
+}
+
 pub fn get_permission_pda(
     swap_info: &Pubkey,
     permission_authority: &Pubkey
@@ -169,12 +231,24 @@ pub async fn fetch_permission(
         .unwrap()
         .unwrap();
 
-    let permission = Permission::unpack(&account.data)
+    let permission = Permission::unpack_account_data(&account.data)
         .unwrap();
 
     permission
 }
 
+pub async fn fetch_rate_provider(
+    banks_client: &mut BanksClient,
+    rate_provider: &Pubkey
+) -> RateProvider {
+    let account = banks_client.get_account(*rate_provider)
+        .await
+        .unwrap()
+        .unwrap();
+
+    RateProvider::unpack(&account.data).unwrap()
+}
+
 pub async fn fetch_redemption_rate_curve(
     banks_client: &mut BanksClient,
     swap_info: &Pubkey
@@ -194,7 +268,7 @@ pub async fn fetch_redemption_rate_curve(
     ).unwrap()
 }
 
-async fn create_token_account(
+pub async fn create_token_account(
     banks_client: &mut BanksClient,
     last_blockhash: Hash,
     payer: &Keypair,
@@ -263,7 +337,7 @@ async fn create_swap_account(
     keypair.pubkey()
 }
 
-async fn create_mint(
+pub async fn create_mint(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     last_blockhash: Hash,
@@ -304,7 +378,7 @@ async fn create_mint(
 }
 
 
-async fn mint_to_token_account(
+pub async fn mint_to_token_account(
     banks_client: &mut BanksClient,
     token_program_id: &Pubkey,
     mint: &Pubkey, 
@@ -3,18 +3,25 @@
 //! Test for testing redemption rate curve authority features. 
 
 use helpers::{
+    create_mint, 
+    create_token_account, 
     fetch_permission, 
+    fetch_rate_provider, 
     fetch_redemption_rate_curve, 
     get_init_curve_setup, 
     get_permission_pda, 
     get_transaction_simulation_cu_used, 
+    mint_to_token_account, 
     program_test_context, 
+    program_test_context_with_bridge_receivers, 
     PROGRAM_ID
 };
-use solana_program_test::ProgramTestContext;
+use solana_program_test::{BanksClientError, ProgramTestContext};
 use solana_sdk::{
+    account::Account,
     clock::Clock, 
     compute_budget::ComputeBudgetInstruction, 
+    ed25519_program, 
     instruction::{AccountMeta, Instruction}, 
     program_pack::Pack, 
     pubkey::Pubkey, 
@@ -22,12 +29,68 @@ use solana_sdk::{
     signer::Signer, 
     system_program::ID as SYSTEM_PROGRAM_ID, 
     transaction::Transaction,
-    system_instruction::transfer
+    system_instruction::{self, transfer},
+    transaction::TransactionError,
+    instruction::InstructionError
 };
-use solana_psm::curve::{
-    redemption_rate::RedemptionRateCurve, 
-    fees::Fees
+use solana_psm::{
+    allowlist::AllowlistEntry,
+    bridge::derive_bridge_authority_pubkey_and_bump,
+    curve::{
+        base::CurveType,
+        calculator::TradeDirection,
+        redemption_rate::RedemptionRateCurve, 
+        fees::Fees
+    },
+    error::SwapError,
+    instruction::{
+        bridge_set_rates, 
+        deposit_all_token_types, 
+        deposit_single_token_type_exact_amount_in, 
+        get_conversion_rate, 
+        initialize_psm3, 
+        initialize_rate_provider, 
+        preview_deposit, 
+        preview_swap, 
+        preview_withdraw, 
+        psm3_deposit, 
+        psm3_swap, 
+        psm3_withdraw, 
+        relay_signed_rates, 
+        swap, 
+        swap_exact_amount_out, 
+        withdraw_all_token_types, 
+        withdraw_single_token_type_exact_amount_out, 
+        BridgeSetRates, 
+        DepositAllTokenTypes, 
+        DepositSingleTokenTypeExactAmountIn, 
+        GetConversionRate, 
+        InitializePsm3, 
+        InitializeRateProvider, 
+        PreviewDeposit, 
+        PreviewSwap, 
+        PreviewWithdraw, 
+        Psm3Accounts, 
+        Psm3Deposit, 
+        Psm3Swap, 
+        Psm3Withdraw, 
+        RelaySignedRates, 
+        RedemptionRateExtraAccounts, 
+        Swap, 
+        SwapExactAmountOut, 
+        WithdrawAllTokenTypes, 
+        WithdrawSingleTokenTypeExactAmountOut
+    },
+    preview::SwapPreview,
+    multisig::RateMultisig,
+    permission::{roles, Permission},
+    psm3::Psm3Pool,
+    rate_provider::RateProvider,
+    relay::{signed_rates_message, RelayNonce},
+    state::{PoolConfig, SwapState, SwapVersion},
+    timelock::{ParameterChange, QueuedChange}
 };
+use std::sync::Arc;
 use spl_token::ID as TOKEN_PROGRAM_ID;
 
 mod helpers;
@@ -161,7 +224,7 @@ async fn test_redemption_curve_permission_system() {
 }
 
 #[tokio::test]
-async fn test_rpow_performace_with_duration() {
+async fn test_pool_pause() {
     let mut context = program_test_context().await;
     let authority_keypair = Keypair::new();
     let fee_and_destination_owner = Pubkey::new_unique();
@@ -195,377 +258,6231 @@ async fn test_rpow_performace_with_duration() {
         token_b_mint,
         fee_account,
         destination_account,
-        ONE_HUNDRED_PCT_APY_SSR
+        0
     ).await;
 
-    test_rpow_compute_units_with_growing_duration(
-        &mut context,
-        &swap_info,
-        &authority_keypair
-    ).await
+    // super admin can pause by default
+    set_paused(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        true
+    ).await.unwrap();
+    assert!(fetch_swap(&mut context, &swap_info).await.is_paused());
+
+    // signer without a permission can't unpause
+    let fake = Keypair::new();
+    let result = set_paused(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        false
+    ).await;
+    assert!(result.is_err());
+    assert!(fetch_swap(&mut context, &swap_info).await.is_paused());
+
+    set_paused(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        false
+    ).await.unwrap();
+    assert!(!fetch_swap(&mut context, &swap_info).await.is_paused());
 }
 
 #[tokio::test]
-async fn test_rpow_performance_with_different_max_ssr() {
-    // Define precise APY values
-    const ONE_PCT_APY_SSR: u128 = 1_000_000_000_314_714_530_356_867_391;
-    const FIFTEEN_PCT_APY_SSR: u128 = 1_000_000_004_432_554_513_667_376_032;
-    const TWENTY_PCT_APY_SSR: u128 = 1_000_000_005_860_733_888_492_302_697;
-    const TWENTY_FIVE_PCT_APY_SSR: u128 = 1_000_000_007_264_617_216_271_247_405;
-    
-    // Create a test matrix with different max_ssr values
-    let max_ssr_values = vec![
-        (ONE_PCT_APY_SSR, "1% APY compound"),
-        (FIVE_PCT_APY_SSR, "5% APY compound"),
-        (FIFTEEN_PCT_APY_SSR, "15% APY compound"),
-        (TWENTY_PCT_APY_SSR, "20% APY compound"),
-        (TWENTY_FIVE_PCT_APY_SSR, "25% APY compound"),
-        (ONE_HUNDRED_PCT_APY_SSR, "100% APY compound")
-    ];
-    
-    println!("testing _rpow compute units with different max_ssr values:");
-    
-    for (max_ssr, label) in max_ssr_values {
-        // Create a fresh context for each test
-        let mut context = program_test_context().await;
-        let authority_keypair = Keypair::new();
-        let fee_and_destination_owner = Pubkey::new_unique();
-        
-        // Set up a new curve for this max_ssr value
-        let (
-            swap_info,
-            authority,
-            token_a_mint,
-            token_b_mint,
-            pool_mint,
-            token_a_account,
-            token_b_account,
-            fee_account,
-            destination_account
-        ) = get_init_curve_setup(
-            &mut context.banks_client,
-            &context.payer,
-            context.last_blockhash,
-            &fee_and_destination_owner
-        ).await;
-        
-        // Create redemption rate curve with this specific max_ssr
-        create_redemption_rate_curve(
-            &mut context,
-            &swap_info,
-            &authority,
-            &authority_keypair,
-            token_a_account,
-            token_b_account,
-            pool_mint,
-            token_a_mint,
-            token_b_mint,
-            fee_account,
-            destination_account,
-            max_ssr
+async fn test_swap_directions() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
         ).await;
-        
-        println!("\n--- Testing with max_ssr = {} ({}) ---", max_ssr, label);
-        
-        // Test this curve with different durations
-        test_rpow_compute_units_with_growing_duration(
-            &mut context,
-            &swap_info,
-            &authority_keypair
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
         ).await;
+        user_accounts.push(user_account);
     }
+    let (user_token_a, user_token_b) = (user_accounts[0], user_accounts[1]);
+
+    let swap_ix = |source, swap_source, swap_destination, destination, source_mint, destination_mint| swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        source, 
+        swap_source, 
+        swap_destination, 
+        destination, 
+        &pool_mint, 
+        &fee_account, 
+        source_mint, 
+        destination_mint, 
+        None, 
+        None, 
+        None, 
+        Swap {
+            amount_in: 1_000,
+            minimum_amount_out: 0,
+            referral_code: None,
+        }
+    ).unwrap();
+    let a_to_b_ix = swap_ix(
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_b, 
+        &token_a_mint, 
+        &token_b_mint
+    );
+    let b_to_a_ix = swap_ix(
+        &user_token_b, 
+        &token_b_account, 
+        &token_a_account, 
+        &user_token_a, 
+        &token_b_mint, 
+        &token_a_mint
+    );
+
+    // signer without a permission can't change the directions
+    let fake = Keypair::new();
+    let result = set_swap_directions(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        false, 
+        true
+    ).await;
+    assert!(result.is_err());
+
+    // pausing the pool doesn't allow disabling a single direction
+    let pauser = Keypair::new();
+    initialize_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &pauser.pubkey(), 
+        roles::PAUSE
+    ).await.unwrap();
+    let err = set_swap_directions(
+        &mut context, 
+        &swap_info, 
+        &pauser, 
+        false, 
+        true
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidUpdatePermission as u32)
+        )
+    );
+
+    let direction_admin = Keypair::new();
+    initialize_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &direction_admin.pubkey(), 
+        roles::SET_SWAP_DIRECTIONS
+    ).await.unwrap();
+    set_swap_directions(
+        &mut context, 
+        &swap_info, 
+        &direction_admin, 
+        false, 
+        true
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert!(pool_config.a_to_b_disabled);
+    assert!(!pool_config.b_to_a_disabled);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[a_to_b_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::SwapDirectionDisabled as u32)
+        )
+    );
+
+    // the other direction is still open
+    let tx = Transaction::new_signed_with_payer(
+        &[b_to_a_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    set_swap_directions(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        true, 
+        true
+    ).await.unwrap();
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[a_to_b_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
 }
 
+#[tokio::test]
+async fn test_allowlist() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let user_token_a = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &payer, 
+        &token_a_mint, 
+        &payer.pubkey()
+    ).await;
+    mint_to_token_account(
+        &mut context.banks_client, 
+        &TOKEN_PROGRAM_ID, 
+        &token_a_mint, 
+        &payer, 
+        &user_token_a, 
+        1_000_000, 
+        context.last_blockhash
+    ).await;
+    let user_token_b = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &payer, 
+        &token_b_mint, 
+        &payer.pubkey()
+    ).await;
+
+    let (allowlist_entry, _) = Pubkey::find_program_address(
+        &[
+            AllowlistEntry::ALLOWLIST_SEED,
+            &swap_info.to_bytes(),
+            &payer.pubkey().to_bytes()
+        ], 
+        &PROGRAM_ID
+    );
+
+    // the allowlist entry goes right before the host fee account
+    let host_fee_account = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &payer, 
+        &pool_mint, 
+        &payer.pubkey()
+    ).await;
+    let swap_ix = swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_b, 
+        &pool_mint, 
+        &fee_account, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        Some(&allowlist_entry), 
+        Some(&host_fee_account), 
+        Swap {
+            amount_in: 1_000,
+            minimum_amount_out: 0,
+            referral_code: None,
+        }
+    ).unwrap();
+    assert_eq!(swap_ix.accounts[14].pubkey, allowlist_entry);
+    assert_eq!(swap_ix.accounts[15].pubkey, host_fee_account);
+
+    // signer without a permission can't switch the mode
+    let fake = Keypair::new();
+    let result = set_allowlist_mode(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        false, 
+        true
+    ).await;
+    assert!(result.is_err());
+
+    set_allowlist_mode(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        false, 
+        true
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert!(!pool_config.liquidity_allowlisted);
+    assert!(pool_config.swaps_allowlisted);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::NotAllowlisted as u32)
+        )
+    );
+
+    // signer without a permission can't allow users
+    let result = add_to_allowlist(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &payer.pubkey()
+    ).await;
+    assert!(result.is_err());
+
+    add_to_allowlist(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &payer.pubkey()
+    ).await.unwrap();
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    remove_from_allowlist(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &allowlist_entry
+    ).await.unwrap();
+    let account = context.banks_client
+        .get_account(allowlist_entry)
+        .await
+        .unwrap();
+    assert!(account.is_none());
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::NotAllowlisted as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_swap_rate_limits() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
+        ).await;
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let (user_token_a, user_token_b) = (user_accounts[0], user_accounts[1]);
+
+    let a_to_b_ix = |amount_in| swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_b, 
+        &pool_mint, 
+        &fee_account, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        None, 
+        Swap {
+            amount_in,
+            minimum_amount_out: 0,
+            referral_code: None,
+        }
+    ).unwrap();
+    let b_to_a_ix = swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_b, 
+        &token_b_account, 
+        &token_a_account, 
+        &user_token_a, 
+        &pool_mint, 
+        &fee_account, 
+        &token_b_mint, 
+        &token_a_mint, 
+        None, 
+        None, 
+        None, 
+        Swap {
+            amount_in: 10_000,
+            minimum_amount_out: 0,
+            referral_code: None,
+        }
+    ).unwrap();
+
+    // signer without a permission can't set the limits
+    let fake = Keypair::new();
+    let result = set_swap_rate_limits(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        2_500, 
+        0, 
+        0, 
+        0
+    ).await;
+    assert!(result.is_err());
+
+    set_swap_rate_limits(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        2_500, 
+        0, 
+        0, 
+        0
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.a_to_b_rate_limit.max_amount, 2_500);
+    assert_eq!(pool_config.a_to_b_rate_limit.last_amount, 2_500);
+    assert!(!pool_config.b_to_a_rate_limit.is_bounded());
+
+    for amount_in in [1_000, 1_500] {
+        let tx = Transaction::new_signed_with_payer(
+            &[a_to_b_ix(amount_in)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+    }
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.a_to_b_rate_limit.last_amount, 0);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[a_to_b_ix(1)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::SwapRateLimitExceeded as u32)
+        )
+    );
+
+    // the other direction is unbounded
+    let tx = Transaction::new_signed_with_payer(
+        &[b_to_a_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // resetting the limits refills them
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    set_swap_rate_limits(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        2_500, 
+        0, 
+        0, 
+        0
+    ).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[a_to_b_ix(2_500)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_max_tvl() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint, pool_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let (user_token_a, user_token_b, user_pool_token) = (
+        user_accounts[0], 
+        user_accounts[1], 
+        user_accounts[2]
+    );
+    for (mint, user_account) in [(token_a_mint, user_token_a), (token_b_mint, user_token_b)] {
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+    }
+
+    let deposit_a_ix = |source_token_amount| deposit_single_token_type_exact_amount_in(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &pool_mint, 
+        &user_pool_token, 
+        &token_a_mint, 
+        None, 
+        None, 
+        DepositSingleTokenTypeExactAmountIn {
+            source_token_amount,
+            minimum_pool_token_amount: 0,
+            referral_code: None,
+        }
+    ).unwrap();
+    let deposit_all_ix = deposit_all_token_types(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &user_token_b, 
+        &token_a_account, 
+        &token_b_account, 
+        &pool_mint, 
+        &user_pool_token, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        DepositAllTokenTypes {
+            pool_token_amount: 1_000,
+            maximum_token_a_amount: 1_000_000,
+            maximum_token_b_amount: 1_000_000,
+            referral_code: None,
+        }
+    ).unwrap();
+
+    // a keeper without the MANAGE_LIQUIDITY role can't set the cap
+    let keeper = Keypair::new();
+    initialize_keeper_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &keeper.pubkey()
+    ).await.unwrap();
+    let result = set_max_tvl(
+        &mut context, 
+        &swap_info, 
+        &keeper, 
+        1
+    ).await;
+    assert!(result.is_err());
+
+    // both reserves hold 1_000_000_000 at a rate of one, the pool is worth
+    // 2_000_000_000 in token A and a cap below that rejects any deposit
+    set_max_tvl(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        1_999_999_999
+    ).await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_a_ix(1)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::MaxTvlExceeded as u32)
+        )
+    );
+
+    let max_tvl = 2_000_010_000;
+    set_max_tvl(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        max_tvl
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.max_tvl, max_tvl);
+
+    // deposits up to the cap go through
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_a_ix(10_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    for ix in [deposit_a_ix(2), deposit_all_ix.clone()] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer],
+            context.last_blockhash,
+        );
+        let err = context.banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err, 
+            TransactionError::InstructionError(
+                0, 
+                InstructionError::Custom(SwapError::MaxTvlExceeded as u32)
+            )
+        );
+    }
+
+    // removing the cap allows deposits again
+    set_max_tvl(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        0
+    ).await.unwrap();
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_all_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_referral_code() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
+        ).await;
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let (user_token_a, user_token_b) = (user_accounts[0], user_accounts[1]);
+
+    let swap_ix = |amount_in, referral_code| swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_b, 
+        &pool_mint, 
+        &fee_account, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        None, 
+        Swap {
+            amount_in,
+            minimum_amount_out: 0,
+            referral_code,
+        }
+    ).unwrap();
+
+    // with and without the trailing referral code
+    for (amount_in, referral_code) in [(1_000, Some(42)), (2_000, None)] {
+        let tx = Transaction::new_signed_with_payer(
+            &[swap_ix(amount_in, referral_code)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+    }
+    assert_eq!(
+        get_token_balance(&mut context, &user_token_a).await,
+        1_000_000 - 3_000
+    );
+}
+
+#[tokio::test]
+async fn test_swap_exact_amount_out() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
+        ).await;
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let (user_token_a, user_token_b) = (user_accounts[0], user_accounts[1]);
+
+    let swap_ix = |trade_direction, amount_out, maximum_amount_in| {
+        let (source, swap_source, swap_destination, destination, source_mint, destination_mint) =
+            match trade_direction {
+                TradeDirection::AtoB => (
+                    user_token_a, token_a_account, token_b_account, user_token_b, token_a_mint, token_b_mint
+                ),
+                TradeDirection::BtoA => (
+                    user_token_b, token_b_account, token_a_account, user_token_a, token_b_mint, token_a_mint
+                ),
+            };
+        swap_exact_amount_out(
+            &PROGRAM_ID, 
+            &TOKEN_PROGRAM_ID, 
+            &TOKEN_PROGRAM_ID, 
+            &TOKEN_PROGRAM_ID, 
+            &swap_info, 
+            &authority, 
+            &payer.pubkey(), 
+            &source, 
+            &swap_source, 
+            &swap_destination, 
+            &destination, 
+            &pool_mint, 
+            &fee_account, 
+            &source_mint, 
+            &destination_mint, 
+            None, 
+            None, 
+            None, 
+            SwapExactAmountOut {
+                amount_out,
+                maximum_amount_in,
+                referral_code: None,
+            }
+        ).unwrap()
+    };
+
+    // requires more than the maximum amount in
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(TradeDirection::AtoB, 1_000, 999)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::ExceededSlippage as u32)
+        )
+    );
+
+    // at a rate of one, the exact amount out is paid one for one
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(TradeDirection::AtoB, 1_000, 1_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_token_a).await, 999_000);
+    assert_eq!(get_token_balance(&mut context, &user_token_b).await, 1_001_000);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(TradeDirection::BtoA, 500, 1_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_token_a).await, 999_500);
+    assert_eq!(get_token_balance(&mut context, &user_token_b).await, 1_000_500);
+}
+
+#[tokio::test]
+async fn test_previews() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint, pool_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let (user_token_a, user_token_b, user_pool_token) = (
+        user_accounts[0], 
+        user_accounts[1], 
+        user_accounts[2]
+    );
+    for (mint, user_account) in [(token_a_mint, user_token_a), (token_b_mint, user_token_b)] {
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+    }
+
+    let fees = Fees {
+        trade_fee_numerator: 25,
+        trade_fee_denominator: 10_000,
+        owner_trade_fee_numerator: 5,
+        owner_trade_fee_denominator: 10_000,
+        owner_withdraw_fee_numerator: 1,
+        owner_withdraw_fee_denominator: 1_000,
+        ..Fees::default()
+    };
+    update_fees(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &fees
+    ).await.unwrap();
+
+    // exact in swap
+    let preview_ix = preview_swap(
+        &PROGRAM_ID, 
+        &swap_info, 
+        &token_a_account, 
+        &token_b_account, 
+        PreviewSwap {
+            amount: 100_000,
+            exact_out: false,
+        }
+    ).unwrap();
+    let return_data = simulate_return_data(&mut context, preview_ix).await.unwrap();
+    let preview = SwapPreview::unpack_from_slice(&return_data).unwrap();
+    assert_eq!(preview.amount_in, 100_000);
+    assert_eq!(preview.amount_out, 99_700);
+
+    let swap_ix = swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_b, 
+        &pool_mint, 
+        &fee_account, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        None, 
+        Swap {
+            amount_in: 100_000,
+            minimum_amount_out: preview.amount_out,
+            referral_code: None,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_token_a).await, 1_000_000 - 100_000);
+    assert_eq!(get_token_balance(&mut context, &user_token_b).await, 1_000_000 + 99_700);
+
+    // exact out swap
+    let preview_ix = preview_swap(
+        &PROGRAM_ID, 
+        &swap_info, 
+        &token_b_account, 
+        &token_a_account, 
+        PreviewSwap {
+            amount: 50_000,
+            exact_out: true,
+        }
+    ).unwrap();
+    let return_data = simulate_return_data(&mut context, preview_ix).await.unwrap();
+    let preview = SwapPreview::unpack_from_slice(&return_data).unwrap();
+    assert_eq!(preview.amount_out, 50_000);
+
+    let swap_ix = swap_exact_amount_out(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_b, 
+        &token_b_account, 
+        &token_a_account, 
+        &user_token_a, 
+        &pool_mint, 
+        &fee_account, 
+        &token_b_mint, 
+        &token_a_mint, 
+        None, 
+        None, 
+        None, 
+        SwapExactAmountOut {
+            amount_out: 50_000,
+            maximum_amount_in: preview.amount_in,
+            referral_code: None,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_token_a).await, 900_000 + 50_000);
+    assert_eq!(
+        get_token_balance(&mut context, &user_token_b).await, 
+        1_099_700 - preview.amount_in
+    );
+
+    // single token type deposit
+    let preview_ix = preview_deposit(
+        &PROGRAM_ID, 
+        &swap_info, 
+        &token_a_account, 
+        &token_b_account, 
+        &pool_mint, 
+        &token_a_mint, 
+        PreviewDeposit {
+            source_token_amount: 10_000,
+        }
+    ).unwrap();
+    let return_data = simulate_return_data(&mut context, preview_ix).await.unwrap();
+    let pool_token_amount = u64::from_le_bytes(return_data.try_into().unwrap());
+    assert!(pool_token_amount > 0);
+
+    let deposit_ix = deposit_single_token_type_exact_amount_in(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &pool_mint, 
+        &user_pool_token, 
+        &token_a_mint, 
+        None, 
+        None, 
+        DepositSingleTokenTypeExactAmountIn {
+            source_token_amount: 10_000,
+            minimum_pool_token_amount: pool_token_amount,
+            referral_code: None,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_pool_token).await, pool_token_amount);
+
+    // single token type withdrawal, withdraw fee included
+    let preview_ix = preview_withdraw(
+        &PROGRAM_ID, 
+        &swap_info, 
+        &token_a_account, 
+        &token_b_account, 
+        &pool_mint, 
+        &fee_account, 
+        &token_a_mint, 
+        PreviewWithdraw {
+            destination_token_amount: 5_000,
+        }
+    ).unwrap();
+    let return_data = simulate_return_data(&mut context, preview_ix).await.unwrap();
+    let burned_pool_token_amount = u64::from_le_bytes(return_data.try_into().unwrap());
+    assert!(burned_pool_token_amount > 0);
+
+    let withdraw_ix = withdraw_single_token_type_exact_amount_out(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &pool_mint, 
+        &fee_account, 
+        &user_pool_token, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_a, 
+        &token_a_mint, 
+        None, 
+        None, 
+        WithdrawSingleTokenTypeExactAmountOut {
+            destination_token_amount: 5_000,
+            maximum_pool_token_amount: burned_pool_token_amount,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(
+        get_token_balance(&mut context, &user_pool_token).await, 
+        pool_token_amount - burned_pool_token_amount
+    );
+
+    // previews fail like the previewed instruction
+    set_paused(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        true
+    ).await.unwrap();
+    let preview_ix = preview_swap(
+        &PROGRAM_ID, 
+        &swap_info, 
+        &token_a_account, 
+        &token_b_account, 
+        PreviewSwap {
+            amount: 100_000,
+            exact_out: false,
+        }
+    ).unwrap();
+    let err = simulate_return_data(&mut context, preview_ix).await.unwrap_err();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::PoolPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_get_conversion_rate() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let conversion_rate_ix = |timestamp| get_conversion_rate(
+        &PROGRAM_ID, 
+        &swap_info, 
+        GetConversionRate { timestamp }
+    ).unwrap();
+
+    // chi is one right after initialization
+    let return_data = simulate_return_data(
+        &mut context, 
+        conversion_rate_ix(None)
+    ).await.unwrap();
+    assert_eq!(u128::from_le_bytes(return_data.try_into().unwrap()), RAY);
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let rho = clock.unix_timestamp as u128;
+    set_rates(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR, 
+        rho, 
+        RAY
+    ).await.unwrap();
+
+    // extrapolated with the ssr to a later timestamp
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    let one_year_later = rho as u64 + 365 * 24 * 60 * 60;
+    let return_data = simulate_return_data(
+        &mut context, 
+        conversion_rate_ix(Some(one_year_later))
+    ).await.unwrap();
+    let rate = u128::from_le_bytes(return_data.try_into().unwrap());
+    assert_eq!(
+        rate, 
+        curve.get_conversion_rate(one_year_later as u128).unwrap().as_u128()
+    );
+    assert!(rate > RAY * 1_049 / 1_000 && rate < RAY * 1_051 / 1_000);
+
+    // earlier timestamps are rejected
+    let err = simulate_return_data(
+        &mut context, 
+        conversion_rate_ix(Some(rho as u64 - 1))
+    ).await.unwrap_err();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidInput as u32)
+        )
+    );
+
+    // and stale rates too
+    set_max_staleness(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        60
+    ).await.unwrap();
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp = (rho + 61) as i64;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    let err = simulate_return_data(
+        &mut context, 
+        conversion_rate_ix(None)
+    ).await.unwrap_err();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::StaleRate as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_swap() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    // rewrite the swap and the super admin permission with their legacy layouts,
+    // SwapV1 is a prefix of the latest version
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let swap_account = context.banks_client
+        .get_account(swap_info)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut legacy_swap_data = swap_account.data[..SwapVersion::V1_LEN].to_vec();
+    legacy_swap_data[0] = 1;
+    context.set_account(
+        &swap_info, 
+        &Account {
+            lamports: rent.minimum_balance(SwapVersion::V1_LEN),
+            data: legacy_swap_data,
+            owner: PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        }.into()
+    );
+
+    let permission_account = get_permission_pda(
+        &swap_info, 
+        &authority_keypair.pubkey()
+    );
+    // is_super_admin, can_update_parameters
+    set_legacy_permission(
+        &mut context, 
+        &permission_account, 
+        &[1, 1]
+    ).await;
+
+    let legacy_swap = fetch_swap(&mut context, &swap_info).await;
+    assert!(!legacy_swap.is_paused());
+
+    // pool controls are not available before migrating
+    let result = set_paused(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        true
+    ).await;
+    assert!(result.is_err());
+
+    let payer = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer(
+            &context.payer.pubkey(), 
+            &payer.pubkey(), 
+            1_000_000_000
+        )], 
+        Some(&context.payer.pubkey()), 
+        &[&context.payer], 
+        context.last_blockhash
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(swap_info, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            // migrate swap discriminator
+            data: vec![10],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let swap_account = context.banks_client
+        .get_account(swap_info)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(swap_account.data.len(), SwapVersion::LATEST_LEN);
+    assert_eq!(swap_account.data[0], 2);
+    assert_eq!(swap_account.lamports, rent.minimum_balance(SwapVersion::LATEST_LEN));
+
+    let migrated_swap = fetch_swap(&mut context, &swap_info).await;
+    assert_eq!(*migrated_swap.swap_curve(), *legacy_swap.swap_curve());
+    assert_eq!(*migrated_swap.token_a_account(), token_a_account);
+    assert_eq!(*migrated_swap.token_b_account(), token_b_account);
+
+    // legacy super admin permission can pause the migrated swap,
+    // with a new blockhash as the same transaction failed before
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    set_paused(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        true
+    ).await.unwrap();
+    assert!(fetch_swap(&mut context, &swap_info).await.is_paused());
+
+    let migrate_permission = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(permission_account, false),
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+        // migrate permission discriminator
+        data: vec![17],
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_permission.clone()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let permission = context.banks_client
+        .get_account(permission_account)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(permission.data.len(), Permission::LEN);
+    assert_eq!(permission.lamports, rent.minimum_balance(Permission::LEN));
+
+    let migrated_permission = fetch_permission(
+        &mut context.banks_client, 
+        &permission_account
+    ).await;
+    assert_eq!(migrated_permission.authority, authority_keypair.pubkey());
+    assert_eq!(migrated_permission.roles, roles::SUPER_ADMIN | roles::UPDATE_RATES);
+
+    // an up to date permission can't be migrated again
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_permission],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn test_rate_staleness() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let max_staleness = 60;
+
+    // signer without a permission can't update the limit
+    let fake = Keypair::new();
+    let result = set_max_staleness(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        max_staleness
+    ).await;
+    assert!(result.is_err());
+    assert_eq!(fetch_swap(&mut context, &swap_info).await.max_staleness(), 0);
+
+    // keepers can't disable the guard against their own failure
+    let keeper = Keypair::new();
+    initialize_keeper_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &keeper.pubkey()
+    ).await.unwrap();
+    let result = set_max_staleness(
+        &mut context, 
+        &swap_info, 
+        &keeper, 
+        max_staleness
+    ).await;
+    assert!(result.is_err());
+
+    set_max_staleness(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        max_staleness
+    ).await.unwrap();
+    assert_eq!(fetch_swap(&mut context, &swap_info).await.max_staleness(), max_staleness);
+
+    let user_token_a = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &context.payer, 
+        &token_a_mint, 
+        &context.payer.pubkey()
+    ).await;
+    mint_to_token_account(
+        &mut context.banks_client, 
+        &TOKEN_PROGRAM_ID, 
+        &token_a_mint, 
+        &context.payer, 
+        &user_token_a, 
+        1_000_000, 
+        context.last_blockhash
+    ).await;
+    let user_token_b = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &context.payer, 
+        &token_b_mint, 
+        &context.payer.pubkey()
+    ).await;
+
+    let swap_ix = swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &context.payer.pubkey(), 
+        &user_token_a, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_b, 
+        &pool_mint, 
+        &fee_account, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        None, 
+        Swap {
+            amount_in: 1_000,
+            minimum_amount_out: 0,
+            referral_code: None,
+        }
+    ).unwrap();
+
+    // fresh rate, swap succeeds
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix.clone()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // rate is older than the limit
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp = (curve.rho + max_staleness as u128 + 1) as i64;
+    context.set_sysvar(&clock);
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix.clone()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::StaleRate as u32)
+        )
+    );
+
+    // updating the rates makes the pool usable again
+    let permission_account = get_permission_pda(
+        &swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let update_data = vec![
+        // update discriminator
+        vec![6],
+        RAY.to_le_bytes().to_vec(),
+        (clock.unix_timestamp as u128).to_le_bytes().to_vec(),
+        RAY.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            Instruction {
+                program_id: PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(swap_info, false),
+                    AccountMeta::new_readonly(permission_account, false),
+                    AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+                ],
+                data: update_data,
+            },
+            swap_ix,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rate_update_limits() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let max_ssr_delta = 1_000_000_000_000_000_000;
+    let min_update_interval = 3_600;
+
+    // signer without a permission can't set the limits
+    let fake = Keypair::new();
+    let result = set_rate_update_limits(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        max_ssr_delta, 
+        min_update_interval
+    ).await;
+    assert!(result.is_err());
+
+    set_rate_update_limits(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        max_ssr_delta, 
+        min_update_interval
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.max_ssr_delta, max_ssr_delta);
+    assert_eq!(pool_config.min_rates_update_interval, min_update_interval);
+
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+
+    // the ssr can't jump by more than the delta
+    let err = set_rates(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::SsrDeltaExceeded as u32)
+        )
+    );
+
+    set_rates(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        RAY + max_ssr_delta, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await.unwrap();
+    assert_eq!(
+        fetch_pool_config(&mut context, &swap_info).await.last_rates_update, 
+        clock.unix_timestamp as u64
+    );
+
+    // the next update has to wait for the minimum interval
+    let err = set_rates(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        RAY, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::RateUpdateTooFrequent as u32)
+        )
+    );
+
+    clock.unix_timestamp += min_update_interval as i64;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    set_rates(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        RAY, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await.unwrap();
+
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.ssr, RAY);
+}
+
+#[tokio::test]
+async fn test_rate_multisig() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let keepers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let keeper_keys: Vec<Pubkey> = keepers.iter().map(|keeper| keeper.pubkey()).collect();
+
+    // only a super admin can set the multisig
+    let result = set_rate_multisig(
+        &mut context, 
+        &swap_info, 
+        &keepers[0], 
+        2, 
+        &keeper_keys
+    ).await;
+    assert!(result.is_err());
+
+    // the threshold can't exceed the number of signers
+    let result = set_rate_multisig(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        4, 
+        &keeper_keys
+    ).await;
+    assert!(result.is_err());
+
+    let multisig = set_rate_multisig(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        2, 
+        &keeper_keys
+    ).await.unwrap();
+
+    let account = context.banks_client
+        .get_account(multisig)
+        .await
+        .unwrap()
+        .unwrap();
+    let rate_multisig = RateMultisig::unpack(&account.data).unwrap();
+    assert_eq!(rate_multisig.swap, swap_info);
+    assert_eq!(rate_multisig.threshold, 2);
+    assert_eq!(rate_multisig.signers_count, 3);
+    assert_eq!(&rate_multisig.signers[..3], keeper_keys.as_slice());
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+
+    // a single keeper isn't enough
+    let err = set_rates_multisig(
+        &mut context, 
+        &swap_info, 
+        &[&keepers[0]], 
+        FIVE_PCT_APY_SSR, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::MissingRequiredSignature
+        )
+    );
+
+    // signers outside of the multisig don't count
+    let outsider = Keypair::new();
+    let result = set_rates_multisig(
+        &mut context, 
+        &swap_info, 
+        &[&keepers[0], &outsider], 
+        FIVE_PCT_APY_SSR, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await;
+    assert!(result.is_err());
+
+    set_rates_multisig(
+        &mut context, 
+        &swap_info, 
+        &[&keepers[0], &keepers[2]], 
+        FIVE_PCT_APY_SSR, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await.unwrap();
+
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.ssr, FIVE_PCT_APY_SSR);
+
+    // removing the multisig closes its account
+    set_rate_multisig(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        0, 
+        &[]
+    ).await.unwrap();
+
+    let account = context.banks_client
+        .get_account(multisig)
+        .await
+        .unwrap();
+    assert!(account.is_none());
+
+    let result = set_rates_multisig(
+        &mut context, 
+        &swap_info, 
+        &[&keepers[1], &keepers[2]], 
+        RAY, 
+        clock.unix_timestamp as u128, 
+        RAY
+    ).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_set_fee_account() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let treasury = Pubkey::new_unique();
+    let new_fee_account = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &context.payer, 
+        &pool_mint, 
+        &treasury
+    ).await;
+
+    // signer without a permission can't change the fee account
+    let fake = Keypair::new();
+    let result = set_fee_account(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &new_fee_account
+    ).await;
+    assert!(result.is_err());
+
+    // the fee account has to hold pool tokens
+    let err = set_fee_account(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &token_a_account
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidFeeAccount as u32)
+        )
+    );
+
+    // and can't be owned by the swap authority
+    let authority_fee_account = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &context.payer, 
+        &pool_mint, 
+        &authority
+    ).await;
+    let err = set_fee_account(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &authority_fee_account
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidOutputOwner as u32)
+        )
+    );
+
+    set_fee_account(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &new_fee_account
+    ).await.unwrap();
+    assert_eq!(
+        *fetch_swap(&mut context, &swap_info).await.pool_fee_account(), 
+        new_fee_account
+    );
+
+    // with a timelock, the change has to be queued
+    set_timelock_delay(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        86_400
+    ).await.unwrap();
+
+    let err = set_fee_account(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &fee_account
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::TimelockRequired as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_close_pool() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let payer = context.payer.insecure_clone();
+    let mut user_accounts = vec![];
+    for mint in [token_a_mint, token_b_mint, pool_mint] {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &payer.pubkey()
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let (user_token_a, user_token_b, user_pool_token) = (
+        user_accounts[0], 
+        user_accounts[1], 
+        user_accounts[2]
+    );
+    for (mint, user_account) in [(token_a_mint, user_token_a), (token_b_mint, user_token_b)] {
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+    }
+
+    let pool_token_amount = 1_000;
+    let ix = deposit_all_token_types(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_token_a, 
+        &user_token_b, 
+        &token_a_account, 
+        &token_b_account, 
+        &pool_mint, 
+        &user_pool_token, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        DepositAllTokenTypes {
+            pool_token_amount,
+            maximum_token_a_amount: 1_000_000,
+            maximum_token_b_amount: 1_000_000,
+            referral_code: None,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let recipient = Pubkey::new_unique();
+
+    // only a super admin can close the pool
+    let fake = Keypair::new();
+    let result = close_pool(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &destination_account, 
+        &user_token_a, 
+        &user_token_b, 
+        &recipient
+    ).await;
+    assert!(result.is_err());
+
+    // the locked initial supply has to be the only one left
+    let err = close_pool(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &destination_account, 
+        &user_token_a, 
+        &user_token_b, 
+        &recipient
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::PoolNotEmpty as u32)
+        )
+    );
+
+    // the deposited pool tokens can't pass for the locked ones
+    let err = close_pool(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &user_pool_token, 
+        &user_token_a, 
+        &user_token_b, 
+        &recipient
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidOwner as u32)
+        )
+    );
+
+    let ix = withdraw_all_token_types(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &swap_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &pool_mint, 
+        &fee_account, 
+        &user_pool_token, 
+        &token_a_account, 
+        &token_b_account, 
+        &user_token_a, 
+        &user_token_b, 
+        &token_a_mint, 
+        &token_b_mint, 
+        None, 
+        None, 
+        WithdrawAllTokenTypes {
+            pool_token_amount,
+            minimum_token_a_amount: 0,
+            minimum_token_b_amount: 0,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let reserve_a = get_token_balance(&mut context, &token_a_account).await;
+    let reserve_b = get_token_balance(&mut context, &token_b_account).await;
+    let user_a = get_token_balance(&mut context, &user_token_a).await;
+    let user_b = get_token_balance(&mut context, &user_token_b).await;
+
+    let mut rent = 0;
+    for account in [swap_info, token_a_account, token_b_account] {
+        rent += context.banks_client
+            .get_balance(account)
+            .await
+            .unwrap();
+    }
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    close_pool(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &destination_account, 
+        &user_token_a, 
+        &user_token_b, 
+        &recipient
+    ).await.unwrap();
+
+    // the reserves are sent out and the accounts closed
+    assert_eq!(get_token_balance(&mut context, &user_token_a).await, user_a + reserve_a);
+    assert_eq!(get_token_balance(&mut context, &user_token_b).await, user_b + reserve_b);
+    for account in [swap_info, token_a_account, token_b_account] {
+        let account = context.banks_client
+            .get_account(account)
+            .await
+            .unwrap();
+        assert!(account.is_none());
+    }
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(), 
+        rent
+    );
+}
+
+#[tokio::test]
+async fn test_max_ssr_update() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    // signer without a permission can't update max_ssr
+    let fake = Keypair::new();
+    let result = set_max_ssr(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        FIVE_PCT_APY_SSR
+    ).await;
+    assert!(result.is_err());
+
+    // max_ssr can't be below the current ssr
+    let result = set_max_ssr(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        RAY - 1
+    ).await;
+    assert!(result.is_err());
+
+    set_max_ssr(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR
+    ).await.unwrap();
+
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.max_ssr, FIVE_PCT_APY_SSR);
+    assert_eq!(curve.ssr, RAY);
+    assert_eq!(curve.chi, RAY);
+
+    // rates above the new bound are rejected
+    let permission_account = get_permission_pda(
+        &swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let update_data = vec![
+        // update discriminator
+        vec![6],
+        ONE_HUNDRED_PCT_APY_SSR.to_le_bytes().to_vec(),
+        curve.rho.to_le_bytes().to_vec(),
+        RAY.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(swap_info, false),
+                AccountMeta::new_readonly(permission_account, false),
+                AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+            ],
+            data: update_data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority_keypair],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidSsr as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_update_fees() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let permission_account = get_permission_pda(
+        &swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let fee_admin = Keypair::new();
+    let fee_admin_permission = get_permission_pda(
+        &swap_info, 
+        &fee_admin.pubkey()
+    );
+
+    // grant a permission that can only update fees
+    let init_data = vec![
+        // init permission discriminator
+        vec![7],
+        fee_admin.pubkey().to_bytes().to_vec(),
+        roles::UPDATE_FEES.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(swap_info, false),
+                AccountMeta::new_readonly(permission_account, false),
+                AccountMeta::new(fee_admin_permission, false),
+                AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+                AccountMeta::new_readonly(context.payer.pubkey(), true),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            data: init_data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let fetched = fetch_permission(
+        &mut context.banks_client, 
+        &fee_admin_permission
+    ).await;
+    assert!(fetched.has_role(roles::UPDATE_FEES));
+    assert!(!fetched.has_role(roles::UPDATE_RATES));
+
+    let new_fees = Fees {
+        trade_fee_numerator: 1,
+        trade_fee_denominator: 10_000,
+        ..Fees::default()
+    };
+
+    // signer without a permission can't update the fees
+    let fake = Keypair::new();
+    let result = update_fees(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &new_fees
+    ).await;
+    assert!(result.is_err());
+
+    update_fees(
+        &mut context, 
+        &swap_info, 
+        &fee_admin, 
+        &new_fees
+    ).await.unwrap();
+    assert_eq!(*fetch_swap(&mut context, &swap_info).await.fees(), new_fees);
+
+    // legacy permission accounts have no room for the UPDATE_FEES role
+    // is_super_admin, can_update_parameters
+    set_legacy_permission(
+        &mut context, 
+        &fee_admin_permission, 
+        &[0, 0]
+    ).await;
+
+    let update_permission = |roles: u64| Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(swap_info, false),
+            AccountMeta::new_readonly(permission_account, false),
+            AccountMeta::new(fee_admin_permission, false),
+            AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        ],
+        // update permission discriminator, roles
+        data: [&[8][..], &roles.to_le_bytes()].concat(),
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[update_permission(roles::UPDATE_FEES)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority_keypair],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall)
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[update_permission(roles::UPDATE_RATES)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let permission = context.banks_client
+        .get_account(fee_admin_permission)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched = Permission::unpack_account_data(&permission.data).unwrap();
+    assert_eq!(fetched.roles, roles::UPDATE_RATES);
+}
+
+#[tokio::test]
+async fn test_super_admin_handover() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let new_admin = Keypair::new();
+
+    // nothing to accept yet
+    let result = accept_super_admin(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair.pubkey(), 
+        &new_admin
+    ).await;
+    assert!(result.is_err());
+
+    // signer without a permission can't nominate
+    let fake = Keypair::new();
+    let result = propose_super_admin(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &fake.pubkey()
+    ).await;
+    assert!(result.is_err());
+
+    // a mistyped nomination can't be accepted by anyone else,
+    // and the super admin keeps control to fix it
+    propose_super_admin(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &Pubkey::new_unique()
+    ).await.unwrap();
+    let err = accept_super_admin(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair.pubkey(), 
+        &new_admin
+    ).await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidPendingSuperAdmin as u32)
+        )
+    );
+
+    propose_super_admin(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &new_admin.pubkey()
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.pending_super_admin, new_admin.pubkey());
+    assert_eq!(pool_config.super_admin_proposer, authority_keypair.pubkey());
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    accept_super_admin(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair.pubkey(), 
+        &new_admin
+    ).await.unwrap();
+
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.pending_super_admin, Pubkey::default());
+    assert_eq!(pool_config.super_admin_proposer, Pubkey::default());
+
+    let new_permission = fetch_permission(
+        &mut context.banks_client, 
+        &get_permission_pda(&swap_info, &new_admin.pubkey())
+    ).await;
+    assert!(new_permission.is_initialized);
+    assert_eq!(new_permission.roles, roles::ALL);
+    assert_eq!(new_permission.authority, new_admin.pubkey());
+
+    let old_permission = fetch_permission(
+        &mut context.banks_client, 
+        &get_permission_pda(&swap_info, &authority_keypair.pubkey())
+    ).await;
+    assert_eq!(old_permission.roles, 0);
+
+    // the previous super admin lost control, the new one has it
+    let result = propose_super_admin(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &authority_keypair.pubkey()
+    ).await;
+    assert!(result.is_err());
+
+    set_paused(
+        &mut context, 
+        &swap_info, 
+        &new_admin, 
+        true
+    ).await.unwrap();
+    assert!(fetch_swap(&mut context, &swap_info).await.is_paused());
+}
+
+#[tokio::test]
+async fn test_close_permission() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let keeper = Keypair::new();
+    let keeper_permission = get_permission_pda(
+        &swap_info, 
+        &keeper.pubkey()
+    );
+    initialize_keeper_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &keeper.pubkey()
+    ).await.unwrap();
+
+    let rent = context.banks_client
+        .get_account(keeper_permission)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+
+    // signer without a permission can't close it
+    let fake = Keypair::new();
+    let result = close_permission(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &keeper_permission, 
+        &fake.pubkey()
+    ).await;
+    assert!(result.is_err());
+
+    // super admin permissions can only be handed over
+    let permission_account = get_permission_pda(
+        &swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let err = close_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &permission_account, 
+        &authority_keypair.pubkey()
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::SuperAdminHandoverRequired as u32)
+        )
+    );
+
+    // a copy of the keeper permission outside of its PDA can't be closed
+    let keeper_permission_account = context.banks_client
+        .get_account(keeper_permission)
+        .await
+        .unwrap()
+        .unwrap();
+    let fake_permission = Pubkey::new_unique();
+    context.set_account(&fake_permission, &keeper_permission_account.into());
+    let err = close_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &fake_permission, 
+        &Pubkey::new_unique()
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidPermissionAddress as u32)
+        )
+    );
+
+    // super admin closes the keeper permission
+    let recipient = Pubkey::new_unique();
+    close_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &keeper_permission, 
+        &recipient
+    ).await.unwrap();
+
+    assert!(context.banks_client
+        .get_account(keeper_permission)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(), 
+        rent
+    );
+
+    // the same permission can be created again
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    initialize_keeper_permission(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &keeper.pubkey()
+    ).await.unwrap();
+    let fetched = fetch_permission(
+        &mut context.banks_client, 
+        &keeper_permission
+    ).await;
+    assert!(fetched.is_initialized);
+    assert!(fetched.has_role(roles::UPDATE_RATES));
+
+    // and closed by its own authority
+    close_permission(
+        &mut context, 
+        &swap_info, 
+        &keeper, 
+        &keeper_permission, 
+        &recipient
+    ).await.unwrap();
+    assert!(context.banks_client
+        .get_account(keeper_permission)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(), 
+        2 * rent
+    );
+}
+
+#[tokio::test]
+async fn test_timelock() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let timelock_delay = 86_400;
+    set_timelock_delay(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        timelock_delay
+    ).await.unwrap();
+    assert_eq!(
+        fetch_pool_config(&mut context, &swap_info).await.timelock_delay, 
+        timelock_delay
+    );
+
+    // sensitive changes can't be applied directly anymore
+    let timelock_required = TransactionError::InstructionError(
+        0, 
+        InstructionError::Custom(SwapError::TimelockRequired as u32)
+    );
+    let err = set_max_ssr(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR
+    ).await.unwrap_err().unwrap();
+    assert_eq!(err, timelock_required);
+
+    let new_fees = Fees {
+        trade_fee_numerator: 1,
+        trade_fee_denominator: 10_000,
+        ..Fees::default()
+    };
+    let err = update_fees(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &new_fees
+    ).await.unwrap_err().unwrap();
+    assert_eq!(err, timelock_required);
+
+    let err = set_timelock_delay(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        0
+    ).await.unwrap_err().unwrap();
+    assert_eq!(err, timelock_required);
+
+    let err = set_max_staleness(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        0
+    ).await.unwrap_err().unwrap();
+    assert_eq!(err, timelock_required);
+
+    // signer without a permission can't queue a change
+    let fake = Keypair::new();
+    let max_ssr_change = ParameterChange::MaxSsr(FIVE_PCT_APY_SSR);
+    let result = queue_change(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &max_ssr_change
+    ).await;
+    assert!(result.is_err());
+
+    let max_ssr_queued = queue_change(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &max_ssr_change
+    ).await.unwrap();
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let queued = fetch_queued_change(&mut context, &max_ssr_queued).await;
+    assert_eq!(queued.swap, swap_info);
+    assert_eq!(queued.payer, context.payer.pubkey());
+    assert_eq!(queued.eta, clock.unix_timestamp + timelock_delay as i64);
+    assert_eq!(queued.change, max_ssr_change);
+
+    // only one change of a kind can be queued at a time
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = queue_change(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &ParameterChange::MaxSsr(0)
+    ).await;
+    assert!(result.is_err());
+
+    let err = execute_change(
+        &mut context, 
+        &swap_info, 
+        &max_ssr_queued, 
+        None
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::TimelockNotElapsed as u32)
+        )
+    );
+
+    // queue the fees and the fee account too
+    let fees_queued = queue_change(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &ParameterChange::Fees(new_fees.clone())
+    ).await.unwrap();
+
+    let new_fee_account = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &context.payer, 
+        &pool_mint, 
+        &fee_and_destination_owner
+    ).await;
+    let fee_account_queued = queue_change(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &ParameterChange::FeeAccount(new_fee_account)
+    ).await.unwrap();
+
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp += timelock_delay as i64;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    // anyone can execute an elapsed change, the rent goes back to its payer
+    let payer_balance = context.banks_client
+        .get_balance(context.payer.pubkey())
+        .await
+        .unwrap();
+    let rent = context.banks_client
+        .get_balance(max_ssr_queued)
+        .await
+        .unwrap();
+    execute_change(
+        &mut context, 
+        &swap_info, 
+        &max_ssr_queued, 
+        None
+    ).await.unwrap();
+
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.max_ssr, FIVE_PCT_APY_SSR);
+    assert!(context.banks_client
+        .get_account(max_ssr_queued)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context.banks_client
+            .get_balance(context.payer.pubkey())
+            .await
+            .unwrap(),
+        payer_balance + rent - 5_000
+    );
+
+    // the fee account change needs the new fee account
+    let result = execute_change(
+        &mut context, 
+        &swap_info, 
+        &fee_account_queued, 
+        Some(&fee_account)
+    ).await;
+    assert!(result.is_err());
+
+    execute_change(
+        &mut context, 
+        &swap_info, 
+        &fee_account_queued, 
+        Some(&new_fee_account)
+    ).await.unwrap();
+    assert_eq!(
+        *fetch_swap(&mut context, &swap_info).await.pool_fee_account(), 
+        new_fee_account
+    );
+
+    // a super admin can cancel a queued change
+    let result = cancel_change(
+        &mut context, 
+        &swap_info, 
+        &fake, 
+        &fees_queued
+    ).await;
+    assert!(result.is_err());
+
+    let old_fees = fetch_swap(&mut context, &swap_info).await.fees().clone();
+    cancel_change(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &fees_queued
+    ).await.unwrap();
+    assert!(context.banks_client
+        .get_account(fees_queued)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(*fetch_swap(&mut context, &swap_info).await.fees(), old_fees);
+
+    // the delay itself is changed through the timelock
+    let delay_queued = queue_change(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &ParameterChange::TimelockDelay(0)
+    ).await.unwrap();
+
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp += timelock_delay as i64;
+    context.set_sysvar(&clock);
+
+    execute_change(
+        &mut context, 
+        &swap_info, 
+        &delay_queued, 
+        None
+    ).await.unwrap();
+    assert_eq!(fetch_pool_config(&mut context, &swap_info).await.timelock_delay, 0);
+
+    update_fees(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &new_fees
+    ).await.unwrap();
+    assert_eq!(*fetch_swap(&mut context, &swap_info).await.fees(), new_fees);
+}
+
+#[tokio::test]
+async fn test_psm3() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let payer = context.payer.insecure_clone();
+
+    let pool_keypair = Keypair::new();
+    let pool_info = pool_keypair.pubkey();
+    let (authority, _) = Pubkey::find_program_address(
+        &[&pool_info.to_bytes()], 
+        &PROGRAM_ID
+    );
+
+    let mut mints = vec![];
+    let mut reserves = vec![];
+    for _ in 0..3 {
+        let mint = create_mint(
+            &mut context.banks_client, 
+            &payer, 
+            context.last_blockhash, 
+            &TOKEN_PROGRAM_ID, 
+            None, 
+            None
+        ).await;
+        let reserve = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            &mint, 
+            &authority
+        ).await;
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            &mint, 
+            &payer, 
+            &reserve, 
+            1_000_000_000, 
+            context.last_blockhash
+        ).await;
+        mints.push(mint);
+        reserves.push(reserve);
+    }
+    let (usdc, usds, susds) = (0, 1, 2);
+
+    let pool_mint = create_mint(
+        &mut context.banks_client, 
+        &payer, 
+        context.last_blockhash, 
+        &TOKEN_PROGRAM_ID, 
+        Some(&authority), 
+        None
+    ).await;
+    let (destination_owner, _) = Pubkey::find_program_address(
+        &[
+            b"init_destination", 
+            &pool_info.to_bytes()
+        ],
+        &PROGRAM_ID
+    );
+    let destination = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &payer, 
+        &pool_mint, 
+        &destination_owner
+    ).await;
+
+    let psm3_accounts = || Psm3Accounts {
+        pool: &pool_info,
+        authority: &authority,
+        reserves: [&reserves[0], &reserves[1], &reserves[2]],
+        pool_mint: &pool_mint,
+        pool_token_program_id: &TOKEN_PROGRAM_ID,
+    };
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let rates = RedemptionRateCurve {
+        max_ssr: 0,
+        ssr: RAY,
+        rho: clock.unix_timestamp as u128,
+        chi: RAY * 5 / 4,
+    };
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let permission_account = get_permission_pda(
+        &pool_info, 
+        &authority_keypair.pubkey()
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(), 
+                &pool_info, 
+                rent.minimum_balance(Psm3Pool::LEN), 
+                Psm3Pool::LEN as u64, 
+                &PROGRAM_ID
+            ),
+            initialize_psm3(
+                &PROGRAM_ID, 
+                psm3_accounts(), 
+                [&mints[0], &mints[1], &mints[2]], 
+                &destination, 
+                RedemptionRateExtraAccounts {
+                    permission_account: &permission_account,
+                    authority: &authority_keypair.pubkey(),
+                    payer: &payer.pubkey(),
+                    system_program: &SYSTEM_PROGRAM_ID,
+                }, 
+                InitializePsm3 { rates }
+            ).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &pool_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // the initial supply is the value of the reserves, sUSDS at 1.25
+    assert_eq!(get_token_balance(&mut context, &destination).await, 3_250_000_000);
+    assert_eq!(
+        fetch_permission(&mut context.banks_client, &permission_account).await.roles, 
+        roles::ALL
+    );
+
+    let mut user_accounts = vec![];
+    for mint in &mints {
+        let user_account = create_token_account(
+            &mut context.banks_client, 
+            context.last_blockhash, 
+            &payer, 
+            mint, 
+            &payer.pubkey()
+        ).await;
+        mint_to_token_account(
+            &mut context.banks_client, 
+            &TOKEN_PROGRAM_ID, 
+            mint, 
+            &payer, 
+            &user_account, 
+            1_000_000, 
+            context.last_blockhash
+        ).await;
+        user_accounts.push(user_account);
+    }
+    let user_pool_account = create_token_account(
+        &mut context.banks_client, 
+        context.last_blockhash, 
+        &payer, 
+        &pool_mint, 
+        &payer.pubkey()
+    ).await;
+
+    let swap_ix = |source: usize, destination: usize, amount_in, minimum_amount_out| psm3_swap(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        &pool_info, 
+        &authority, 
+        &payer.pubkey(), 
+        &user_accounts[source], 
+        &reserves[source], 
+        &reserves[destination], 
+        &user_accounts[destination], 
+        &mints[source], 
+        &mints[destination], 
+        Psm3Swap {
+            amount_in,
+            minimum_amount_out,
+        }
+    ).unwrap();
+
+    // swapping an asset for itself is rejected
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(usdc, usdc, 1_000, 0)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidInput as u32)
+        )
+    );
+
+    // USDC and USDS swap one for one
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(usdc, usds, 1_000, 1_001)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::ExceededSlippage as u32)
+        )
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(usdc, usds, 1_000, 1_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usdc]).await, 999_000);
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usds]).await, 1_001_000);
+
+    // and sUSDS at the conversion rate
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(usds, susds, 1_250, 1_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usds]).await, 999_750);
+    assert_eq!(get_token_balance(&mut context, &user_accounts[susds]).await, 1_001_000);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(susds, usdc, 2_000, 2_500)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_accounts[susds]).await, 999_000);
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usdc]).await, 1_001_500);
+    assert_eq!(get_token_balance(&mut context, &reserves[usdc]).await, 999_998_500);
+
+    // pool tokens are worth one dollar as the swaps kept the value of the pool
+    let deposit_ix = |asset: usize, source_token_amount, minimum_pool_token_amount| psm3_deposit(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        psm3_accounts(), 
+        &payer.pubkey(), 
+        &user_accounts[asset], 
+        &user_pool_account, 
+        &mints[asset], 
+        Psm3Deposit {
+            source_token_amount,
+            minimum_pool_token_amount,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix(susds, 800, 1_001)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::ExceededSlippage as u32)
+        )
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            deposit_ix(susds, 800, 1_000),
+            deposit_ix(usds, 1_000, 1_000),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_pool_account).await, 2_000);
+    assert_eq!(get_token_balance(&mut context, &user_accounts[susds]).await, 998_200);
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usds]).await, 998_750);
+
+    let withdraw_ix = |asset: usize, destination_token_amount, maximum_pool_token_amount| psm3_withdraw(
+        &PROGRAM_ID, 
+        &TOKEN_PROGRAM_ID, 
+        psm3_accounts(), 
+        &payer.pubkey(), 
+        &user_pool_account, 
+        &user_accounts[asset], 
+        &mints[asset], 
+        Psm3Withdraw {
+            destination_token_amount,
+            maximum_pool_token_amount,
+        }
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix(usdc, 1_500, 1_499)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::ExceededSlippage as u32)
+        )
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix(usdc, 1_500, 1_500)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_pool_account).await, 500);
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usdc]).await, 1_003_000);
+
+    // the rates of the pool are updated with SetRates
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let rho = clock.unix_timestamp as u128;
+    set_rates(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR, 
+        rho, 
+        RAY * 3 / 2
+    ).await.unwrap();
+
+    let account = context.banks_client
+        .get_account(pool_info)
+        .await
+        .unwrap()
+        .unwrap();
+    let pool = Psm3Pool::unpack(&account.data).unwrap();
+    assert_eq!(
+        pool.rates, 
+        RedemptionRateCurve {
+            max_ssr: 0,
+            ssr: FIVE_PCT_APY_SSR,
+            rho,
+            chi: RAY * 3 / 2,
+        }
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(susds, usds, 1_000, 1_500)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usds]).await, 1_000_250);
+
+    // the pool has its own max_ssr, pause and staleness bound
+    set_max_ssr(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR
+    ).await.unwrap();
+    set_paused(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        true
+    ).await.unwrap();
+    set_max_staleness(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        60
+    ).await.unwrap();
+
+    let account = context.banks_client
+        .get_account(pool_info)
+        .await
+        .unwrap()
+        .unwrap();
+    let pool = Psm3Pool::unpack(&account.data).unwrap();
+    assert_eq!(pool.rates.max_ssr, FIVE_PCT_APY_SSR);
+    assert!(pool.is_paused);
+    assert_eq!(pool.max_staleness, 60);
+
+    // swaps and deposits are paused, withdrawals stay open
+    for ix in [swap_ix(usds, usdc, 1_000, 1_000), deposit_ix(usds, 1_000, 0)] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer],
+            context.last_blockhash,
+        );
+        let err = context.banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err, 
+            TransactionError::InstructionError(
+                0, 
+                InstructionError::Custom(SwapError::PoolPaused as u32)
+            )
+        );
+    }
+
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix(usdc, 100, 100)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usdc]).await, 1_003_100);
+
+    set_paused(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        false
+    ).await.unwrap();
+
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp = (rho + 61) as i64;
+    context.set_sysvar(&clock);
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[swap_ix(usds, usdc, 1_000, 1_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::StaleRate as u32)
+        )
+    );
+
+    // withdrawals use the last known rates once they are stale
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix(usdc, 100, 100)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(get_token_balance(&mut context, &user_accounts[usdc]).await, 1_003_200);
+
+    // the pool has the rate update limits of a swap, enforced on every
+    // rate update
+    set_rate_update_limits(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        0, 
+        3_600
+    ).await.unwrap();
+    let err = set_rates(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR, 
+        rho + 61, 
+        RAY * 3 / 2
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::RateUpdateTooFrequent as u32)
+        )
+    );
+
+    // once it has a timelock delay, its parameters are queued
+    let timelock_delay = 3_600;
+    set_timelock_delay(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        timelock_delay
+    ).await.unwrap();
+    for result in [
+        set_max_ssr(&mut context, &pool_info, &authority_keypair, FIVE_PCT_APY_SSR).await,
+        set_max_staleness(&mut context, &pool_info, &authority_keypair, 0).await,
+        set_rate_update_limits(&mut context, &pool_info, &authority_keypair, 0, 0).await,
+    ] {
+        assert_eq!(
+            result.unwrap_err().unwrap(), 
+            TransactionError::InstructionError(
+                0, 
+                InstructionError::Custom(SwapError::TimelockRequired as u32)
+            )
+        );
+    }
+
+    // changes of swap parameters can't be queued on a three-asset pool
+    let err = queue_change(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        &ParameterChange::BridgeReceiver(Pubkey::new_unique())
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidInput as u32)
+        )
+    );
+
+    let limits_queued = queue_change(
+        &mut context, 
+        &pool_info, 
+        &authority_keypair, 
+        &ParameterChange::RateUpdateLimits {
+            max_ssr_delta: 0,
+            min_update_interval: 0,
+        }
+    ).await.unwrap();
+
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp += timelock_delay as i64;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    execute_change(
+        &mut context, 
+        &pool_info, 
+        &limits_queued, 
+        None
+    ).await.unwrap();
+
+    let account = context.banks_client
+        .get_account(pool_info)
+        .await
+        .unwrap()
+        .unwrap();
+    let pool = Psm3Pool::unpack(&account.data).unwrap();
+    assert_eq!(pool.min_rates_update_interval, 0);
+    assert_eq!(pool.timelock_delay, timelock_delay);
+
+    // the pool is not a swap
+    let err = simulate_return_data(
+        &mut context, 
+        get_conversion_rate(
+            &PROGRAM_ID, 
+            &pool_info, 
+            GetConversionRate { timestamp: None }
+        ).unwrap()
+    ).await.unwrap_err();
+    assert!(matches!(err, TransactionError::InstructionError(0, _)));
+}
+
+#[tokio::test]
+async fn test_rate_provider() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let payer = context.payer.insecure_clone();
+
+    let rate_provider_keypair = Keypair::new();
+    let rate_provider = rate_provider_keypair.pubkey();
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let rho = clock.unix_timestamp as u128;
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let permission_account = get_permission_pda(
+        &rate_provider, 
+        &authority_keypair.pubkey()
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(), 
+                &rate_provider, 
+                rent.minimum_balance(RateProvider::LEN), 
+                RateProvider::LEN as u64, 
+                &PROGRAM_ID
+            ),
+            initialize_rate_provider(
+                &PROGRAM_ID, 
+                &rate_provider, 
+                RedemptionRateExtraAccounts {
+                    permission_account: &permission_account,
+                    authority: &authority_keypair.pubkey(),
+                    payer: &payer.pubkey(),
+                    system_program: &SYSTEM_PROGRAM_ID,
+                }, 
+                InitializeRateProvider {
+                    rates: RedemptionRateCurve {
+                        max_ssr: 0,
+                        ssr: RAY,
+                        rho,
+                        chi: RAY,
+                    }
+                }
+            ).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &rate_provider_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(
+        fetch_permission(&mut context.banks_client, &permission_account).await.roles, 
+        roles::ALL
+    );
+
+    // two pools priced with the rates of the same rate provider
+    let other_rate_provider = Pubkey::new_unique();
+    let mut swap_ixs = vec![];
+    let mut user_token_b_accounts = vec![];
+    for _ in 0..2 {
+        let (
+            swap_info,
+            authority,
+            token_a_mint,
+            token_b_mint,
+            pool_mint,
+            token_a_account,
+            token_b_account,
+            fee_account,
+            destination_account
+        ) = get_init_curve_setup(
+            &mut context.banks_client,
+            &context.payer,
+            context.last_blockhash,
+            &Pubkey::new_unique()
+        ).await;
+
+        create_rate_provider_curve(
+            &mut context,
+            &swap_info,
+            &authority,
+            &authority_keypair,
+            token_a_account,
+            token_b_account,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            fee_account,
+            destination_account,
+            &rate_provider
+        ).await;
+
+        let mut user_accounts = vec![];
+        for mint in [token_a_mint, token_b_mint] {
+            let user_account = create_token_account(
+                &mut context.banks_client, 
+                context.last_blockhash, 
+                &payer, 
+                &mint, 
+                &payer.pubkey()
+            ).await;
+            mint_to_token_account(
+                &mut context.banks_client, 
+                &TOKEN_PROGRAM_ID, 
+                &mint, 
+                &payer, 
+                &user_account, 
+                1_000_000, 
+                context.last_blockhash
+            ).await;
+            user_accounts.push(user_account);
+        }
+
+        let swap_ix = |rate_provider| swap(
+            &PROGRAM_ID, 
+            &TOKEN_PROGRAM_ID, 
+            &TOKEN_PROGRAM_ID, 
+            &TOKEN_PROGRAM_ID, 
+            &swap_info, 
+            &authority, 
+            &payer.pubkey(), 
+            &user_accounts[0], 
+            &token_a_account, 
+            &token_b_account, 
+            &user_accounts[1], 
+            &pool_mint, 
+            &fee_account, 
+            &token_a_mint, 
+            &token_b_mint, 
+            rate_provider, 
+            None, 
+            None, 
+            Swap {
+                amount_in: 1_000,
+                minimum_amount_out: 0,
+                referral_code: None,
+            }
+        ).unwrap();
+        // without a rate provider, with another one and with the one of the pool
+        swap_ixs.push([
+            swap_ix(None),
+            swap_ix(Some(&other_rate_provider)),
+            swap_ix(Some(&rate_provider)),
+        ]);
+        user_token_b_accounts.push(user_accounts[1]);
+    }
+
+    let process_swap = |ix: &Instruction, blockhash| {
+        Transaction::new_signed_with_payer(
+            &[ix.clone()],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        )
+    };
+
+    // the rate provider is required, and must be the one of the pool
+    for (swap_ix, error) in swap_ixs[0].iter().zip([
+        InstructionError::NotEnoughAccountKeys,
+        InstructionError::Custom(SwapError::IncorrectRateProvider as u32),
+    ]) {
+        let err = context.banks_client
+            .process_transaction(process_swap(swap_ix, context.last_blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, error));
+    }
+
+    // at a rate of one, both pools swap one for one
+    for (swap_ix, user_token_b) in swap_ixs.iter().zip(&user_token_b_accounts) {
+        context.banks_client
+            .process_transaction(process_swap(&swap_ix[2], context.last_blockhash))
+            .await
+            .unwrap();
+        assert_eq!(get_token_balance(&mut context, user_token_b).await, 1_001_000);
+    }
+
+    // a single update of the rate provider reprices both pools
+    set_rates(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        RAY, 
+        rho, 
+        RAY * 5 / 4
+    ).await.unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    for (swap_ix, user_token_b) in swap_ixs.iter().zip(&user_token_b_accounts) {
+        context.banks_client
+            .process_transaction(process_swap(&swap_ix[2], context.last_blockhash))
+            .await
+            .unwrap();
+        assert_eq!(get_token_balance(&mut context, user_token_b).await, 1_001_800);
+    }
+
+    // the conversion rate is read from the rate provider itself
+    // or through a pool using it
+    let mut pool_conversion_rate_ix = get_conversion_rate(
+        &PROGRAM_ID, 
+        &swap_ixs[1][2].accounts[0].pubkey, 
+        GetConversionRate { timestamp: None }
+    ).unwrap();
+    pool_conversion_rate_ix.accounts.push(AccountMeta::new_readonly(rate_provider, false));
+    for ix in [
+        get_conversion_rate(
+            &PROGRAM_ID, 
+            &rate_provider, 
+            GetConversionRate { timestamp: None }
+        ).unwrap(),
+        pool_conversion_rate_ix,
+    ] {
+        let return_data = simulate_return_data(&mut context, ix).await.unwrap();
+        assert_eq!(u128::from_le_bytes(return_data.try_into().unwrap()), RAY * 5 / 4);
+    }
+
+    // the rate provider has the rate update limits of a swap
+    set_rate_update_limits(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        0, 
+        3_600
+    ).await.unwrap();
+    let err = set_rates(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        RAY, 
+        rho, 
+        RAY * 3 / 2
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::RateUpdateTooFrequent as u32)
+        )
+    );
+    set_rate_update_limits(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        0, 
+        0
+    ).await.unwrap();
+
+    // and can be updated by a rate multisig
+    let keeper = Keypair::new();
+    set_rate_multisig(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        1, 
+        &[keeper.pubkey()]
+    ).await.unwrap();
+    set_rates_multisig(
+        &mut context, 
+        &rate_provider, 
+        &[&keeper], 
+        RAY, 
+        rho, 
+        RAY * 3 / 2
+    ).await.unwrap();
+    assert_eq!(
+        fetch_rate_provider(&mut context.banks_client, &rate_provider).await.rates.chi, 
+        RAY * 3 / 2
+    );
+
+    // once it has a timelock delay, max_ssr changes are queued
+    let timelock_delay = 3_600;
+    set_timelock_delay(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        timelock_delay
+    ).await.unwrap();
+    let err = set_max_ssr(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        FIVE_PCT_APY_SSR
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::TimelockRequired as u32)
+        )
+    );
+
+    // changes of swap parameters can't be queued on a rate provider
+    let err = queue_change(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        &ParameterChange::MaxStaleness(60)
+    ).await.unwrap_err().unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidInput as u32)
+        )
+    );
+
+    let max_ssr_queued = queue_change(
+        &mut context, 
+        &rate_provider, 
+        &authority_keypair, 
+        &ParameterChange::MaxSsr(FIVE_PCT_APY_SSR)
+    ).await.unwrap();
+
+    let mut clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp += timelock_delay as i64;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    execute_change(
+        &mut context, 
+        &rate_provider, 
+        &max_ssr_queued, 
+        None
+    ).await.unwrap();
+    assert_eq!(
+        fetch_rate_provider(&mut context.banks_client, &rate_provider).await.rates.max_ssr, 
+        FIVE_PCT_APY_SSR
+    );
+}
+
+#[tokio::test]
+async fn test_relay_signed_rates() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let rho = clock.unix_timestamp as u128;
+
+    // the payer relays rates signed by the keeper
+    let payer = context.payer.insecure_clone();
+    let relay_ixs = |keeper: &Keypair, signed_rates: &RelaySignedRates, rates: RelaySignedRates| {
+        let (relay_nonce, _) = RelayNonce::derive_relay_nonce_pubkey_and_bump(
+            &swap_info, 
+            &keeper.pubkey()
+        );
+        vec![
+            ed25519_instruction(keeper, &signed_rates_message(&swap_info, signed_rates)),
+            relay_signed_rates(
+                &PROGRAM_ID, 
+                &swap_info, 
+                &get_permission_pda(&swap_info, &keeper.pubkey()), 
+                &relay_nonce, 
+                &payer.pubkey(), 
+                rates
+            ).unwrap(),
+        ]
+    };
+    let rates = |ssr, chi, nonce| RelaySignedRates {
+        ssr,
+        rho,
+        chi,
+        nonce,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &relay_ixs(
+            &authority_keypair, 
+            &rates(FIVE_PCT_APY_SSR, RAY, 1), 
+            rates(FIVE_PCT_APY_SSR, RAY, 1)
+        ),
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.ssr, FIVE_PCT_APY_SSR);
+    assert_eq!(curve.rho, rho);
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    let unauthorized_keypair = Keypair::new();
+    for (ixs, error) in [
+        // the same update can't be relayed twice
+        (
+            relay_ixs(
+                &authority_keypair, 
+                &rates(FIVE_PCT_APY_SSR, RAY, 1), 
+                rates(FIVE_PCT_APY_SSR, RAY, 1)
+            ),
+            InstructionError::Custom(SwapError::NonceAlreadyUsed as u32)
+        ),
+        // the relayed rates must be the signed ones
+        (
+            relay_ixs(
+                &authority_keypair, 
+                &rates(FIVE_PCT_APY_SSR, RAY, 2), 
+                rates(ONE_HUNDRED_PCT_APY_SSR, RAY, 2)
+            ),
+            InstructionError::Custom(SwapError::InvalidSignedRates as u32)
+        ),
+        // and signed by a permission with the UPDATE_RATES role
+        (
+            relay_ixs(
+                &unauthorized_keypair, 
+                &rates(FIVE_PCT_APY_SSR, RAY, 2), 
+                rates(FIVE_PCT_APY_SSR, RAY, 2)
+            ),
+            InstructionError::IllegalOwner
+        ),
+        // signed rates are validated like SetRates ones
+        (
+            relay_ixs(
+                &authority_keypair, 
+                &rates(RAY - 1, RAY, 2), 
+                rates(RAY - 1, RAY, 2)
+            ),
+            InstructionError::Custom(SwapError::InvalidSsr as u32)
+        ),
+    ] {
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&payer.pubkey()),
+            &[&payer],
+            context.last_blockhash,
+        );
+        let err = context.banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, TransactionError::InstructionError(1, error));
+    }
+
+    // the Ed25519 verification must come right before
+    let mut ixs = relay_ixs(
+        &authority_keypair, 
+        &rates(FIVE_PCT_APY_SSR, RAY, 2), 
+        rates(FIVE_PCT_APY_SSR, RAY, 2)
+    );
+    ixs.remove(0);
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    let err = context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidSignedRates as u32)
+        )
+    );
+
+    // nonces only have to increase
+    let tx = Transaction::new_signed_with_payer(
+        &relay_ixs(
+            &authority_keypair, 
+            &rates(ONE_HUNDRED_PCT_APY_SSR, RAY, 5), 
+            rates(ONE_HUNDRED_PCT_APY_SSR, RAY, 5)
+        ),
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.ssr, ONE_HUNDRED_PCT_APY_SSR);
+
+    let (relay_nonce, _) = RelayNonce::derive_relay_nonce_pubkey_and_bump(
+        &swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let account = context.banks_client
+        .get_account(relay_nonce)
+        .await
+        .unwrap()
+        .unwrap();
+    let relay_nonce = RelayNonce::unpack(&account.data).unwrap();
+    assert_eq!(relay_nonce.authority, authority_keypair.pubkey());
+    assert_eq!(relay_nonce.nonce, 5);
+}
+
+#[tokio::test]
+async fn test_bridge_set_rates() {
+    let bridge_receiver = Pubkey::new_unique();
+    let other_bridge_receiver = Pubkey::new_unique();
+    let mut context = program_test_context_with_bridge_receivers(
+        &[bridge_receiver, other_bridge_receiver]
+    ).await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        0
+    ).await;
+
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let rho = clock.unix_timestamp as u128;
+
+    // instruction of a bridge receiver, forwarding the rates through CPI
+    let receiver_ix = |bridge_receiver: &Pubkey, ssr| {
+        let (bridge_authority, _) = derive_bridge_authority_pubkey_and_bump(bridge_receiver);
+        let mut ix = bridge_set_rates(
+            &PROGRAM_ID, 
+            &swap_info, 
+            &bridge_authority, 
+            BridgeSetRates {
+                ssr,
+                rho,
+                chi: RAY,
+            }
+        ).unwrap();
+        ix.program_id = *bridge_receiver;
+        ix.accounts[1].is_signer = false;
+        ix.accounts.push(AccountMeta::new_readonly(PROGRAM_ID, false));
+        ix
+    };
+    let bridge_error = TransactionError::InstructionError(
+        0, 
+        InstructionError::Custom(SwapError::InvalidBridgeReceiver as u32)
+    );
+
+    // no bridge receiver is configured yet
+    let err = process_instruction(&mut context, receiver_ix(&bridge_receiver, FIVE_PCT_APY_SSR))
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(err, bridge_error);
+
+    set_bridge_receiver(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &bridge_receiver
+    ).await.unwrap();
+    let pool_config = fetch_pool_config(&mut context, &swap_info).await;
+    assert_eq!(pool_config.bridge_receiver, bridge_receiver);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    process_instruction(&mut context, receiver_ix(&bridge_receiver, FIVE_PCT_APY_SSR))
+        .await
+        .unwrap();
+    let curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        &swap_info
+    ).await;
+    assert_eq!(curve.ssr, FIVE_PCT_APY_SSR);
+
+    // other programs can't update the rates
+    let err = process_instruction(
+        &mut context, 
+        receiver_ix(&other_bridge_receiver, ONE_HUNDRED_PCT_APY_SSR)
+    )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(err, bridge_error);
+
+    // and neither can a transaction instruction to the program
+    let (bridge_authority, _) = derive_bridge_authority_pubkey_and_bump(&bridge_receiver);
+    let mut ix = bridge_set_rates(
+        &PROGRAM_ID, 
+        &swap_info, 
+        &bridge_authority, 
+        BridgeSetRates {
+            ssr: ONE_HUNDRED_PCT_APY_SSR,
+            rho,
+            chi: RAY,
+        }
+    ).unwrap();
+    ix.accounts[1].is_signer = false;
+    let err = process_instruction(&mut context, ix)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(err, bridge_error);
+
+    // bridged rates are validated like SetRates ones
+    let err = process_instruction(&mut context, receiver_ix(&bridge_receiver, RAY - 1))
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::InvalidSsr as u32)
+        )
+    );
+
+    // the bridge receiver is a timelocked change
+    set_timelock_delay(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        86_400
+    ).await.unwrap();
+    let err = set_bridge_receiver(
+        &mut context, 
+        &swap_info, 
+        &authority_keypair, 
+        &other_bridge_receiver
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err, 
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(SwapError::TimelockRequired as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_rpow_performace_with_duration() {
+    let mut context = program_test_context().await;
+    let authority_keypair = Keypair::new();
+    let fee_and_destination_owner = Pubkey::new_unique();
+
+    let (
+        swap_info,
+        authority,
+        token_a_mint,
+        token_b_mint,
+        pool_mint,
+        token_a_account,
+        token_b_account,
+        fee_account,
+        destination_account
+    ) = get_init_curve_setup(
+        &mut context.banks_client,
+        &context.payer,
+        context.last_blockhash,
+        &fee_and_destination_owner
+    ).await;
+
+    create_redemption_rate_curve(
+        &mut context,
+        &swap_info,
+        &authority,
+        &authority_keypair,
+        token_a_account,
+        token_b_account,
+        pool_mint,
+        token_a_mint,
+        token_b_mint,
+        fee_account,
+        destination_account,
+        ONE_HUNDRED_PCT_APY_SSR
+    ).await;
+
+    test_rpow_compute_units_with_growing_duration(
+        &mut context,
+        &swap_info,
+        &authority_keypair
+    ).await
+}
+
+#[tokio::test]
+async fn test_rpow_performance_with_different_max_ssr() {
+    // Define precise APY values
+    const ONE_PCT_APY_SSR: u128 = 1_000_000_000_314_714_530_356_867_391;
+    const FIFTEEN_PCT_APY_SSR: u128 = 1_000_000_004_432_554_513_667_376_032;
+    const TWENTY_PCT_APY_SSR: u128 = 1_000_000_005_860_733_888_492_302_697;
+    const TWENTY_FIVE_PCT_APY_SSR: u128 = 1_000_000_007_264_617_216_271_247_405;
+    
+    // Create a test matrix with different max_ssr values
+    let max_ssr_values = vec![
+        (ONE_PCT_APY_SSR, "1% APY compound"),
+        (FIVE_PCT_APY_SSR, "5% APY compound"),
+        (FIFTEEN_PCT_APY_SSR, "15% APY compound"),
+        (TWENTY_PCT_APY_SSR, "20% APY compound"),
+        (TWENTY_FIVE_PCT_APY_SSR, "25% APY compound"),
+        (ONE_HUNDRED_PCT_APY_SSR, "100% APY compound")
+    ];
+    
+    println!("testing _rpow compute units with different max_ssr values:");
+    
+    for (max_ssr, label) in max_ssr_values {
+        // Create a fresh context for each test
+        let mut context = program_test_context().await;
+        let authority_keypair = Keypair::new();
+        let fee_and_destination_owner = Pubkey::new_unique();
+        
+        // Set up a new curve for this max_ssr value
+        let (
+            swap_info,
+            authority,
+            token_a_mint,
+            token_b_mint,
+            pool_mint,
+            token_a_account,
+            token_b_account,
+            fee_account,
+            destination_account
+        ) = get_init_curve_setup(
+            &mut context.banks_client,
+            &context.payer,
+            context.last_blockhash,
+            &fee_and_destination_owner
+        ).await;
+        
+        // Create redemption rate curve with this specific max_ssr
+        create_redemption_rate_curve(
+            &mut context,
+            &swap_info,
+            &authority,
+            &authority_keypair,
+            token_a_account,
+            token_b_account,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            fee_account,
+            destination_account,
+            max_ssr
+        ).await;
+        
+        println!("\n--- Testing with max_ssr = {} ({}) ---", max_ssr, label);
+        
+        // Test this curve with different durations
+        test_rpow_compute_units_with_growing_duration(
+            &mut context,
+            &swap_info,
+            &authority_keypair
+        ).await;
+    }
+}
+
+
+async fn test_rpow_compute_units_with_growing_duration(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    authority_keypair: &Keypair
+) {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &authority_keypair.pubkey()
+    );
+
+    let initial_curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        swap_info
+    ).await;
+
+    let durations = vec![
+        1,              // 1 second
+        10,             // 10 seconds
+        60,             // 1 minute
+        3600,           // 1 hour
+        86400,          // 1 day
+        604800,         // 1 week
+        2592000,        // 1 month
+        15552000,       // 6 months
+        31536000,       // 1 year
+        315360000,      // 10 years
+        3153600000,      // 100 years, overflows!
+    ];
+    
+    println!("testing _rpow compute units with different durations");
+
+    for duration in durations {
+        let mut clock = Clock::default();
+        clock.unix_timestamp = (initial_curve.rho + duration) as i64;
+        context.set_sysvar(&clock);
+        
+        // For 5% APY
+        let ssr = FIVE_PCT_APY_SSR;
+        let rho = clock.unix_timestamp as u128;
+        let chi = RAY;
+
+        let update_data = vec![
+            vec![6], // update discriminator
+            ssr.to_le_bytes().to_vec(),
+            rho.to_le_bytes().to_vec(),
+            chi.to_le_bytes().to_vec(),
+        ].concat();
+
+        let accounts = vec![
+            AccountMeta::new(*swap_info, false),
+            AccountMeta::new_readonly(permission_account, false),
+            AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        ];
+
+        let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+
+
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: update_data,
+        };
+
+        let tx = Transaction::new_signed_with_payer(
+            &[compute_budget_ix, ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, authority_keypair],
+            context.last_blockhash,
+        );
+
+        let cu_used = get_transaction_simulation_cu_used(context, tx).await.unwrap();
+        println!("Duration: {} seconds, CU used: {}", duration, cu_used);
+    }
+}
+
+
+async fn test_curve_update_valid(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    authority_keypair: &Keypair
+) {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let clock: Clock = context.banks_client.get_sysvar::<Clock>()
+        .await
+        .unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+    ];
+
+    let update_data = vec![
+        // update discriminator
+        vec![6],
+        (2 * RAY).to_le_bytes().to_vec(),
+        (clock.unix_timestamp as u128).to_le_bytes().to_vec(),
+        (2 * RAY).to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data: update_data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority_keypair],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let updated_curve = fetch_redemption_rate_curve(
+        &mut context.banks_client, 
+        swap_info
+    ).await;
+
+    assert_eq!(updated_curve.ssr, 2 * RAY);
+    assert_eq!(updated_curve.chi, 2 * RAY);
+}
+
+async fn test_curve_update_invalid_rho(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    authority_keypair: &Keypair
+) {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &authority_keypair.pubkey()
+    );
+    let clock: Clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+    ];
+
+    let update_data = vec![
+        // update discriminator
+        vec![6],
+        (2 * RAY).to_le_bytes().to_vec(),
+        (clock.unix_timestamp as u128 - 1).to_le_bytes().to_vec(),
+        (2 * RAY).to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data: update_data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority_keypair],
+        context.last_blockhash,
+    );
+
+    let result = context.banks_client
+        .process_transaction(tx)
+        .await;
+
+    assert!(result.is_err());
+}
+
+async fn test_permission_metadata(
+    context: &mut ProgramTestContext,
+    permission_account: &Pubkey,
+    swap_info: &Pubkey,
+    authority_keypair: &Keypair
+) {
+    let permission = fetch_permission(
+        &mut context.banks_client, 
+        permission_account
+    ).await;
+    assert!(permission.has_role(roles::UPDATE_RATES));
+    assert!(permission.has_role(roles::SUPER_ADMIN));
+    assert_eq!(permission.swap, *swap_info);
+    assert_eq!(permission.authority, authority_keypair.pubkey());
+}
+
+async fn test_permission_rejection_unauthorized_update(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    permission_account: &Pubkey
+) {
+    let fake = Keypair::new();
+    let clock: Clock = context.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(*permission_account, false),
+        AccountMeta::new_readonly(fake.pubkey(), true),
+    ];
+
+    let data = vec![
+        // update discriminator
+        vec![6],
+        (2 * RAY).to_le_bytes().to_vec(),
+        (clock.unix_timestamp as u128).to_le_bytes().to_vec(),
+        (2 * RAY).to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &fake],
+        context.last_blockhash,
+    );
+
+    let result = context.banks_client
+        .process_transaction(tx)
+        .await;
+
+    assert!(result.is_err());
+}
+
+async fn test_permission_grant_and_update(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    permission_account: &Pubkey,
+    authority_keypair: &Keypair,
+    transfer_before_creation: bool,
+) {
+    let new_auth = Keypair::new();
+
+    // Create new permission (can update, not super)
+    let new_permission = get_permission_pda(
+        swap_info, 
+        &new_auth.pubkey()
+    );
+
+    // Transfer some lamports to new_permission, before creating it,
+    // making sure it does not cause DoS
+    if transfer_before_creation {
+        let tx = Transaction::new_signed_with_payer(
+            &[transfer(
+                &context.payer.pubkey(), 
+                &new_permission, 
+                1000000
+            )], 
+            Some(&context.payer.pubkey()), 
+            &[&context.payer], 
+            context.last_blockhash
+        );
+
+        context.banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap();
+    }
+
+    let init_data = vec![
+        // init permission discrriminator
+        vec![7],
+        // new authority
+        new_auth.pubkey().to_bytes().to_vec(),
+        // is_super_admin
+        vec![false as u8],
+        // can_update_parameters
+        vec![true as u8],
+    ]
+    .concat();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(*permission_account, false),
+        AccountMeta::new(new_permission, false),
+        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        AccountMeta::new_readonly(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: init_data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority_keypair],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let fetched = fetch_permission(
+        &mut context.banks_client, 
+        &new_permission
+    ).await;
+
+    assert!(fetched.has_role(roles::UPDATE_RATES));
+    assert!(!fetched.has_role(roles::SUPER_ADMIN));
+
+    // Now upgrade to super admin
+    let upgrade_data = vec![
+        // update permission discriminator
+        vec![8],
+        // is_super_admin
+         vec![true as u8], 
+         // can_update_parameters
+         vec![true as u8]
+         ].concat();
+
+    let upgrade_accounts = vec![
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(*permission_account, false),
+        AccountMeta::new(new_permission, false),
+        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+    ];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts: upgrade_accounts,
+            data: upgrade_data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority_keypair],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let upgraded = fetch_permission(
+        &mut context.banks_client, 
+        &new_permission
+    ).await;
+
+    assert!(upgraded.has_role(roles::SUPER_ADMIN));
+}
+
+async fn set_paused(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    is_paused: bool
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    // set paused discriminator, is_paused
+    let data = vec![9, is_paused as u8];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_swap_directions(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    a_to_b_enabled: bool,
+    b_to_a_enabled: bool
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    // set swap directions discriminator, a_to_b_enabled, b_to_a_enabled
+    let data = vec![26, a_to_b_enabled as u8, b_to_a_enabled as u8];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_allowlist_mode(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    liquidity_allowlisted: bool,
+    swaps_allowlisted: bool
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    // set allowlist mode discriminator, liquidity_allowlisted, swaps_allowlisted
+    let data = vec![27, liquidity_allowlisted as u8, swaps_allowlisted as u8];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn add_to_allowlist(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    user: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+    let (allowlist_entry, _) = Pubkey::find_program_address(
+        &[
+            AllowlistEntry::ALLOWLIST_SEED,
+            &swap_info.to_bytes(),
+            &user.to_bytes()
+        ], 
+        &PROGRAM_ID
+    );
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new(allowlist_entry, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+
+    let data = vec![
+        // add to allowlist discriminator
+        vec![28],
+        user.to_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn remove_from_allowlist(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    allowlist_entry: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new(*allowlist_entry, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), false),
+    ];
+
+    // remove from allowlist discriminator
+    let data = vec![29];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_swap_rate_limits(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    a_to_b_max_amount: u64,
+    a_to_b_slope: u64,
+    b_to_a_max_amount: u64,
+    b_to_a_slope: u64
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // set swap rate limits discriminator
+        vec![30],
+        a_to_b_max_amount.to_le_bytes().to_vec(),
+        a_to_b_slope.to_le_bytes().to_vec(),
+        b_to_a_max_amount.to_le_bytes().to_vec(),
+        b_to_a_slope.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_max_tvl(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    max_tvl: u128
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // set max tvl discriminator
+        vec![31],
+        max_tvl.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_max_staleness(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    max_staleness: u64
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // set max staleness discriminator
+        vec![11],
+        max_staleness.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_max_ssr(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    max_ssr: u128
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // set max ssr discriminator
+        vec![12],
+        max_ssr.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn update_fees(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    fees: &Fees
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let mut fees_buf = vec![0; Fees::LEN];
+    fees.pack_into_slice(&mut fees_buf);
+
+    let data = vec![
+        // update fees discriminator
+        vec![13],
+        fees_buf,
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_fee_account(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    fee_account: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new_readonly(*fee_account, false),
+    ];
+
+    // set fee account discriminator
+    let data = vec![24];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn close_pool(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    locked_pool_token: &Pubkey,
+    dest_token_a: &Pubkey,
+    dest_token_b: &Pubkey,
+    recipient: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+    let swap = fetch_swap(context, swap_info).await;
+    let (authority, _) = Pubkey::find_program_address(
+        &[&swap_info.to_bytes()], 
+        &PROGRAM_ID
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(authority, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(*swap.token_a_account(), false),
+        AccountMeta::new(*swap.token_b_account(), false),
+        AccountMeta::new_readonly(*swap.pool_mint(), false),
+        AccountMeta::new_readonly(*locked_pool_token, false),
+        AccountMeta::new(*dest_token_a, false),
+        AccountMeta::new(*dest_token_b, false),
+        AccountMeta::new_readonly(*swap.token_a_mint(), false),
+        AccountMeta::new_readonly(*swap.token_b_mint(), false),
+        AccountMeta::new(*recipient, false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+    ];
+
+    // close pool discriminator
+    let data = vec![25];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn propose_super_admin(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    new_authority: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // propose super admin discriminator
+        vec![14],
+        new_authority.to_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn accept_super_admin(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    proposer: &Pubkey,
+    new_authority: &Keypair
+) -> Result<(), BanksClientError> {
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new(get_permission_pda(swap_info, proposer), false),
+        AccountMeta::new(get_permission_pda(swap_info, &new_authority.pubkey()), false),
+        AccountMeta::new_readonly(new_authority.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            // accept super admin discriminator
+            data: vec![15],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, new_authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn fetch_pool_config(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey
+) -> PoolConfig {
+    let account = context.banks_client
+        .get_account(*swap_info)
+        .await
+        .unwrap()
+        .unwrap();
+
+    SwapVersion::unpack_version(&account.data)
+        .unwrap()
+        .pool_config()
+        .unwrap()
+        .clone()
+}
+
+async fn set_rates(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    ssr: u128,
+    rho: u128,
+    chi: u128
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // update discriminator
+        vec![6],
+        ssr.to_le_bytes().to_vec(),
+        rho.to_le_bytes().to_vec(),
+        chi.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
 
-async fn test_rpow_compute_units_with_growing_duration(
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn set_rate_multisig(
     context: &mut ProgramTestContext,
     swap_info: &Pubkey,
-    authority_keypair: &Keypair
-) {
+    signer: &Keypair,
+    threshold: u8,
+    signers: &[Pubkey]
+) -> Result<Pubkey, BanksClientError> {
     let permission_account = get_permission_pda(
         swap_info, 
-        &authority_keypair.pubkey()
+        &signer.pubkey()
+    );
+    let (multisig, _) = Pubkey::find_program_address(
+        &[
+            RateMultisig::RATE_MULTISIG_SEED,
+            &swap_info.to_bytes()
+        ], 
+        &PROGRAM_ID
     );
 
-    let initial_curve = fetch_redemption_rate_curve(
-        &mut context.banks_client, 
-        swap_info
-    ).await;
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new(multisig, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+    accounts.extend(
+        signers.iter().map(|signer| AccountMeta::new_readonly(*signer, false))
+    );
 
-    let durations = vec![
-        1,              // 1 second
-        10,             // 10 seconds
-        60,             // 1 minute
-        3600,           // 1 hour
-        86400,          // 1 day
-        604800,         // 1 week
-        2592000,        // 1 month
-        15552000,       // 6 months
-        31536000,       // 1 year
-        315360000,      // 10 years
-        3153600000,      // 100 years, overflows!
+    let data = vec![
+        // set rate multisig discriminator
+        23,
+        threshold,
     ];
-    
-    println!("testing _rpow compute units with different durations");
 
-    for duration in durations {
-        let mut clock = Clock::default();
-        clock.unix_timestamp = (initial_curve.rho + duration) as i64;
-        context.set_sysvar(&clock);
-        
-        // For 5% APY
-        let ssr = FIVE_PCT_APY_SSR;
-        let rho = clock.unix_timestamp as u128;
-        let chi = RAY;
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
 
-        let update_data = vec![
-            vec![6], // update discriminator
-            ssr.to_le_bytes().to_vec(),
-            rho.to_le_bytes().to_vec(),
-            chi.to_le_bytes().to_vec(),
-        ].concat();
+    context.banks_client
+        .process_transaction(tx)
+        .await?;
 
-        let accounts = vec![
-            AccountMeta::new(*swap_info, false),
-            AccountMeta::new_readonly(permission_account, false),
-            AccountMeta::new_readonly(authority_keypair.pubkey(), true),
-        ];
+    Ok(multisig)
+}
 
-        let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+async fn set_rates_multisig(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signers: &[&Keypair],
+    ssr: u128,
+    rho: u128,
+    chi: u128
+) -> Result<(), BanksClientError> {
+    let (multisig, _) = Pubkey::find_program_address(
+        &[
+            RateMultisig::RATE_MULTISIG_SEED,
+            &swap_info.to_bytes()
+        ], 
+        &PROGRAM_ID
+    );
 
+    let mut accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(multisig, false),
+    ];
+    accounts.extend(
+        signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true))
+    );
 
-        let ix = Instruction {
+    let data = vec![
+        // update discriminator
+        vec![6],
+        ssr.to_le_bytes().to_vec(),
+        rho.to_le_bytes().to_vec(),
+        chi.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let mut tx_signers = vec![&context.payer];
+    tx_signers.extend_from_slice(signers);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
             program_id: PROGRAM_ID,
             accounts,
-            data: update_data,
-        };
-
-        let tx = Transaction::new_signed_with_payer(
-            &[compute_budget_ix, ix],
-            Some(&context.payer.pubkey()),
-            &[&context.payer, authority_keypair],
-            context.last_blockhash,
-        );
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &tx_signers,
+        context.last_blockhash,
+    );
 
-        let cu_used = get_transaction_simulation_cu_used(context, tx).await.unwrap();
-        println!("Duration: {} seconds, CU used: {}", duration, cu_used);
-    }
+    context.banks_client
+        .process_transaction(tx)
+        .await
 }
 
-
-async fn test_curve_update_valid(
+async fn set_rate_update_limits(
     context: &mut ProgramTestContext,
     swap_info: &Pubkey,
-    authority_keypair: &Keypair
-) {
+    signer: &Keypair,
+    max_ssr_delta: u128,
+    min_update_interval: u64
+) -> Result<(), BanksClientError> {
     let permission_account = get_permission_pda(
         swap_info, 
-        &authority_keypair.pubkey()
+        &signer.pubkey()
     );
-    let clock: Clock = context.banks_client.get_sysvar::<Clock>()
-        .await
-        .unwrap();
 
     let accounts = vec![
         AccountMeta::new(*swap_info, false),
         AccountMeta::new_readonly(permission_account, false),
-        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        AccountMeta::new_readonly(signer.pubkey(), true),
     ];
 
-    let update_data = vec![
-        // update discriminator
-        vec![6],
-        (2 * RAY).to_le_bytes().to_vec(),
-        (clock.unix_timestamp as u128).to_le_bytes().to_vec(),
-        (2 * RAY).to_le_bytes().to_vec(),
+    let data = vec![
+        // set rate update limits discriminator
+        vec![22],
+        max_ssr_delta.to_le_bytes().to_vec(),
+        min_update_interval.to_le_bytes().to_vec(),
     ]
     .concat();
 
-    let ix = Instruction {
-        program_id: PROGRAM_ID,
-        accounts,
-        data: update_data,
-    };
-
     let tx = Transaction::new_signed_with_payer(
-        &[ix],
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
         Some(&context.payer.pubkey()),
-        &[&context.payer, authority_keypair],
+        &[&context.payer, signer],
         context.last_blockhash,
     );
 
-    context.banks_client.process_transaction(tx).await.unwrap();
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
 
-    let updated_curve = fetch_redemption_rate_curve(
-        &mut context.banks_client, 
-        swap_info
-    ).await;
+async fn set_bridge_receiver(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    bridge_receiver: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
 
-    assert_eq!(updated_curve.ssr, 2 * RAY);
-    assert_eq!(updated_curve.chi, 2 * RAY);
+    let accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+    ];
+
+    let data = vec![
+        // set bridge receiver discriminator
+        vec![43],
+        bridge_receiver.to_bytes().to_vec(),
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
 }
 
-async fn test_curve_update_invalid_rho(
+async fn set_timelock_delay(
     context: &mut ProgramTestContext,
     swap_info: &Pubkey,
-    authority_keypair: &Keypair
-) {
+    signer: &Keypair,
+    timelock_delay: u64
+) -> Result<(), BanksClientError> {
     let permission_account = get_permission_pda(
         swap_info, 
-        &authority_keypair.pubkey()
+        &signer.pubkey()
     );
-    let clock: Clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
 
     let accounts = vec![
         AccountMeta::new(*swap_info, false),
         AccountMeta::new_readonly(permission_account, false),
-        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        AccountMeta::new_readonly(signer.pubkey(), true),
     ];
 
-    let update_data = vec![
-        // update discriminator
-        vec![6],
-        (2 * RAY).to_le_bytes().to_vec(),
-        (clock.unix_timestamp as u128 - 1).to_le_bytes().to_vec(),
-        (2 * RAY).to_le_bytes().to_vec(),
+    let data = vec![
+        // set timelock delay discriminator
+        vec![18],
+        timelock_delay.to_le_bytes().to_vec(),
     ]
     .concat();
 
-    let ix = Instruction {
-        program_id: PROGRAM_ID,
-        accounts,
-        data: update_data,
-    };
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+/// Queues `change` paid by the context payer, returning the queued change account
+async fn queue_change(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    change: &ParameterChange
+) -> Result<Pubkey, BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
+    let (queued_change, _) = Pubkey::find_program_address(
+        &[
+            QueuedChange::QUEUED_CHANGE_SEED,
+            &swap_info.to_bytes(),
+            &[change.kind()]
+        ], 
+        &PROGRAM_ID
+    );
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new(queued_change, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+
+    let mut change_buf = vec![0; ParameterChange::LEN];
+    change.pack_into_slice(&mut change_buf);
+
+    let data = vec![
+        // queue change discriminator
+        vec![19],
+        change_buf,
+    ]
+    .concat();
 
     let tx = Transaction::new_signed_with_payer(
-        &[ix],
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data,
+        }],
         Some(&context.payer.pubkey()),
-        &[&context.payer, authority_keypair],
+        &[&context.payer, signer],
         context.last_blockhash,
     );
 
-    let result = context.banks_client
+    context.banks_client
         .process_transaction(tx)
-        .await;
+        .await?;
 
-    assert!(result.is_err());
+    Ok(queued_change)
 }
 
-async fn test_permission_metadata(
+async fn execute_change(
     context: &mut ProgramTestContext,
-    permission_account: &Pubkey,
     swap_info: &Pubkey,
-    authority_keypair: &Keypair
-) {
-    let permission = fetch_permission(
-        &mut context.banks_client, 
-        permission_account
-    ).await;
-    assert!(permission.can_update_parameters);
-    assert!(permission.is_super_admin);
-    assert_eq!(permission.swap, *swap_info);
-    assert_eq!(permission.authority, authority_keypair.pubkey());
+    queued_change: &Pubkey,
+    fee_account: Option<&Pubkey>
+) -> Result<(), BanksClientError> {
+    let mut accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new(*queued_change, false),
+        AccountMeta::new(context.payer.pubkey(), false),
+    ];
+    if let Some(fee_account) = fee_account {
+        accounts.push(AccountMeta::new_readonly(*fee_account, false));
+    }
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            // execute change discriminator
+            data: vec![20],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
 }
 
-async fn test_permission_rejection_unauthorized_update(
+async fn cancel_change(
     context: &mut ProgramTestContext,
     swap_info: &Pubkey,
-    permission_account: &Pubkey
-) {
-    let fake = Keypair::new();
-    let clock: Clock = context.banks_client
-        .get_sysvar::<Clock>()
-        .await
-        .unwrap();
+    signer: &Keypair,
+    queued_change: &Pubkey
+) -> Result<(), BanksClientError> {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &signer.pubkey()
+    );
 
     let accounts = vec![
-        AccountMeta::new(*swap_info, false),
-        AccountMeta::new_readonly(*permission_account, false),
-        AccountMeta::new_readonly(fake.pubkey(), true),
+        AccountMeta::new_readonly(*swap_info, false),
+        AccountMeta::new_readonly(permission_account, false),
+        AccountMeta::new(*queued_change, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), false),
     ];
 
-    let data = vec![
-        // update discriminator
-        vec![6],
-        (2 * RAY).to_le_bytes().to_vec(),
-        (clock.unix_timestamp as u128).to_le_bytes().to_vec(),
-        (2 * RAY).to_le_bytes().to_vec(),
-    ]
-    .concat();
-
-    let ix = Instruction {
-        program_id: PROGRAM_ID,
-        accounts,
-        data,
-    };
-
     let tx = Transaction::new_signed_with_payer(
-        &[ix],
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            // cancel change discriminator
+            data: vec![21],
+        }],
         Some(&context.payer.pubkey()),
-        &[&context.payer, &fake],
+        &[&context.payer, signer],
         context.last_blockhash,
     );
 
-    let result = context.banks_client
+    context.banks_client
         .process_transaction(tx)
-        .await;
+        .await
+}
 
-    assert!(result.is_err());
+async fn fetch_queued_change(
+    context: &mut ProgramTestContext,
+    queued_change: &Pubkey
+) -> QueuedChange {
+    let account = context.banks_client
+        .get_account(*queued_change)
+        .await
+        .unwrap()
+        .unwrap();
+
+    QueuedChange::unpack(&account.data).unwrap()
 }
 
-async fn test_permission_grant_and_update(
+async fn initialize_keeper_permission(
     context: &mut ProgramTestContext,
     swap_info: &Pubkey,
-    permission_account: &Pubkey,
-    authority_keypair: &Keypair,
-    transfer_before_creation: bool,
-) {
-    let new_auth = Keypair::new();
-
-    // Create new permission (can update, not super)
-    let new_permission = get_permission_pda(
+    super_admin: &Keypair,
+    keeper: &Pubkey
+) -> Result<(), BanksClientError> {
+    initialize_permission(
+        context, 
         swap_info, 
-        &new_auth.pubkey()
-    );
-
-    // Transfer some lamports to new_permission, before creating it,
-    // making sure it does not cause DoS
-    if transfer_before_creation {
-        let tx = Transaction::new_signed_with_payer(
-            &[transfer(
-                &context.payer.pubkey(), 
-                &new_permission, 
-                1000000
-            )], 
-            Some(&context.payer.pubkey()), 
-            &[&context.payer], 
-            context.last_blockhash
-        );
-
-        context.banks_client
-            .process_transaction(tx)
-            .await
-            .unwrap();
-    }
+        super_admin, 
+        keeper, 
+        roles::UPDATE_RATES
+    ).await
+}
 
-    let init_data = vec![
-        // init permission discrriminator
+async fn initialize_permission(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    super_admin: &Keypair,
+    authority: &Pubkey,
+    roles: u64
+) -> Result<(), BanksClientError> {
+    let data = vec![
+        // init permission discriminator
         vec![7],
-        // new authority
-        new_auth.pubkey().to_bytes().to_vec(),
-        // is_super_admin
-        vec![false as u8],
-        // can_update_parameters
-        vec![true as u8],
+        authority.to_bytes().to_vec(),
+        roles.to_le_bytes().to_vec(),
     ]
     .concat();
 
     let accounts = vec![
         AccountMeta::new_readonly(*swap_info, false),
-        AccountMeta::new_readonly(*permission_account, false),
-        AccountMeta::new(new_permission, false),
-        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
-        AccountMeta::new_readonly(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(get_permission_pda(swap_info, &super_admin.pubkey()), false),
+        AccountMeta::new(get_permission_pda(swap_info, authority), false),
+        AccountMeta::new_readonly(super_admin.pubkey(), true),
+        AccountMeta::new(context.payer.pubkey(), true),
         AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
     ];
 
@@ -573,65 +6490,142 @@ async fn test_permission_grant_and_update(
         &[Instruction {
             program_id: PROGRAM_ID,
             accounts,
-            data: init_data,
+            data,
         }],
         Some(&context.payer.pubkey()),
-        &[&context.payer, authority_keypair],
+        &[&context.payer, super_admin],
         context.last_blockhash,
     );
 
     context.banks_client
         .process_transaction(tx)
         .await
-        .unwrap();
-
-    let fetched = fetch_permission(
-        &mut context.banks_client, 
-        &new_permission
-    ).await;
-
-    assert!(fetched.can_update_parameters);
-    assert!(!fetched.is_super_admin);
-
-    // Now upgrade to super admin
-    let upgrade_data = vec![
-        // update permission discriminator
-        vec![8],
-        // is_super_admin
-         vec![true as u8], 
-         // can_update_parameters
-         vec![true as u8]
-         ].concat();
+}
 
-    let upgrade_accounts = vec![
+async fn close_permission(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    signer: &Keypair,
+    close_permission_account: &Pubkey,
+    recipient: &Pubkey
+) -> Result<(), BanksClientError> {
+    let accounts = vec![
         AccountMeta::new_readonly(*swap_info, false),
-        AccountMeta::new_readonly(*permission_account, false),
-        AccountMeta::new(new_permission, false),
-        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        AccountMeta::new_readonly(get_permission_pda(swap_info, &signer.pubkey()), false),
+        AccountMeta::new(*close_permission_account, false),
+        AccountMeta::new_readonly(signer.pubkey(), true),
+        AccountMeta::new(*recipient, false),
     ];
 
     let tx = Transaction::new_signed_with_payer(
         &[Instruction {
             program_id: PROGRAM_ID,
-            accounts: upgrade_accounts,
-            data: upgrade_data,
+            accounts,
+            // close permission discriminator
+            data: vec![16],
         }],
         Some(&context.payer.pubkey()),
-        &[&context.payer, authority_keypair],
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+}
+
+async fn process_instruction(
+    context: &mut ProgramTestContext,
+    instruction: Instruction
+) -> Result<(), BanksClientError> {
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
         context.last_blockhash,
     );
 
     context.banks_client
         .process_transaction(tx)
         .await
+}
+
+async fn simulate_return_data(
+    context: &mut ProgramTestContext,
+    instruction: Instruction
+) -> Result<Vec<u8>, TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let simulation = context.banks_client
+        .simulate_transaction(tx)
+        .await
         .unwrap();
+    simulation.result.unwrap()?;
 
-    let upgraded = fetch_permission(
-        &mut context.banks_client, 
-        &new_permission
-    ).await;
+    Ok(simulation.simulation_details
+        .unwrap()
+        .return_data
+        .map(|return_data| return_data.data)
+        .unwrap_or_default())
+}
+
+async fn get_token_balance(
+    context: &mut ProgramTestContext,
+    token_account: &Pubkey
+) -> u64 {
+    let account = context.banks_client
+        .get_account(*token_account)
+        .await
+        .unwrap()
+        .unwrap();
+
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+async fn fetch_swap(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey
+) -> Arc<dyn SwapState> {
+    let account = context.banks_client
+        .get_account(*swap_info)
+        .await
+        .unwrap()
+        .unwrap();
+
+    SwapVersion::unpack(&account.data).unwrap()
+}
+
+/// Rewrites a permission account with the legacy layout,
+/// `flags` being its booleans starting with is_super_admin
+async fn set_legacy_permission(
+    context: &mut ProgramTestContext,
+    permission_account: &Pubkey,
+    flags: &[u8]
+) {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let permission = context.banks_client
+        .get_account(*permission_account)
+        .await
+        .unwrap()
+        .unwrap();
 
-    assert!(upgraded.is_super_admin);
+    let data = [&permission.data[..Permission::LEGACY_LEN - 2], flags].concat();
+    context.set_account(
+        permission_account, 
+        &Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        }.into()
+    );
 }
 
 async fn create_redemption_rate_curve(
@@ -723,4 +6717,113 @@ async fn create_redemption_rate_curve(
     ).await;
 
     assert_eq!(result, curve);
-}
\ No newline at end of file
+}
+
+async fn create_rate_provider_curve(
+    context: &mut ProgramTestContext,
+    swap_info: &Pubkey,
+    authority: &Pubkey,
+    authority_keypair: &Keypair,
+    token_a_account: Pubkey,
+    token_b_account: Pubkey,
+    pool_mint: Pubkey,
+    token_a_mint: Pubkey,
+    token_b_mint: Pubkey,
+    fee_account: Pubkey,
+    destination_account: Pubkey,
+    rate_provider: &Pubkey
+) {
+    let permission_account = get_permission_pda(
+        swap_info, 
+        &authority_keypair.pubkey()
+    );
+
+    let init_accounts = vec![
+        AccountMeta::new(*swap_info, false),
+        AccountMeta::new_readonly(*authority, false),
+        AccountMeta::new_readonly(token_a_account, false),
+        AccountMeta::new_readonly(token_b_account, false),
+        AccountMeta::new(pool_mint, false),
+        AccountMeta::new(token_a_mint, false),
+        AccountMeta::new(token_b_mint, false),
+        AccountMeta::new_readonly(fee_account, false),
+        AccountMeta::new(destination_account, false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new(permission_account, false),
+        AccountMeta::new_readonly(authority_keypair.pubkey(), false),
+        AccountMeta::new(context.payer.pubkey(), true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        AccountMeta::new_readonly(*rate_provider, false),
+    ];
+
+    let fees = Fees::default();
+    let mut fees_buf = vec![0; 64];
+    fees.pack_into_slice(&mut fees_buf);
+
+    // the calculator is padded to the size of the largest curve
+    let mut curve_buf = vec![0; 64];
+    curve_buf[..32].copy_from_slice(&rate_provider.to_bytes());
+
+    let data = vec![
+        // init curve discriminator
+        vec![0],        
+        fees_buf,
+        // RateProviderCurve
+        vec![4],
+        curve_buf,
+    ]
+    .concat();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: PROGRAM_ID,
+            accounts: init_accounts,
+            data,
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap();
+
+    let swap = fetch_swap(context, swap_info).await;
+    assert_eq!(swap.swap_curve().curve_type, CurveType::RateProviderCurve);
+    assert_eq!(swap.swap_curve().calculator.rate_provider(), Some(rate_provider));
+}
+
+/// Ed25519 program instruction verifying the signature of `message`
+/// by `signer`, with the signature, public key and message in its data
+fn ed25519_instruction(
+    signer: &Keypair,
+    message: &[u8]
+) -> Instruction {
+    let public_key_offset: u16 = 16;
+    let signature_offset: u16 = public_key_offset + 32;
+    let message_offset: u16 = signature_offset + 64;
+
+    let data = vec![
+        // one signature and a padding byte
+        vec![1, 0],
+        signature_offset.to_le_bytes().to_vec(),
+        u16::MAX.to_le_bytes().to_vec(),
+        public_key_offset.to_le_bytes().to_vec(),
+        u16::MAX.to_le_bytes().to_vec(),
+        message_offset.to_le_bytes().to_vec(),
+        (message.len() as u16).to_le_bytes().to_vec(),
+        u16::MAX.to_le_bytes().to_vec(),
+        signer.pubkey().to_bytes().to_vec(),
+        signer.sign_message(message).as_ref().to_vec(),
+        message.to_vec(),
+    ]
+    .concat();
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}